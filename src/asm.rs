@@ -0,0 +1,1145 @@
+// src/asm.rs
+//! Assembler for Octo, the de facto CHIP-8 assembly language.
+//!
+//! Supported: labels (`: name`), `:const`, `:alias`, `:macro`, `:byte`,
+//! `:org`, `:call`, `:breakpoint`, every CHIP-8/SUPER-CHIP/XO-CHIP statement,
+//! `if ... then`, `if ... begin ... else ... end`, `loop ... while ... again`
+//! and raw numbers as sprite data. Programs start at `: main`.
+//!
+//! ```
+//! let program = chip8_emu::asm::assemble(": main  v0 := 5  loop again").unwrap();
+//! assert_eq!(program.rom, [0x60, 0x05, 0x12, 0x02]);
+//! ```
+use crate::constants::START_ADDR;
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::fmt;
+use std::str::FromStr;
+
+/// An assembly error with the source line it occurred on.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AsmError {
+    /// 1-based line number.
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for AsmError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for AsmError {}
+
+/// An assembled program.
+#[derive(Debug, Clone)]
+pub struct Program {
+    /// Bytes to load at 0x200 with [`Emu::load_rom`](crate::Emu::load_rom).
+    pub rom: Vec<u8>,
+    pub symbols: SymbolMap,
+}
+
+/// Label and breakpoint addresses of an assembled program.
+///
+/// The text form has one entry per line, `label <addr> <name>` or
+/// `break <addr> <name>`, and is what the debugger reads from `<rom>.sym`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SymbolMap {
+    pub labels: BTreeMap<String, u16>,
+    /// Locations marked with `:breakpoint`.
+    pub breakpoints: BTreeMap<String, u16>,
+}
+
+impl SymbolMap {
+    /// Name of a label defined at `addr`, if any.
+    pub fn label_at(&self, addr: u16) -> Option<&str> {
+        self.labels
+            .iter()
+            .find(|(_, &a)| a == addr)
+            .map(|(name, _)| name.as_str())
+    }
+}
+
+impl fmt::Display for SymbolMap {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (name, addr) in &self.labels {
+            writeln!(f, "label 0x{:03X} {}", addr, name)?;
+        }
+        for (name, addr) in &self.breakpoints {
+            writeln!(f, "break 0x{:03X} {}", addr, name)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for SymbolMap {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut map = SymbolMap::default();
+        for (i, line) in s.lines().enumerate() {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let (kind, addr, name) = match fields.as_slice() {
+                [] => continue,
+                [kind, addr, name] => (*kind, *addr, name.to_string()),
+                _ => return Err(format!("line {}: expected '<kind> <addr> <name>'", i + 1)),
+            };
+            let addr = addr
+                .strip_prefix("0x")
+                .and_then(|hex| u16::from_str_radix(hex, 16).ok())
+                .ok_or_else(|| format!("line {}: invalid address '{}'", i + 1, addr))?;
+            match kind {
+                "label" => map.labels.insert(name, addr),
+                "break" => map.breakpoints.insert(name, addr),
+                _ => return Err(format!("line {}: unknown entry '{}'", i + 1, kind)),
+            };
+        }
+        Ok(map)
+    }
+}
+
+/// Assembles Octo source into a ROM.
+pub fn assemble(source: &str) -> Result<Program, AsmError> {
+    let mut asm = Assembler::new(tokenize(source));
+    asm.run()?;
+    asm.finish()
+}
+
+#[derive(Debug, Clone)]
+struct Token {
+    text: String,
+    line: usize,
+}
+
+// `#` starts a comment that runs to the end of the line
+fn tokenize(source: &str) -> VecDeque<Token> {
+    source
+        .lines()
+        .enumerate()
+        .flat_map(|(i, line)| {
+            let code = line.split('#').next().unwrap_or("");
+            code.split_whitespace().map(move |text| Token {
+                text: text.to_string(),
+                line: i + 1,
+            })
+        })
+        .collect()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Operand {
+    Reg(u16),
+    Byte(u16),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Cond {
+    Eq(u16, Operand),
+    Ne(u16, Operand),
+    Lt(u16, Operand),
+    Gt(u16, Operand),
+    Le(u16, Operand),
+    Ge(u16, Operand),
+    Key(u16),
+    NotKey(u16),
+}
+
+impl Cond {
+    fn negate(self) -> Cond {
+        match self {
+            Cond::Eq(x, o) => Cond::Ne(x, o),
+            Cond::Ne(x, o) => Cond::Eq(x, o),
+            Cond::Lt(x, o) => Cond::Ge(x, o),
+            Cond::Ge(x, o) => Cond::Lt(x, o),
+            Cond::Gt(x, o) => Cond::Le(x, o),
+            Cond::Le(x, o) => Cond::Gt(x, o),
+            Cond::Key(x) => Cond::NotKey(x),
+            Cond::NotKey(x) => Cond::Key(x),
+        }
+    }
+}
+
+// How an unresolved label is patched in once it is defined
+#[derive(Debug, Clone, Copy)]
+enum FixupKind {
+    Addr12, // Low 12 bits of an opcode
+    Addr16, // A full 16-bit word (F000 NNNN)
+}
+
+struct Fixup {
+    offset: usize,
+    label: String,
+    kind: FixupKind,
+    line: usize,
+}
+
+// Open `if ... begin` and `loop` blocks
+enum Block {
+    If {
+        jump: usize,
+        line: usize,
+    },
+    Loop {
+        start: u16,
+        breaks: Vec<usize>,
+        line: usize,
+    },
+}
+
+struct Macro {
+    params: Vec<String>,
+    body: Vec<Token>,
+}
+
+// Guards against macros that expand themselves forever
+const MAX_MACRO_EXPANSIONS: usize = 100_000;
+
+struct Assembler {
+    tokens: VecDeque<Token>,
+    line: usize, // Line of the last token read, for errors
+    rom: Vec<u8>,
+    here: usize, // Address the next byte is written to
+    labels: HashMap<String, u16>,
+    consts: HashMap<String, i64>,
+    aliases: HashMap<String, u16>,
+    macros: HashMap<String, Macro>,
+    expansions: usize,
+    fixups: Vec<Fixup>,
+    blocks: Vec<Block>,
+    breakpoints: BTreeMap<String, u16>,
+}
+
+impl Assembler {
+    fn new(tokens: VecDeque<Token>) -> Self {
+        Assembler {
+            tokens,
+            line: 1,
+            // Like Octo, execution starts with a jump to main; it is dropped
+            // if main turns out to be the very first label
+            rom: vec![0x10, 0x00],
+            here: START_ADDR as usize + 2,
+            labels: HashMap::new(),
+            consts: HashMap::new(),
+            aliases: HashMap::new(),
+            macros: HashMap::new(),
+            expansions: 0,
+            fixups: vec![Fixup {
+                offset: 0,
+                label: "main".to_string(),
+                kind: FixupKind::Addr12,
+                line: 1,
+            }],
+            blocks: Vec::new(),
+            breakpoints: BTreeMap::new(),
+        }
+    }
+
+    fn error<T>(&self, message: impl Into<String>) -> Result<T, AsmError> {
+        Err(AsmError {
+            line: self.line,
+            message: message.into(),
+        })
+    }
+
+    fn next(&mut self) -> Result<String, AsmError> {
+        match self.tokens.pop_front() {
+            Some(token) => {
+                self.line = token.line;
+                Ok(token.text)
+            }
+            None => self.error("unexpected end of input"),
+        }
+    }
+
+    fn peek(&self) -> Option<&str> {
+        self.tokens.front().map(|t| t.text.as_str())
+    }
+
+    fn expect(&mut self, expected: &str) -> Result<(), AsmError> {
+        let token = self.next()?;
+        if token != expected {
+            return self.error(format!("expected '{}', found '{}'", expected, token));
+        }
+        Ok(())
+    }
+
+    fn emit(&mut self, byte: u8) -> Result<(), AsmError> {
+        if self.here > 0xFFFF {
+            return self.error("program does not fit in 64K");
+        }
+        let offset = self.here - START_ADDR as usize;
+        if self.rom.len() <= offset {
+            self.rom.resize(offset + 1, 0);
+        }
+        self.rom[offset] = byte;
+        self.here += 1;
+        Ok(())
+    }
+
+    fn patch(&mut self, offset: usize, addr: u16, kind: FixupKind) {
+        match kind {
+            FixupKind::Addr12 => {
+                self.rom[offset] = (self.rom[offset] & 0xF0) | ((addr >> 8) as u8 & 0x0F);
+                self.rom[offset + 1] = addr as u8;
+            }
+            FixupKind::Addr16 => {
+                self.rom[offset] = (addr >> 8) as u8;
+                self.rom[offset + 1] = addr as u8;
+            }
+        }
+    }
+
+    fn run(&mut self) -> Result<(), AsmError> {
+        while !self.tokens.is_empty() {
+            self.statement()?;
+        }
+        if let Some(block) = self.blocks.last() {
+            self.line = match block {
+                Block::If { line, .. } | Block::Loop { line, .. } => *line,
+            };
+            return match block {
+                Block::If { .. } => self.error("'begin' without matching 'end'"),
+                Block::Loop { .. } => self.error("'loop' without matching 'again'"),
+            };
+        }
+        Ok(())
+    }
+
+    fn finish(mut self) -> Result<Program, AsmError> {
+        for fixup in std::mem::take(&mut self.fixups) {
+            let Some(&addr) = self.labels.get(&fixup.label) else {
+                self.line = fixup.line;
+                return if fixup.label == "main" && fixup.offset == 0 {
+                    self.error("no ': main' label")
+                } else {
+                    self.error(format!("undefined label '{}'", fixup.label))
+                };
+            };
+            if matches!(fixup.kind, FixupKind::Addr12) && addr > 0xFFF {
+                self.line = fixup.line;
+                return self.error(format!(
+                    "label '{}' at 0x{:X} is out of 12-bit range",
+                    fixup.label, addr
+                ));
+            }
+            self.patch(fixup.offset, addr, fixup.kind);
+        }
+
+        Ok(Program {
+            rom: self.rom,
+            symbols: SymbolMap {
+                labels: self.labels.into_iter().collect(),
+                breakpoints: self.breakpoints,
+            },
+        })
+    }
+
+    fn statement(&mut self) -> Result<(), AsmError> {
+        let token = self.next()?;
+        match token.as_str() {
+            ":" => {
+                let name = self.name()?;
+                self.define_label(name)
+            }
+            ":const" => {
+                let name = self.name()?;
+                let value = self.value()?;
+                self.consts.insert(name, value);
+                Ok(())
+            }
+            ":alias" => {
+                let name = self.name()?;
+                let reg = self.register()?;
+                self.aliases.insert(name, reg);
+                Ok(())
+            }
+            ":macro" => self.define_macro(),
+            ":byte" => {
+                let value = self.byte()?;
+                self.emit(value as u8)
+            }
+            ":org" => {
+                let addr = self.value()?;
+                if !(START_ADDR as i64..=0xFFFF).contains(&addr) {
+                    return self.error(format!("origin 0x{:X} is outside the program", addr));
+                }
+                self.here = addr as usize;
+                Ok(())
+            }
+            ":call" => self.address_op(0x2000),
+            ":breakpoint" => {
+                let name = self.name()?;
+                self.breakpoints.insert(name, self.here as u16);
+                Ok(())
+            }
+            "clear" => self.op(0x00E0),
+            "return" | ";" => self.op(0x00EE),
+            "exit" => self.op(0x00FD),
+            "lores" => self.op(0x00FE),
+            "hires" => self.op(0x00FF),
+            "scroll-down" => {
+                let n = self.nibble()?;
+                self.op(0x00C0 | n)
+            }
+            "scroll-up" => {
+                let n = self.nibble()?;
+                self.op(0x00D0 | n)
+            }
+            "scroll-right" => self.op(0x00FB),
+            "scroll-left" => self.op(0x00FC),
+            "audio" => self.op(0xF002),
+            "plane" => {
+                let n = self.nibble()?;
+                if n > 3 {
+                    return self.error("plane must be 0-3");
+                }
+                self.op(0xF001 | n << 8)
+            }
+            "jump" => self.address_op(0x1000),
+            "jump0" => self.address_op(0xB000),
+            "bcd" => self.reg_op(0xF033),
+            "saveflags" => self.reg_op(0xF075),
+            "loadflags" => self.reg_op(0xF085),
+            "save" | "load" => {
+                let x = self.register()?;
+                if self.peek() == Some("-") {
+                    self.next()?;
+                    let y = self.register()?;
+                    let base = if token == "save" { 0x5002 } else { 0x5003 };
+                    self.op(base | x << 8 | y << 4)
+                } else {
+                    let base = if token == "save" { 0xF055 } else { 0xF065 };
+                    self.op(base | x << 8)
+                }
+            }
+            "sprite" => {
+                let x = self.register()?;
+                let y = self.register()?;
+                let n = self.nibble()?;
+                self.op(0xD000 | x << 8 | y << 4 | n)
+            }
+            "delay" | "buzzer" | "pitch" => {
+                self.expect(":=")?;
+                let x = self.register()?;
+                let base = match token.as_str() {
+                    "delay" => 0xF015,
+                    "buzzer" => 0xF018,
+                    _ => 0xF03A,
+                };
+                self.op(base | x << 8)
+            }
+            "i" => self.index_statement(),
+            "if" => self.if_statement(),
+            "else" => match self.blocks.pop() {
+                Some(Block::If { jump, .. }) => {
+                    let line = self.line;
+                    let end_jump = self.here - START_ADDR as usize;
+                    self.op(0x1000)?;
+                    self.patch_jump(jump)?;
+                    self.blocks.push(Block::If {
+                        jump: end_jump,
+                        line,
+                    });
+                    Ok(())
+                }
+                _ => self.error("'else' without 'if ... begin'"),
+            },
+            "end" => match self.blocks.pop() {
+                Some(Block::If { jump, .. }) => self.patch_jump(jump),
+                _ => self.error("'end' without 'if ... begin'"),
+            },
+            "loop" => {
+                self.blocks.push(Block::Loop {
+                    start: self.here as u16,
+                    breaks: Vec::new(),
+                    line: self.line,
+                });
+                Ok(())
+            }
+            "while" => {
+                let cond = self.condition()?;
+                // Leave the loop unless the condition holds
+                self.skip_unless(cond.negate())?;
+                let jump = self.here - START_ADDR as usize;
+                self.op(0x1000)?;
+                let in_loop = self.blocks.iter_mut().rev().find_map(|block| match block {
+                    Block::Loop { breaks, .. } => Some(breaks),
+                    Block::If { .. } => None,
+                });
+                match in_loop {
+                    Some(breaks) => {
+                        breaks.push(jump);
+                        Ok(())
+                    }
+                    None => self.error("'while' outside of a loop"),
+                }
+            }
+            "again" => match self.blocks.pop() {
+                Some(Block::Loop { start, breaks, .. }) => {
+                    self.check_addr12(start as i64)?;
+                    self.op(0x1000 | start)?;
+                    for jump in breaks {
+                        self.patch_jump(jump)?;
+                    }
+                    Ok(())
+                }
+                _ => self.error("'again' without 'loop'"),
+            },
+            _ => {
+                if let Some(x) = self.register_name(&token) {
+                    return self.register_statement(x);
+                }
+                if let Some(value) = self.literal(&token) {
+                    return self.data_byte(value);
+                }
+                if self.macros.contains_key(&token) {
+                    return self.expand_macro(&token);
+                }
+                if token.starts_with(':') {
+                    return self.error(format!("unsupported directive '{}'", token));
+                }
+                // Any other name calls a subroutine, possibly defined later
+                self.check_name(&token)?;
+                self.address_token_op(0x2000, token)
+            }
+        }
+    }
+
+    fn define_label(&mut self, name: String) -> Result<(), AsmError> {
+        if self.labels.contains_key(&name) {
+            return self.error(format!("label '{}' is already defined", name));
+        }
+        // main straight after the entry jump: the jump is not needed
+        if name == "main" && self.here == START_ADDR as usize + 2 && self.rom.len() == 2 {
+            self.rom.clear();
+            self.here = START_ADDR as usize;
+            self.fixups.retain(|f| f.offset != 0);
+        }
+        self.labels.insert(name, self.here as u16);
+        Ok(())
+    }
+
+    fn define_macro(&mut self) -> Result<(), AsmError> {
+        let name = self.name()?;
+        let mut params = Vec::new();
+        loop {
+            let token = self.next()?;
+            if token == "{" {
+                break;
+            }
+            self.check_name(&token)?;
+            params.push(token);
+        }
+
+        let mut body = Vec::new();
+        let mut depth = 1;
+        loop {
+            let Some(token) = self.tokens.pop_front() else {
+                return self.error(format!("macro '{}' is missing '}}'", name));
+            };
+            match token.text.as_str() {
+                "{" => depth += 1,
+                "}" => {
+                    depth -= 1;
+                    if depth == 0 {
+                        break;
+                    }
+                }
+                _ => {}
+            }
+            body.push(token);
+        }
+        self.macros.insert(name, Macro { params, body });
+        Ok(())
+    }
+
+    fn expand_macro(&mut self, name: &str) -> Result<(), AsmError> {
+        self.expansions += 1;
+        if self.expansions > MAX_MACRO_EXPANSIONS {
+            return self.error("too many macro expansions (recursive macro?)");
+        }
+
+        let params = self.macros[name].params.clone();
+        let mut args = HashMap::new();
+        for param in params {
+            let arg = self.next()?;
+            args.insert(param, arg);
+        }
+
+        // Expanded tokens keep their own lines so errors point into the macro
+        let body: Vec<Token> = self.macros[name]
+            .body
+            .iter()
+            .map(|token| Token {
+                text: args.get(&token.text).unwrap_or(&token.text).clone(),
+                line: token.line,
+            })
+            .collect();
+        for token in body.into_iter().rev() {
+            self.tokens.push_front(token);
+        }
+        Ok(())
+    }
+
+    fn index_statement(&mut self) -> Result<(), AsmError> {
+        let op = self.next()?;
+        match op.as_str() {
+            ":=" => match self.peek() {
+                Some("hex") => {
+                    self.next()?;
+                    self.reg_op(0xF029)
+                }
+                Some("bighex") => {
+                    self.next()?;
+                    self.reg_op(0xF030)
+                }
+                Some("long") => {
+                    self.next()?;
+                    let token = self.next()?;
+                    self.op(0xF000)?;
+                    match self.literal(&token) {
+                        Some(value) => {
+                            if !(0..=0xFFFF).contains(&value) {
+                                return self.error(format!("address {} is out of range", value));
+                            }
+                            self.op(value as u16)?;
+                        }
+                        None => {
+                            self.check_name(&token)?;
+                            self.reference(token, FixupKind::Addr16);
+                            self.op(0)?;
+                        }
+                    }
+                    Ok(())
+                }
+                _ => self.address_op(0xA000),
+            },
+            "+=" => self.reg_op(0xF01E),
+            _ => self.error(format!("unknown operator 'i {}'", op)),
+        }
+    }
+
+    fn register_statement(&mut self, x: u16) -> Result<(), AsmError> {
+        let op = self.next()?;
+        match op.as_str() {
+            ":=" => match self.peek() {
+                Some("random") => {
+                    self.next()?;
+                    let nn = self.byte()?;
+                    self.op(0xC000 | x << 8 | nn)
+                }
+                Some("key") => {
+                    self.next()?;
+                    self.op(0xF00A | x << 8)
+                }
+                Some("delay") => {
+                    self.next()?;
+                    self.op(0xF007 | x << 8)
+                }
+                _ => match self.operand()? {
+                    Operand::Reg(y) => self.op(0x8000 | x << 8 | y << 4),
+                    Operand::Byte(nn) => self.op(0x6000 | x << 8 | nn),
+                },
+            },
+            "+=" => match self.operand()? {
+                Operand::Reg(y) => self.op(0x8004 | x << 8 | y << 4),
+                Operand::Byte(nn) => self.op(0x7000 | x << 8 | nn),
+            },
+            "-=" => match self.operand()? {
+                Operand::Reg(y) => self.op(0x8005 | x << 8 | y << 4),
+                // Adding the two's complement subtracts a constant
+                Operand::Byte(nn) => self.op(0x7000 | x << 8 | (nn.wrapping_neg() & 0xFF)),
+            },
+            "=-" | "|=" | "&=" | "^=" | ">>=" | "<<=" => {
+                let y = self.register()?;
+                let n = match op.as_str() {
+                    "=-" => 0x7,
+                    "|=" => 0x1,
+                    "&=" => 0x2,
+                    "^=" => 0x3,
+                    ">>=" => 0x6,
+                    _ => 0xE,
+                };
+                self.op(0x8000 | x << 8 | y << 4 | n)
+            }
+            _ => self.error(format!("unknown operator '{}'", op)),
+        }
+    }
+
+    fn if_statement(&mut self) -> Result<(), AsmError> {
+        let cond = self.condition()?;
+        let line = self.line;
+        match self.next()?.as_str() {
+            "then" => self.skip_unless(cond),
+            "begin" => {
+                // Jump over the block when the condition is false
+                self.skip_unless(cond.negate())?;
+                let jump = self.here - START_ADDR as usize;
+                self.op(0x1000)?;
+                self.blocks.push(Block::If { jump, line });
+                Ok(())
+            }
+            other => self.error(format!("expected 'then' or 'begin', found '{}'", other)),
+        }
+    }
+
+    fn condition(&mut self) -> Result<Cond, AsmError> {
+        let x = self.register()?;
+        let op = self.next()?;
+        let cond = match op.as_str() {
+            "key" => Cond::Key(x),
+            "-key" => Cond::NotKey(x),
+            "==" => Cond::Eq(x, self.operand()?),
+            "!=" => Cond::Ne(x, self.operand()?),
+            "<" => Cond::Lt(x, self.operand()?),
+            ">" => Cond::Gt(x, self.operand()?),
+            "<=" => Cond::Le(x, self.operand()?),
+            ">=" => Cond::Ge(x, self.operand()?),
+            _ => return self.error(format!("unknown comparison '{}'", op)),
+        };
+        Ok(cond)
+    }
+
+    // Emits code after which the next instruction runs only if `cond` holds.
+    // Ordered comparisons use VF as a scratch register, as Octo does.
+    fn skip_unless(&mut self, cond: Cond) -> Result<(), AsmError> {
+        match cond {
+            Cond::Eq(x, Operand::Byte(nn)) => self.op(0x4000 | x << 8 | nn),
+            Cond::Eq(x, Operand::Reg(y)) => self.op(0x9000 | x << 8 | y << 4),
+            Cond::Ne(x, Operand::Byte(nn)) => self.op(0x3000 | x << 8 | nn),
+            Cond::Ne(x, Operand::Reg(y)) => self.op(0x5000 | x << 8 | y << 4),
+            Cond::Key(x) => self.op(0xE0A1 | x << 8),
+            Cond::NotKey(x) => self.op(0xE09E | x << 8),
+            Cond::Lt(x, o) | Cond::Ge(x, o) => {
+                // VF = 1 when x >= operand
+                match o {
+                    Operand::Reg(y) => {
+                        self.op(0x8F00 | x << 4)?;
+                        self.op(0x8F05 | y << 4)?;
+                    }
+                    Operand::Byte(nn) => {
+                        self.op(0x6F00 | nn)?;
+                        self.op(0x8F07 | x << 4)?;
+                    }
+                }
+                let skip_if = if matches!(cond, Cond::Lt(..)) {
+                    0x4F00
+                } else {
+                    0x3F00
+                };
+                self.op(skip_if)
+            }
+            Cond::Gt(x, o) | Cond::Le(x, o) => {
+                // VF = 1 when operand >= x
+                match o {
+                    Operand::Reg(y) => {
+                        self.op(0x8F00 | y << 4)?;
+                        self.op(0x8F05 | x << 4)?;
+                    }
+                    Operand::Byte(nn) => {
+                        self.op(0x6F00 | nn)?;
+                        self.op(0x8F05 | x << 4)?;
+                    }
+                }
+                let skip_if = if matches!(cond, Cond::Gt(..)) {
+                    0x4F00
+                } else {
+                    0x3F00
+                };
+                self.op(skip_if)
+            }
+        }
+    }
+
+    fn patch_jump(&mut self, offset: usize) -> Result<(), AsmError> {
+        self.check_addr12(self.here as i64)?;
+        self.patch(offset, self.here as u16, FixupKind::Addr12);
+        Ok(())
+    }
+
+    fn op(&mut self, op: u16) -> Result<(), AsmError> {
+        self.emit((op >> 8) as u8)?;
+        self.emit(op as u8)
+    }
+
+    fn reg_op(&mut self, base: u16) -> Result<(), AsmError> {
+        let x = self.register()?;
+        self.op(base | x << 8)
+    }
+
+    // An opcode with a 12-bit address operand: a number, constant or label
+    fn address_op(&mut self, base: u16) -> Result<(), AsmError> {
+        let token = self.next()?;
+        self.address_token_op(base, token)
+    }
+
+    fn address_token_op(&mut self, base: u16, token: String) -> Result<(), AsmError> {
+        if let Some(value) = self.literal(&token) {
+            self.check_addr12(value)?;
+            return self.op(base | value as u16);
+        }
+        self.check_name(&token)?;
+        match self.labels.get(&token) {
+            Some(&addr) => {
+                self.check_addr12(addr as i64)?;
+                self.op(base | addr)
+            }
+            None => {
+                self.reference(token, FixupKind::Addr12);
+                self.op(base)
+            }
+        }
+    }
+
+    fn reference(&mut self, label: String, kind: FixupKind) {
+        self.fixups.push(Fixup {
+            offset: self.here - START_ADDR as usize,
+            label,
+            kind,
+            line: self.line,
+        });
+    }
+
+    fn data_byte(&mut self, value: i64) -> Result<(), AsmError> {
+        if !(-128..=255).contains(&value) {
+            return self.error(format!("data byte {} is out of range", value));
+        }
+        self.emit(value as u8)
+    }
+
+    fn check_addr12(&self, value: i64) -> Result<(), AsmError> {
+        if !(0..=0xFFF).contains(&value) {
+            return self.error(format!("address 0x{:X} is out of 12-bit range", value));
+        }
+        Ok(())
+    }
+
+    fn check_name(&self, name: &str) -> Result<(), AsmError> {
+        let valid = name
+            .chars()
+            .next()
+            .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+            && name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+        if !valid {
+            return self.error(format!("invalid name '{}'", name));
+        }
+        Ok(())
+    }
+
+    fn name(&mut self) -> Result<String, AsmError> {
+        let name = self.next()?;
+        self.check_name(&name)?;
+        Ok(name)
+    }
+
+    fn register_name(&self, token: &str) -> Option<u16> {
+        if let Some(&reg) = self.aliases.get(token) {
+            return Some(reg);
+        }
+        let digit = token
+            .strip_prefix('v')
+            .or_else(|| token.strip_prefix('V'))?;
+        if digit.len() != 1 {
+            return None;
+        }
+        u16::from_str_radix(digit, 16).ok()
+    }
+
+    fn register(&mut self) -> Result<u16, AsmError> {
+        let token = self.next()?;
+        match self.register_name(&token) {
+            Some(reg) => Ok(reg),
+            None => self.error(format!("expected a register, found '{}'", token)),
+        }
+    }
+
+    fn operand(&mut self) -> Result<Operand, AsmError> {
+        let token = self.next()?;
+        if let Some(reg) = self.register_name(&token) {
+            return Ok(Operand::Reg(reg));
+        }
+        self.byte_value(&token).map(Operand::Byte)
+    }
+
+    // A number or a constant
+    fn literal(&self, token: &str) -> Option<i64> {
+        if let Some(&value) = self.consts.get(token) {
+            return Some(value);
+        }
+        let (negative, digits) = match token.strip_prefix('-') {
+            Some(rest) => (true, rest),
+            None => (false, token),
+        };
+        let value = if let Some(hex) = digits.strip_prefix("0x") {
+            i64::from_str_radix(hex, 16).ok()?
+        } else if let Some(bin) = digits.strip_prefix("0b") {
+            i64::from_str_radix(bin, 2).ok()?
+        } else if digits.starts_with(|c: char| c.is_ascii_digit()) {
+            digits.parse().ok()?
+        } else {
+            return None;
+        };
+        Some(if negative { -value } else { value })
+    }
+
+    // A number, constant or already defined label
+    fn value(&mut self) -> Result<i64, AsmError> {
+        let token = self.next()?;
+        if let Some(value) = self.literal(&token) {
+            return Ok(value);
+        }
+        match self.labels.get(&token) {
+            Some(&addr) => Ok(addr as i64),
+            None => self.error(format!("expected a number, found '{}'", token)),
+        }
+    }
+
+    fn byte_value(&self, token: &str) -> Result<u16, AsmError> {
+        match self.literal(token) {
+            Some(value) if (-128..=255).contains(&value) => Ok(value as u16 & 0xFF),
+            Some(value) => self.error(format!("{} does not fit in a byte", value)),
+            None => self.error(format!("expected a number, found '{}'", token)),
+        }
+    }
+
+    fn byte(&mut self) -> Result<u16, AsmError> {
+        let token = self.next()?;
+        self.byte_value(&token)
+    }
+
+    fn nibble(&mut self) -> Result<u16, AsmError> {
+        let token = self.next()?;
+        match self.literal(&token) {
+            Some(value) if (0..=15).contains(&value) => Ok(value as u16),
+            Some(value) => self.error(format!("{} does not fit in 4 bits", value)),
+            None => self.error(format!("expected a number, found '{}'", token)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rom(source: &str) -> Vec<u8> {
+        match assemble(source) {
+            Ok(program) => program.rom,
+            Err(e) => panic!("{}", e),
+        }
+    }
+
+    fn error(source: &str) -> (usize, String) {
+        let e = assemble(source).unwrap_err();
+        (e.line, e.message)
+    }
+
+    #[test]
+    fn entry_jump_is_kept_when_main_comes_later() {
+        assert_eq!(rom(": main loop again"), [0x12, 0x00]);
+        assert_eq!(
+            rom(": data 0x11\n: main loop again"),
+            [0x12, 0x03, 0x11, 0x12, 0x03]
+        );
+    }
+
+    #[test]
+    fn forward_and_backward_labels() {
+        let source = "
+: main
+    jump later
+: back
+    return
+: later
+    back
+    i := back
+";
+        assert_eq!(
+            rom(source),
+            [0x12, 0x04, 0x00, 0xEE, 0x22, 0x02, 0xA2, 0x02]
+        );
+    }
+
+    #[test]
+    fn long_index_takes_a_16_bit_label() {
+        let source = "
+: main
+    i := long data
+    loop again
+:org 0x1234
+: data
+    0xAA
+";
+        let rom = rom(source);
+        assert_eq!(rom[..6], [0xF0, 0x00, 0x12, 0x34, 0x12, 0x04]);
+        assert_eq!(rom[0x1234 - 0x200], 0xAA);
+    }
+
+    #[test]
+    fn constants_and_aliases() {
+        let source = "
+:const SPEED 7
+:alias x v3
+: main
+    x := SPEED
+    x += 1
+    x -= 2
+    x := vA
+";
+        assert_eq!(
+            rom(source),
+            [0x63, 0x07, 0x73, 0x01, 0x73, 0xFE, 0x83, 0xA0]
+        );
+    }
+
+    #[test]
+    fn skip_comparisons() {
+        let cases: &[(&str, &[u8])] = &[
+            ("if v1 == 5 then", &[0x41, 0x05]),
+            ("if v1 != 5 then", &[0x31, 0x05]),
+            ("if v1 == v2 then", &[0x91, 0x20]),
+            ("if v1 != v2 then", &[0x51, 0x20]),
+            ("if v1 key then", &[0xE1, 0xA1]),
+            ("if v1 -key then", &[0xE1, 0x9E]),
+            // Ordered comparisons go through vF
+            ("if v1 < 3 then", &[0x6F, 0x03, 0x8F, 0x17, 0x4F, 0x00]),
+            ("if v1 >= 3 then", &[0x6F, 0x03, 0x8F, 0x17, 0x3F, 0x00]),
+            ("if v1 > v2 then", &[0x8F, 0x20, 0x8F, 0x15, 0x4F, 0x00]),
+            ("if v1 <= v2 then", &[0x8F, 0x20, 0x8F, 0x15, 0x3F, 0x00]),
+        ];
+        for &(condition, expected) in cases {
+            let source = format!(": main {} v2 := 1", condition);
+            let mut expected = expected.to_vec();
+            expected.extend([0x62, 0x01]);
+            assert_eq!(rom(&source), expected, "{}", condition);
+        }
+    }
+
+    #[test]
+    fn blocks_and_loops() {
+        assert_eq!(
+            rom(": main loop v0 += 1 while v0 != 10 again"),
+            [0x70, 0x01, 0x40, 0x0A, 0x12, 0x08, 0x12, 0x00]
+        );
+        assert_eq!(
+            rom(": main if v0 == 1 begin v1 := 1 else v1 := 2 end"),
+            [0x30, 0x01, 0x12, 0x08, 0x61, 0x01, 0x12, 0x0A, 0x61, 0x02]
+        );
+    }
+
+    #[test]
+    fn macros_substitute_their_arguments() {
+        let source = "
+:macro set reg value { reg := value }
+: main
+    set v1 2
+    set v4 0x10
+";
+        assert_eq!(rom(source), [0x61, 0x02, 0x64, 0x10]);
+    }
+
+    #[test]
+    fn symbol_map_lists_labels_and_breakpoints() {
+        let source = "
+: main
+    v0 := 1
+:breakpoint hit
+: sub
+    return
+";
+        let symbols = assemble(source).unwrap().symbols;
+        assert_eq!(symbols.labels["main"], 0x200);
+        assert_eq!(symbols.labels["sub"], 0x202);
+        assert_eq!(symbols.breakpoints["hit"], 0x202);
+        assert_eq!(symbols.label_at(0x202), Some("sub"));
+        assert_eq!(symbols.label_at(0x201), None);
+
+        let text = symbols.to_string();
+        assert_eq!(text, "label 0x200 main\nlabel 0x202 sub\nbreak 0x202 hit\n");
+        assert_eq!(text.parse::<SymbolMap>(), Ok(symbols));
+    }
+
+    #[test]
+    fn malformed_symbol_maps_are_rejected() {
+        assert!("label 0x200".parse::<SymbolMap>().is_err());
+        assert!("label 200 main".parse::<SymbolMap>().is_err());
+        assert!("watch 0x200 main".parse::<SymbolMap>().is_err());
+        assert_eq!("\n\n".parse::<SymbolMap>(), Ok(SymbolMap::default()));
+    }
+
+    #[test]
+    fn errors_point_at_their_line() {
+        let cases: &[(&str, usize, &str)] = &[
+            ("v0 := 1", 1, "no ': main' label"),
+            (
+                ": main\n  v0 := 1\n  nowhere",
+                3,
+                "undefined label 'nowhere'",
+            ),
+            (": main\n: main", 2, "label 'main' is already defined"),
+            (": main\n\n  v0 ** 2", 3, "unknown operator '**'"),
+            (": main\n  i -= v0", 2, "unknown operator 'i -='"),
+            (
+                ": main\n  loop\n  v0 := 1",
+                2,
+                "'loop' without matching 'again'",
+            ),
+            (
+                ": main\n  if v0 == 1 begin",
+                2,
+                "'begin' without matching 'end'",
+            ),
+            (": main\n  end", 2, "'end' without 'if ... begin'"),
+            (": main\n  while v0 == 1", 2, "'while' outside of a loop"),
+            (": main\n  if v0 ~ 1 then", 2, "unknown comparison '~'"),
+            (
+                ": main\n  if v0 == 1 v1 := 2",
+                2,
+                "expected 'then' or 'begin', found 'v1'",
+            ),
+            (": main\n  300", 2, "data byte 300 is out of range"),
+            (": main\n  plane 4", 2, "plane must be 0-3"),
+            (": main\n  9lives", 2, "invalid name '9lives'"),
+            (
+                ": main\n  :include x",
+                2,
+                "unsupported directive ':include'",
+            ),
+            (
+                ": main\n  jump 0x1000",
+                2,
+                "address 0x1000 is out of 12-bit range",
+            ),
+            (":org 0x100", 1, "origin 0x100 is outside the program"),
+            (": main\n  sprite v0 v1", 2, "unexpected end of input"),
+            (":macro m {\n  v0 := 1", 1, "macro 'm' is missing '}'"),
+        ];
+        for &(source, line, message) in cases {
+            assert_eq!(error(source), (line, message.to_string()), "{:?}", source);
+        }
+    }
+
+    #[test]
+    fn labels_past_4k_need_long_index() {
+        let source = "
+: main
+    jump far
+:org 0x1000
+: far
+    return
+";
+        assert_eq!(
+            error(source),
+            (
+                3,
+                "label 'far' at 0x1000 is out of 12-bit range".to_string()
+            )
+        );
+    }
+
+    #[test]
+    fn comments_are_ignored() {
+        assert_eq!(rom("# start\n: main # here\n  v0 := 1 # one"), [0x60, 0x01]);
+    }
+}
@@ -5,12 +5,26 @@ pub struct SquareWave {
     pub phase_inc: f32,
     pub phase: f32,
     pub volume: f32,
+    // XO-CHIP: 128 one-bit samples replacing the fixed square wave when set
+    pub pattern: Option<[u8; 16]>,
+    pub pattern_inc: f32, // Pattern bits advanced per output sample
+    pub pattern_pos: f32,
 }
 
 impl AudioCallback for SquareWave {
     type Channel = f32;
 
     fn callback(&mut self, out: &mut [f32]) {
+        if let Some(pattern) = self.pattern {
+            for x in out.iter_mut() {
+                let bit = self.pattern_pos as usize;
+                let high = (pattern[bit / 8] >> (7 - bit % 8)) & 1 != 0;
+                *x = if high { self.volume } else { -self.volume };
+                self.pattern_pos = (self.pattern_pos + self.pattern_inc) % 128.0;
+            }
+            return;
+        }
+
         // Kare dalga (Square Wave) üretimi
         for x in out.iter_mut() {
             *x = if self.phase <= 0.5 {
@@ -21,4 +35,4 @@ impl AudioCallback for SquareWave {
             self.phase = (self.phase + self.phase_inc) % 1.0;
         }
     }
-}
\ No newline at end of file
+}
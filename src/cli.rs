@@ -0,0 +1,233 @@
+// src/cli.rs
+use crate::gui::palette::Palette;
+use crate::speed::FastForward;
+use chip8_emu::headless::Until;
+use chip8_emu::record::VideoFormat;
+use chip8_emu::rng::RngMode;
+use chip8_emu::timing::Timing;
+use chip8_emu::trace::{self, TraceFormat};
+use chip8_emu::QuirksProfile;
+use std::ops::RangeInclusive;
+
+pub const USAGE: &str = "\
+Usage: chip8_emu [OPTIONS] [ROM]
+       chip8_emu asm <SOURCE> [-o <ROM>]
+
+Without a ROM the graphical ROM selector is shown. `asm` assembles Octo
+source into ROM (default: SOURCE with a .ch8 extension) and writes the symbol
+map next to it as a .sym file.
+
+Options:
+  --quirks <PROFILE>   Opcode behaviour: vip, chip48, schip, xochip, modern
+  --seed <N>           Seed for the CXNN random generator (default: random)
+  --rng <MODE>         Random generator: xorshift (default)
+  --ips <N>            Instructions per second (default 600, or the settings)
+  --vip-timing         COSMAC VIP cycle timing instead of a fixed --ips
+  --fast-forward <N>   Speed while Tab is held: a factor up to 64 (default 4) or max
+  --palette <NAME>     Display colours: classic, theme, green, amber, gameboy
+  --debug              Run the ROM in the debugger window
+  --disassemble <ROM>  Print a disassembly listing of ROM and exit
+  --trace <FILE>       Log every executed instruction to FILE (F9 toggles)
+  --trace-range <A-B>  Only trace addresses A to B, e.g. 0x200-0x2FF
+  --trace-format <F>   Trace lines: delta (changed registers, default) or full
+  --screenshot-dir <D> Folder for F12 screenshots (default: config folder)
+  --screenshot-scale <N>
+                       Screenshot pixels per CHIP-8 pixel (default 15, 1 = native)
+  --record             Start recording right away (F10 toggles)
+  --record-format <F>  Recording format: gif (default) or y4m
+  --record-scale <N>   Recording pixels per CHIP-8 pixel (default 4)
+  --record-wav         Also record the sound to a .wav next to the video
+  --record-dir <D>     Folder for recordings (default: config folder)
+  --record-movie <FILE>
+                       Record the keys of every frame to an input movie (.c8m)
+  --play <FILE>        Replay an input movie; it sets quirks, RNG, seed and speed
+  --verify             With --play: replay headless and check the final screen
+  --headless           Run without a window (for CI); uses seed 0 unless --seed
+  --frames <N>         Headless: frames to run (default 600)
+  --until <COND>       Headless: stop early on exit, halt or pc=0xNNN
+  --input <FILE>       Headless: key script, lines of '<frame> down|up <key>'
+  --dump-text <FILE>   Headless: write the final screen as text ('-' = stdout)
+  --dump-png <FILE>    Headless: write the final screen as PNG (--screenshot-scale)
+  --dump-json <FILE>   Headless: write the registers as JSON ('-' = stdout)
+  -h, --help           Show this help
+
+Headless exit codes: 0 done, 1 emulator error, 2 --until not reached,
+3 --verify found a different final screen.";
+
+/// Command line options.
+#[derive(Debug, Default)]
+pub struct Options {
+    pub rom_path: Option<String>,
+    pub quirks: Option<QuirksProfile>,
+    pub seed: Option<u64>,
+    pub rng_mode: RngMode,
+    pub timing: Option<Timing>,
+    pub fast_forward: FastForward,
+    pub palette: Option<Palette>,
+    pub debug: bool,
+    pub disassemble: Option<String>,
+    pub asm: Option<AsmOptions>,
+    pub trace: Option<String>,
+    pub trace_range: Option<RangeInclusive<u16>>,
+    pub trace_format: TraceFormat,
+    pub screenshot_dir: Option<String>,
+    pub screenshot_scale: Option<u32>,
+    pub record: bool,
+    pub record_format: VideoFormat,
+    pub record_scale: Option<u32>,
+    pub record_wav: bool,
+    pub record_dir: Option<String>,
+    pub record_movie: Option<String>,
+    pub play: Option<String>,
+    pub verify: bool,
+    pub headless: bool,
+    pub frames: Option<u64>,
+    pub until: Option<Until>,
+    pub input: Option<String>,
+    pub dump_text: Option<String>,
+    pub dump_png: Option<String>,
+    pub dump_json: Option<String>,
+    pub help: bool,
+}
+
+/// Options of the `asm` subcommand.
+#[derive(Debug, Default)]
+pub struct AsmOptions {
+    pub source: String,
+    pub output: Option<String>,
+}
+
+impl Options {
+    /// Parses the arguments that follow the program name.
+    pub fn parse(args: &[String]) -> Result<Self, String> {
+        let mut options = Options::default();
+        if args.first().map(String::as_str) == Some("asm") {
+            options.asm = Some(AsmOptions::parse(&args[1..])?);
+            return Ok(options);
+        }
+        let mut iter = args.iter();
+
+        while let Some(arg) = iter.next() {
+            match arg.as_str() {
+                "-h" | "--help" => options.help = true,
+                "--debug" => options.debug = true,
+                "--trace" => options.trace = Some(value(&mut iter, arg)?.to_string()),
+                "--trace-range" => {
+                    options.trace_range = Some(trace::parse_range(value(&mut iter, arg)?)?);
+                }
+                "--trace-format" => options.trace_format = value(&mut iter, arg)?.parse()?,
+                "--screenshot-dir" => {
+                    options.screenshot_dir = Some(value(&mut iter, arg)?.to_string());
+                }
+                "--screenshot-scale" => {
+                    let scale = value(&mut iter, arg)?;
+                    match scale.parse() {
+                        Ok(n) if (1..=64).contains(&n) => options.screenshot_scale = Some(n),
+                        _ => return Err(format!("invalid --screenshot-scale '{}'", scale)),
+                    }
+                }
+                "--record" => options.record = true,
+                "--record-format" => options.record_format = value(&mut iter, arg)?.parse()?,
+                "--record-scale" => {
+                    let scale = value(&mut iter, arg)?;
+                    match scale.parse() {
+                        Ok(n) if (1..=64).contains(&n) => options.record_scale = Some(n),
+                        _ => return Err(format!("invalid --record-scale '{}'", scale)),
+                    }
+                }
+                "--record-wav" => options.record_wav = true,
+                "--record-dir" => options.record_dir = Some(value(&mut iter, arg)?.to_string()),
+                "--record-movie" => {
+                    options.record_movie = Some(value(&mut iter, arg)?.to_string());
+                }
+                "--play" => options.play = Some(value(&mut iter, arg)?.to_string()),
+                "--verify" => options.verify = true,
+                "--headless" => options.headless = true,
+                "--frames" => {
+                    options.frames = Some(parse_number(value(&mut iter, arg)?)?);
+                }
+                "--until" => options.until = Some(value(&mut iter, arg)?.parse()?),
+                "--input" => options.input = Some(value(&mut iter, arg)?.to_string()),
+                "--dump-text" => options.dump_text = Some(value(&mut iter, arg)?.to_string()),
+                "--dump-png" => options.dump_png = Some(value(&mut iter, arg)?.to_string()),
+                "--dump-json" => options.dump_json = Some(value(&mut iter, arg)?.to_string()),
+                "--disassemble" => {
+                    options.disassemble = Some(value(&mut iter, arg)?.to_string());
+                }
+                "--quirks" => {
+                    options.quirks = Some(value(&mut iter, arg)?.parse()?);
+                }
+                "--seed" => {
+                    options.seed = Some(parse_number(value(&mut iter, arg)?)?);
+                }
+                "--rng" => {
+                    options.rng_mode = value(&mut iter, arg)?.parse()?;
+                }
+                "--ips" => {
+                    let ips = value(&mut iter, arg)?;
+                    match ips.parse() {
+                        Ok(Timing::Ips(n)) => options.timing = Some(Timing::Ips(n)),
+                        _ => return Err(format!("invalid --ips '{}'", ips)),
+                    }
+                }
+                "--vip-timing" => options.timing = Some(Timing::CosmacVip),
+                "--fast-forward" => options.fast_forward = value(&mut iter, arg)?.parse()?,
+                "--palette" => options.palette = Some(value(&mut iter, arg)?.parse()?),
+                flag if flag.starts_with('-') => {
+                    return Err(format!("unknown option '{}'", flag));
+                }
+                path => {
+                    if options.rom_path.is_some() {
+                        return Err(format!("unexpected argument '{}'", path));
+                    }
+                    options.rom_path = Some(path.to_string());
+                }
+            }
+        }
+
+        Ok(options)
+    }
+}
+
+impl AsmOptions {
+    fn parse(args: &[String]) -> Result<Self, String> {
+        let mut source = None;
+        let mut output = None;
+        let mut iter = args.iter();
+
+        while let Some(arg) = iter.next() {
+            match arg.as_str() {
+                "-o" | "--output" => output = Some(value(&mut iter, arg)?.to_string()),
+                flag if flag.starts_with('-') => {
+                    return Err(format!("unknown option '{}'", flag));
+                }
+                path => {
+                    if source.is_some() {
+                        return Err(format!("unexpected argument '{}'", path));
+                    }
+                    source = Some(path.to_string());
+                }
+            }
+        }
+
+        Ok(AsmOptions {
+            source: source.ok_or("asm needs a source file")?,
+            output,
+        })
+    }
+}
+
+fn value<'a>(iter: &mut impl Iterator<Item = &'a String>, flag: &str) -> Result<&'a str, String> {
+    iter.next()
+        .map(|s| s.as_str())
+        .ok_or_else(|| format!("{} needs a value", flag))
+}
+
+/// Parses a decimal or `0x`-prefixed hexadecimal number.
+pub fn parse_number(s: &str) -> Result<u64, String> {
+    let parsed = match s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")) {
+        Some(hex) => u64::from_str_radix(hex, 16),
+        None => s.parse(),
+    };
+    parsed.map_err(|_| format!("invalid number '{}'", s))
+}
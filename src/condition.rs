@@ -0,0 +1,302 @@
+// src/condition.rs
+//! Boolean expressions over the machine state for conditional breakpoints,
+//! e.g. `V3 == 0x10 && DT == 0`.
+//!
+//! Operands are numbers (`16`, `0x10`, `0b1000`), the registers `V0`-`VF`,
+//! `I`, `PC`, `SP`, `DT` and `ST`, and memory bytes written as `[address]`,
+//! e.g. `[I + 2]`. Operators, loosest first: `||`, `&&`, comparisons
+//! (`== != < <= > >=`), `+ - & | ^`, and the prefix `!`. Any non-zero value is
+//! true.
+use crate::emu::Emu;
+use std::fmt;
+use std::str::FromStr;
+
+/// A parsed condition, keeping its source text for display.
+///
+/// ```
+/// use chip8_emu::condition::Condition;
+/// use chip8_emu::Emu;
+///
+/// let condition: Condition = "PC == 0x200 && V3 == 0".parse().unwrap();
+/// assert!(condition.holds(&Emu::new()));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Condition {
+    source: String,
+    expr: Expr,
+}
+
+impl Condition {
+    /// Whether the condition is true for the current machine state.
+    pub fn holds(&self, emu: &Emu) -> bool {
+        self.expr.eval(emu) != 0
+    }
+}
+
+impl fmt::Display for Condition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.source)
+    }
+}
+
+impl FromStr for Condition {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parser = Parser {
+            tokens: tokenize(s)?,
+            pos: 0,
+        };
+        let expr = parser.or()?;
+        match parser.peek() {
+            None => Ok(Condition {
+                source: s.trim().to_string(),
+                expr,
+            }),
+            Some(token) => Err(format!("unexpected '{}'", token)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Register {
+    V(usize),
+    I,
+    Pc,
+    Sp,
+    Dt,
+    St,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum BinOp {
+    Or,
+    And,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Add,
+    Sub,
+    BitAnd,
+    BitOr,
+    BitXor,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Expr {
+    Number(i64),
+    Register(Register),
+    Memory(Box<Expr>),
+    Not(Box<Expr>),
+    Binary(BinOp, Box<Expr>, Box<Expr>),
+}
+
+impl Expr {
+    fn eval(&self, emu: &Emu) -> i64 {
+        match self {
+            Expr::Number(n) => *n,
+            Expr::Register(reg) => match reg {
+                Register::V(x) => emu.v_regs()[*x] as i64,
+                Register::I => emu.i_reg() as i64,
+                Register::Pc => emu.pc() as i64,
+                Register::Sp => emu.sp() as i64,
+                Register::Dt => emu.delay_timer() as i64,
+                Register::St => emu.get_sound_timer() as i64,
+            },
+            Expr::Memory(addr) => {
+                let addr = addr.eval(emu);
+                usize::try_from(addr)
+                    .ok()
+                    .and_then(|a| emu.ram().get(a))
+                    .map_or(0, |&b| b as i64)
+            }
+            Expr::Not(e) => (e.eval(emu) == 0) as i64,
+            Expr::Binary(op, lhs, rhs) => {
+                let a = lhs.eval(emu);
+                // && and || short-circuit
+                match op {
+                    BinOp::And => return (a != 0 && rhs.eval(emu) != 0) as i64,
+                    BinOp::Or => return (a != 0 || rhs.eval(emu) != 0) as i64,
+                    _ => {}
+                }
+                let b = rhs.eval(emu);
+                match op {
+                    BinOp::Eq => (a == b) as i64,
+                    BinOp::Ne => (a != b) as i64,
+                    BinOp::Lt => (a < b) as i64,
+                    BinOp::Le => (a <= b) as i64,
+                    BinOp::Gt => (a > b) as i64,
+                    BinOp::Ge => (a >= b) as i64,
+                    BinOp::Add => a.wrapping_add(b),
+                    BinOp::Sub => a.wrapping_sub(b),
+                    BinOp::BitAnd => a & b,
+                    BinOp::BitOr => a | b,
+                    BinOp::BitXor => a ^ b,
+                    BinOp::And | BinOp::Or => unreachable!(),
+                }
+            }
+        }
+    }
+}
+
+// Operators are kept as their text, longest first so `<=` wins over `<`
+const OPERATORS: [&str; 17] = [
+    "||", "&&", "==", "!=", "<=", ">=", "<", ">", "+", "-", "&", "|", "^", "!", "(", ")", "[",
+];
+
+fn tokenize(s: &str) -> Result<Vec<String>, String> {
+    let mut tokens = Vec::new();
+    let mut rest = s.trim_start();
+    while let Some(c) = rest.chars().next() {
+        let len = if c.is_ascii_alphanumeric() || c == '_' {
+            rest.find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                .unwrap_or(rest.len())
+        } else if c == ']' {
+            1
+        } else {
+            OPERATORS
+                .iter()
+                .find(|op| rest.starts_with(*op))
+                .map(|op| op.len())
+                .ok_or_else(|| format!("unexpected character '{}'", c))?
+        };
+        tokens.push(rest[..len].to_string());
+        rest = rest[len..].trim_start();
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<String>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&str> {
+        self.tokens.get(self.pos).map(|t| t.as_str())
+    }
+
+    fn next(&mut self) -> Result<String, String> {
+        let token = self
+            .tokens
+            .get(self.pos)
+            .cloned()
+            .ok_or_else(|| "unexpected end of condition".to_string())?;
+        self.pos += 1;
+        Ok(token)
+    }
+
+    fn expect(&mut self, expected: &str) -> Result<(), String> {
+        match self.next()? {
+            token if token == expected => Ok(()),
+            token => Err(format!("expected '{}', found '{}'", expected, token)),
+        }
+    }
+
+    // Parses `operand (op operand)*` for the operators of one precedence level
+    fn binary(
+        &mut self,
+        ops: &[(&str, BinOp)],
+        operand: fn(&mut Self) -> Result<Expr, String>,
+    ) -> Result<Expr, String> {
+        let mut lhs = operand(self)?;
+        while let Some(&(_, op)) = ops.iter().find(|(text, _)| self.peek() == Some(*text)) {
+            self.pos += 1;
+            lhs = Expr::Binary(op, Box::new(lhs), Box::new(operand(self)?));
+        }
+        Ok(lhs)
+    }
+
+    fn or(&mut self) -> Result<Expr, String> {
+        self.binary(&[("||", BinOp::Or)], Self::and)
+    }
+
+    fn and(&mut self) -> Result<Expr, String> {
+        self.binary(&[("&&", BinOp::And)], Self::comparison)
+    }
+
+    fn comparison(&mut self) -> Result<Expr, String> {
+        self.binary(
+            &[
+                ("==", BinOp::Eq),
+                ("!=", BinOp::Ne),
+                ("<=", BinOp::Le),
+                (">=", BinOp::Ge),
+                ("<", BinOp::Lt),
+                (">", BinOp::Gt),
+            ],
+            Self::sum,
+        )
+    }
+
+    fn sum(&mut self) -> Result<Expr, String> {
+        self.binary(
+            &[
+                ("+", BinOp::Add),
+                ("-", BinOp::Sub),
+                ("&", BinOp::BitAnd),
+                ("|", BinOp::BitOr),
+                ("^", BinOp::BitXor),
+            ],
+            Self::unary,
+        )
+    }
+
+    fn unary(&mut self) -> Result<Expr, String> {
+        if self.peek() == Some("!") {
+            self.pos += 1;
+            return Ok(Expr::Not(Box::new(self.unary()?)));
+        }
+        self.primary()
+    }
+
+    fn primary(&mut self) -> Result<Expr, String> {
+        let token = self.next()?;
+        match token.as_str() {
+            "(" => {
+                let expr = self.or()?;
+                self.expect(")")?;
+                Ok(expr)
+            }
+            "[" => {
+                let addr = self.or()?;
+                self.expect("]")?;
+                Ok(Expr::Memory(Box::new(addr)))
+            }
+            _ => operand(&token),
+        }
+    }
+}
+
+fn operand(token: &str) -> Result<Expr, String> {
+    let upper = token.to_ascii_uppercase();
+    let register = match upper.as_str() {
+        "I" => Some(Register::I),
+        "PC" => Some(Register::Pc),
+        "SP" => Some(Register::Sp),
+        "DT" => Some(Register::Dt),
+        "ST" => Some(Register::St),
+        _ => upper
+            .strip_prefix('V')
+            .filter(|x| x.len() == 1)
+            .and_then(|x| usize::from_str_radix(x, 16).ok())
+            .map(Register::V),
+    };
+    if let Some(register) = register {
+        return Ok(Expr::Register(register));
+    }
+
+    let number = if let Some(hex) = upper.strip_prefix("0X") {
+        i64::from_str_radix(hex, 16)
+    } else if let Some(bin) = upper.strip_prefix("0B") {
+        i64::from_str_radix(bin, 2)
+    } else {
+        upper.parse()
+    };
+    number
+        .map(Expr::Number)
+        .map_err(|_| format!("unknown operand '{}'", token))
+}
@@ -1,9 +1,34 @@
+// src/constants.rs
+//! Machine dimensions and the built-in font.
+
+/// Display width in pixels (low resolution).
 pub const SCREEN_WIDTH: usize = 64;
+/// Display height in pixels (low resolution).
 pub const SCREEN_HEIGHT: usize = 32;
+/// SUPER-CHIP high resolution display width.
+pub const HIRES_SCREEN_WIDTH: usize = 128;
+/// SUPER-CHIP high resolution display height.
+pub const HIRES_SCREEN_HEIGHT: usize = 64;
+/// Addressable memory in bytes.
 pub const RAM_SIZE: usize = 4096;
+/// XO-CHIP extended address space in bytes.
+pub const XO_RAM_SIZE: usize = 0x10000;
+/// Number of XO-CHIP display bitplanes.
+pub const PLANE_COUNT: usize = 2;
+/// Length of the XO-CHIP audio pattern buffer loaded by `F002`.
+pub const AUDIO_PATTERN_SIZE: usize = 16;
+/// Number of general purpose registers (V0-VF).
 pub const REGISTERS_COUNT: usize = 16;
+/// Maximum subroutine nesting depth.
 pub const STACK_SIZE: usize = 16;
+/// Address where programs are loaded and execution starts.
+pub const START_ADDR: u16 = 0x200;
+/// Number of SUPER-CHIP RPL user flags saved by `FX75`.
+pub const RPL_FLAGS_COUNT: usize = 16;
+/// Address of the SUPER-CHIP 8x10 font, right after the small font.
+pub const BIG_FONT_ADDR: usize = 0x50;
 
+/// Hex digit sprites (0-F), 5 bytes each, loaded at the start of RAM.
 pub const FONTSET: [u8; 80] = [
     0xF0, 0x90, 0x90, 0x90, 0xF0, // 0
     0x20, 0x60, 0x20, 0x20, 0x70, // 1
@@ -22,3 +47,30 @@ pub const FONTSET: [u8; 80] = [
     0xF0, 0x80, 0xF0, 0x80, 0xF0, // E
     0xF0, 0x80, 0xF0, 0x80, 0x80, // F
 ];
+
+/// Large digit sprites, 10 bytes each, loaded at `BIG_FONT_ADDR`.
+/// SUPER-CHIP defines 0-9; XO-CHIP adds A-F.
+pub const BIG_FONTSET: [u8; 160] = [
+    0x3C, 0x7E, 0xE7, 0xC3, 0xC3, 0xC3, 0xC3, 0xE7, 0x7E, 0x3C, // 0
+    0x18, 0x38, 0x58, 0x18, 0x18, 0x18, 0x18, 0x18, 0x18, 0x3C, // 1
+    0x3E, 0x7F, 0xC3, 0x06, 0x0C, 0x18, 0x30, 0x60, 0xFF, 0xFF, // 2
+    0x3C, 0x7E, 0xC3, 0x03, 0x0E, 0x0E, 0x03, 0xC3, 0x7E, 0x3C, // 3
+    0x06, 0x0E, 0x1E, 0x36, 0x66, 0xC6, 0xFF, 0xFF, 0x06, 0x06, // 4
+    0xFF, 0xFF, 0xC0, 0xC0, 0xFC, 0xFE, 0x03, 0xC3, 0x7E, 0x3C, // 5
+    0x3E, 0x7C, 0xC0, 0xC0, 0xFC, 0xFE, 0xC3, 0xC3, 0x7E, 0x3C, // 6
+    0xFF, 0xFF, 0x03, 0x06, 0x0C, 0x18, 0x30, 0x60, 0x60, 0x60, // 7
+    0x3C, 0x7E, 0xC3, 0xC3, 0x7E, 0x7E, 0xC3, 0xC3, 0x7E, 0x3C, // 8
+    0x3C, 0x7E, 0xC3, 0xC3, 0x7F, 0x3F, 0x03, 0x03, 0x3E, 0x7C, // 9
+    0x3C, 0x7E, 0xC3, 0xC3, 0xC3, 0xFF, 0xFF, 0xC3, 0xC3, 0xC3, // A
+    0xFC, 0xFE, 0xC3, 0xC3, 0xFE, 0xFE, 0xC3, 0xC3, 0xFE, 0xFC, // B
+    0x3C, 0x7E, 0xC3, 0xC0, 0xC0, 0xC0, 0xC0, 0xC3, 0x7E, 0x3C, // C
+    0xFC, 0xFE, 0xC3, 0xC3, 0xC3, 0xC3, 0xC3, 0xC3, 0xFE, 0xFC, // D
+    0xFF, 0xFF, 0xC0, 0xC0, 0xFC, 0xFC, 0xC0, 0xC0, 0xFF, 0xFF, // E
+    0xFF, 0xFF, 0xC0, 0xC0, 0xFC, 0xFC, 0xC0, 0xC0, 0xC0, 0xC0, // F
+];
+
+/// Audio pattern an XO-CHIP program hears before it loads its own with `F002`:
+/// a square wave of 8 bits low, 8 bits high (250 Hz at the default pitch).
+pub const DEFAULT_AUDIO_PATTERN: [u8; AUDIO_PATTERN_SIZE] = [
+    0x00, 0xFF, 0x00, 0xFF, 0x00, 0xFF, 0x00, 0xFF, 0x00, 0xFF, 0x00, 0xFF, 0x00, 0xFF, 0x00, 0xFF,
+];
@@ -0,0 +1,193 @@
+// src/controller.rs
+//! SDL game controllers: opened as they are plugged in, their input fed to
+//! the keypad through [`chip8_emu::pad`].
+use chip8_emu::pad::{PadEvent, PadMap, PadState};
+use chip8_emu::Emu;
+use sdl2::controller::GameController;
+use sdl2::event::Event;
+use sdl2::GameControllerSubsystem;
+use std::collections::HashMap;
+
+pub struct Controllers {
+    subsystem: GameControllerSubsystem,
+    open: HashMap<u32, GameController>, // By joystick instance id
+    state: PadState,
+}
+
+impl Controllers {
+    pub fn new(subsystem: GameControllerSubsystem) -> Self {
+        Self {
+            subsystem,
+            open: HashMap::new(),
+            state: PadState::default(),
+        }
+    }
+
+    /// Handles controller events, including hot-plugging; returns false for
+    /// any other event. SDL also reports controllers that are already
+    /// connected at startup as added.
+    pub fn handle(&mut self, event: &Event, map: &PadMap, chip8: &mut Emu) -> bool {
+        match *event {
+            Event::ControllerDeviceAdded { which, .. } => {
+                match self.subsystem.open(which) {
+                    Ok(controller) => {
+                        println!("🎮 Controller connected: {}", controller.name());
+                        self.open.insert(controller.instance_id(), controller);
+                    }
+                    Err(e) => eprintln!("Could not open controller {}: {}", which, e),
+                }
+                return true;
+            }
+            Event::ControllerDeviceRemoved { which, .. } => {
+                if let Some(controller) = self.open.remove(&which) {
+                    println!("🎮 Controller disconnected: {}", controller.name());
+                }
+            }
+            _ => {}
+        }
+        // Unplugging also releases what the controller held
+        match pad_event(event) {
+            Some(pad_event) => {
+                self.state.apply(map, &pad_event, chip8);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Presses the keys controllers hold again after the keypad was reset.
+    pub fn sync(&self, chip8: &mut Emu) {
+        self.state.sync(chip8);
+    }
+}
+
+// Button, axis and unplug events in the frontend-independent form
+fn pad_event(event: &Event) -> Option<PadEvent> {
+    match *event {
+        Event::ControllerButtonDown { which, button, .. } => Some(PadEvent::Button {
+            pad: which,
+            button: button.string(),
+            pressed: true,
+        }),
+        Event::ControllerButtonUp { which, button, .. } => Some(PadEvent::Button {
+            pad: which,
+            button: button.string(),
+            pressed: false,
+        }),
+        Event::ControllerAxisMotion {
+            which, axis, value, ..
+        } => Some(PadEvent::Axis {
+            pad: which,
+            axis: axis.string(),
+            value,
+        }),
+        Event::ControllerDeviceRemoved { which, .. } => Some(PadEvent::Removed { pad: which }),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chip8_emu::pad::AXIS_THRESHOLD;
+    use sdl2::controller::{Axis, Button};
+
+    // Feeds the events through the default map; returns the held CHIP-8 keys
+    fn held_after(events: &[Event]) -> Vec<usize> {
+        let map = PadMap::default();
+        let mut state = PadState::default();
+        let mut emu = Emu::new();
+        for event in events {
+            if let Some(pad_event) = pad_event(event) {
+                state.apply(&map, &pad_event, &mut emu);
+            }
+        }
+        (0..16).filter(|&k| emu.keys()[k]).collect()
+    }
+
+    fn down(which: u32, button: Button) -> Event {
+        Event::ControllerButtonDown {
+            timestamp: 0,
+            which,
+            button,
+        }
+    }
+
+    fn up(which: u32, button: Button) -> Event {
+        Event::ControllerButtonUp {
+            timestamp: 0,
+            which,
+            button,
+        }
+    }
+
+    fn motion(which: u32, axis: Axis, value: i16) -> Event {
+        Event::ControllerAxisMotion {
+            timestamp: 0,
+            which,
+            axis,
+            value,
+        }
+    }
+
+    #[test]
+    fn buttons_press_and_release_keys() {
+        assert_eq!(
+            held_after(&[down(0, Button::DPadUp), down(0, Button::A)]),
+            vec![0x5, 0x6]
+        );
+        assert_eq!(
+            held_after(&[
+                down(0, Button::DPadUp),
+                down(0, Button::A),
+                up(0, Button::DPadUp),
+            ]),
+            vec![0x6]
+        );
+    }
+
+    #[test]
+    fn sticks_count_only_outside_the_deadzone() {
+        assert!(held_after(&[motion(0, Axis::LeftX, -AXIS_THRESHOLD + 1)]).is_empty());
+        assert_eq!(
+            held_after(&[motion(0, Axis::LeftX, -AXIS_THRESHOLD)]),
+            vec![0x7]
+        );
+        assert_eq!(held_after(&[motion(0, Axis::LeftY, i16::MAX)]), vec![0x8]);
+        assert!(held_after(&[
+            motion(0, Axis::LeftX, AXIS_THRESHOLD),
+            motion(0, Axis::LeftX, AXIS_THRESHOLD - 1),
+        ])
+        .is_empty());
+    }
+
+    #[test]
+    fn unplugging_releases_only_that_controllers_keys() {
+        let plugged = [
+            Event::ControllerDeviceAdded {
+                timestamp: 0,
+                which: 1,
+            },
+            down(0, Button::B),
+            down(1, Button::DPadDown),
+        ];
+        assert_eq!(held_after(&plugged), vec![0x4, 0x8]);
+
+        let mut unplugged = plugged.to_vec();
+        unplugged.push(Event::ControllerDeviceRemoved {
+            timestamp: 0,
+            which: 1,
+        });
+        assert_eq!(held_after(&unplugged), vec![0x4]);
+    }
+
+    #[test]
+    fn other_events_are_not_pad_events() {
+        let added = Event::ControllerDeviceAdded {
+            timestamp: 0,
+            which: 0,
+        };
+        assert_eq!(pad_event(&added), None);
+        assert_eq!(pad_event(&Event::Quit { timestamp: 0 }), None);
+    }
+}
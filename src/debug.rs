@@ -0,0 +1,333 @@
+// src/debug.rs
+//! Execution control for debugger frontends: pausing, stepping, PC
+//! breakpoints, memory watchpoints and conditional breakpoints.
+use crate::condition::Condition;
+use crate::emu::{AccessKind, Emu};
+use crate::error::Chip8Error;
+use crate::timing::{Scheduler, Timing};
+use std::collections::BTreeSet;
+use std::fmt;
+use std::ops::RangeInclusive;
+use std::str::FromStr;
+
+/// Why execution stopped before the requested amount of work was done.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StopReason {
+    /// The PC reached a breakpoint; the instruction there has not run yet.
+    Breakpoint(u16),
+    /// The instruction faulted.
+    Error(Chip8Error),
+    /// The program executed `00FD`.
+    Exited,
+    /// The instruction at `pc` touched a watched address; it has already run.
+    Watchpoint {
+        watchpoint: Watchpoint,
+        addr: u16,
+        pc: u16,
+    },
+    /// A condition became true after the instruction at `pc` ran.
+    Condition { condition: String, pc: u16 },
+}
+
+/// What a [`Watchpoint`] reacts to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WatchKind {
+    Read,
+    Write,
+    /// Reads and writes.
+    Access,
+    /// An instruction sets I to an address in the range, without accessing it.
+    Index,
+}
+
+impl WatchKind {
+    pub fn all() -> [WatchKind; 4] {
+        [
+            WatchKind::Read,
+            WatchKind::Write,
+            WatchKind::Access,
+            WatchKind::Index,
+        ]
+    }
+
+    pub fn id(&self) -> &'static str {
+        match self {
+            WatchKind::Read => "read",
+            WatchKind::Write => "write",
+            WatchKind::Access => "access",
+            WatchKind::Index => "index",
+        }
+    }
+}
+
+impl fmt::Display for WatchKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.id())
+    }
+}
+
+impl FromStr for WatchKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        WatchKind::all()
+            .into_iter()
+            .find(|kind| kind.id() == s)
+            .ok_or_else(|| {
+                format!(
+                    "unknown watch kind '{}' (expected read, write, access or index)",
+                    s
+                )
+            })
+    }
+}
+
+/// Stops execution when memory in `range` is accessed or pointed at by I.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Watchpoint {
+    pub range: RangeInclusive<u16>,
+    pub kind: WatchKind,
+}
+
+impl Watchpoint {
+    // The first watched address the last instruction touched
+    fn hit(&self, emu: &Emu, i_before: u16) -> Option<u16> {
+        if self.kind == WatchKind::Index {
+            let i = emu.i_reg();
+            return (i != i_before && self.range.contains(&i)).then_some(i);
+        }
+        emu.memory_accesses()
+            .iter()
+            .find(|access| {
+                let kind_matches = match self.kind {
+                    WatchKind::Read => access.kind == AccessKind::Read,
+                    WatchKind::Write => access.kind == AccessKind::Write,
+                    _ => true,
+                };
+                kind_matches && self.range.contains(&access.addr)
+            })
+            .map(|access| access.addr)
+    }
+}
+
+impl fmt::Display for Watchpoint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.range.start() == self.range.end() {
+            write!(f, "{} {:04X}", self.kind, self.range.start())
+        } else {
+            write!(
+                f,
+                "{} {:04X}-{:04X}",
+                self.kind,
+                self.range.start(),
+                self.range.end()
+            )
+        }
+    }
+}
+
+// A condition and whether it held after the last instruction; breaking only
+// when it turns true lets execution continue past the stop
+struct ConditionBreakpoint {
+    condition: Condition,
+    held: bool,
+}
+
+/// Runs an [`Emu`] instruction by instruction, keeping track of where it is
+/// within the current 60 Hz frame so timers tick at the right moment no
+/// matter how execution is split up by stepping and breakpoints.
+pub struct Debugger {
+    breakpoints: BTreeSet<u16>,
+    watchpoints: Vec<Watchpoint>,
+    conditions: Vec<ConditionBreakpoint>,
+    scheduler: Scheduler,
+    frame_ticks: usize, // Instructions already executed in the current frame
+    paused: bool,
+    skip_breakpoint: bool, // Lets `continue` move off the breakpoint it stopped at
+}
+
+impl Debugger {
+    pub fn new(timing: Timing) -> Self {
+        Self {
+            breakpoints: BTreeSet::new(),
+            watchpoints: Vec::new(),
+            conditions: Vec::new(),
+            scheduler: Scheduler::new(timing),
+            frame_ticks: 0,
+            paused: false,
+            skip_breakpoint: false,
+        }
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    pub fn pause(&mut self) {
+        self.paused = true;
+    }
+
+    /// Resumes free running; the instruction at the current PC runs even if
+    /// it has a breakpoint.
+    pub fn resume(&mut self) {
+        self.paused = false;
+        self.skip_breakpoint = true;
+    }
+
+    pub fn breakpoints(&self) -> &BTreeSet<u16> {
+        &self.breakpoints
+    }
+
+    pub fn add_breakpoint(&mut self, addr: u16) {
+        self.breakpoints.insert(addr);
+    }
+
+    pub fn remove_breakpoint(&mut self, addr: u16) {
+        self.breakpoints.remove(&addr);
+    }
+
+    /// Adds the breakpoint if missing, removes it otherwise.
+    pub fn toggle_breakpoint(&mut self, addr: u16) {
+        if !self.breakpoints.remove(&addr) {
+            self.breakpoints.insert(addr);
+        }
+    }
+
+    pub fn watchpoints(&self) -> &[Watchpoint] {
+        &self.watchpoints
+    }
+
+    /// Adds a watchpoint; it turns on access recording in `emu`.
+    pub fn add_watchpoint(&mut self, emu: &mut Emu, watchpoint: Watchpoint) {
+        if !self.watchpoints.contains(&watchpoint) {
+            self.watchpoints.push(watchpoint);
+        }
+        emu.set_access_recording(true);
+    }
+
+    pub fn remove_watchpoint(&mut self, emu: &mut Emu, index: usize) {
+        if index < self.watchpoints.len() {
+            self.watchpoints.remove(index);
+        }
+        if self.watchpoints.is_empty() {
+            emu.set_access_recording(false);
+        }
+    }
+
+    pub fn conditions(&self) -> impl Iterator<Item = &Condition> {
+        self.conditions.iter().map(|c| &c.condition)
+    }
+
+    /// Adds a conditional breakpoint. It stops execution when the condition
+    /// becomes true, so one that already holds waits until it has been false.
+    pub fn add_condition(&mut self, emu: &Emu, condition: Condition) {
+        let held = condition.holds(emu);
+        self.conditions
+            .push(ConditionBreakpoint { condition, held });
+    }
+
+    pub fn remove_condition(&mut self, index: usize) {
+        if index < self.conditions.len() {
+            self.conditions.remove(index);
+        }
+    }
+
+    /// Instructions executed so far in the current frame.
+    pub fn frame_ticks(&self) -> usize {
+        self.frame_ticks
+    }
+
+    /// Executes exactly one instruction, ignoring breakpoints, and ticks the
+    /// timers if that completes a frame.
+    pub fn step_instruction(&mut self, emu: &mut Emu) -> Option<StopReason> {
+        self.skip_breakpoint = false;
+        self.execute_one(emu).err()
+    }
+
+    /// Runs to the end of the current frame, stopping early at breakpoints.
+    pub fn step_frame(&mut self, emu: &mut Emu) -> Option<StopReason> {
+        // Stepping starts on the current instruction even if it has a breakpoint
+        self.skip_breakpoint = true;
+        self.run_to_frame_end(emu)
+    }
+
+    /// Called once per displayed frame: runs the rest of the frame unless
+    /// paused. Any stop pauses the debugger.
+    pub fn run_frame(&mut self, emu: &mut Emu) -> Option<StopReason> {
+        if self.paused {
+            return None;
+        }
+        self.run_to_frame_end(emu)
+    }
+
+    fn run_to_frame_end(&mut self, emu: &mut Emu) -> Option<StopReason> {
+        loop {
+            if !self.skip_breakpoint && self.breakpoints.contains(&emu.pc()) {
+                self.paused = true;
+                return Some(StopReason::Breakpoint(emu.pc()));
+            }
+            self.skip_breakpoint = false;
+
+            match self.execute_one(emu) {
+                Err(reason) => return Some(reason),
+                Ok(true) => return None,
+                Ok(false) => {}
+            }
+        }
+    }
+
+    // Returns whether the instruction completed the frame
+    fn execute_one(&mut self, emu: &mut Emu) -> Result<bool, StopReason> {
+        let (pc, i_before) = (emu.pc(), emu.i_reg());
+        if self.frame_ticks == 0 {
+            self.scheduler.start_frame();
+        }
+        if let Err(e) = self.scheduler.tick(emu) {
+            self.paused = true;
+            return Err(StopReason::Error(e));
+        }
+        if emu.has_exited() {
+            self.paused = true;
+            return Err(StopReason::Exited);
+        }
+
+        let mut frame_done = false;
+        self.frame_ticks += 1;
+        if self.scheduler.frame_done() {
+            self.frame_ticks = 0;
+            emu.tick_timers();
+            frame_done = true;
+        }
+
+        // Checked after the timers so conditions on DT/ST see their new value
+        if let Some(reason) = self.check_watches(emu, pc, i_before) {
+            self.paused = true;
+            return Err(reason);
+        }
+        Ok(frame_done)
+    }
+
+    fn check_watches(&mut self, emu: &Emu, pc: u16, i_before: u16) -> Option<StopReason> {
+        let mut reason = self.watchpoints.iter().find_map(|watchpoint| {
+            watchpoint
+                .hit(emu, i_before)
+                .map(|addr| StopReason::Watchpoint {
+                    watchpoint: watchpoint.clone(),
+                    addr,
+                    pc,
+                })
+        });
+        // Every condition is evaluated so each one tracks its own state
+        for c in &mut self.conditions {
+            let held = c.condition.holds(emu);
+            if held && !c.held && reason.is_none() {
+                reason = Some(StopReason::Condition {
+                    condition: c.condition.to_string(),
+                    pc,
+                });
+            }
+            c.held = held;
+        }
+        reason
+    }
+}
@@ -0,0 +1,86 @@
+// src/deflicker.rs
+//! Flicker reduction for XOR-drawn sprites.
+//!
+//! Programs redraw a sprite by drawing it over itself, which erases it
+//! (`DXYN` XORs), and then drawing it again. When the erase ends one frame and
+//! the redraw starts the next, the sprite is missing from every other frame
+//! and blinks. Deflicker watches each pixel, per bitplane, for that pattern:
+//! on, off for a single frame, then on again. A pixel that shows it is kept
+//! lit through its off frames; pixels that just turn off, like the old place
+//! of a moving sprite, go dark at once. Some games flicker on purpose, e.g.
+//! for see-through sprites, so this is a choice per ROM.
+use crate::emu::Emu;
+
+/// The last three frames of a plane, newest in the lowest bit, that make up
+/// the erase/redraw pattern: on, off, on.
+const ERASE_REDRAW: u8 = 0b101;
+const PATTERN_MASK: u8 = 0b111;
+/// Frames a plane stays filled in after the pattern was last seen; enough to
+/// bridge one off frame.
+const HOLD_FRAMES: u8 = 2;
+
+/// Per-pixel history of the screen, for display without XOR redraw flicker.
+///
+/// ```
+/// use chip8_emu::deflicker::Deflicker;
+/// use chip8_emu::Emu;
+///
+/// let emu = Emu::new();
+/// let mut deflicker = Deflicker::new();
+/// deflicker.push(&emu);
+/// assert_eq!(deflicker.display(&emu), emu.get_display());
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Deflicker {
+    width: usize,
+    history: Vec<[u8; 2]>, // Recent frames of planes 1 and 2, newest in bit 0
+    hold: Vec<[u8; 2]>,    // Frames each plane is still filled in for
+}
+
+impl Deflicker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Takes the screen of `emu`; call after every emulated frame.
+    pub fn push(&mut self, emu: &Emu) {
+        let display = emu.get_display();
+        if emu.width() != self.width || self.history.len() != display.len() {
+            self.width = emu.width();
+            self.history = vec![[0; 2]; display.len()];
+            self.hold = vec![[0; 2]; display.len()];
+        }
+        for ((history, hold), &px) in self.history.iter_mut().zip(&mut self.hold).zip(display) {
+            for plane in 0..2 {
+                history[plane] = history[plane] << 1 | (px >> plane & 1);
+                hold[plane] = if history[plane] & PATTERN_MASK == ERASE_REDRAW {
+                    HOLD_FRAMES
+                } else {
+                    hold[plane].saturating_sub(1)
+                };
+            }
+        }
+    }
+
+    /// Forgets the earlier frames, e.g. after a state was loaded.
+    pub fn reset(&mut self) {
+        self.history.clear();
+        self.hold.clear();
+    }
+
+    /// The screen of `emu` with each bitplane filled in where it was erased
+    /// only to be redrawn.
+    pub fn display(&self, emu: &Emu) -> Vec<u8> {
+        let mut display = emu.get_display().to_vec();
+        if emu.width() == self.width && self.hold.len() == display.len() {
+            for (px, hold) in display.iter_mut().zip(&self.hold) {
+                for (plane, &frames) in hold.iter().enumerate() {
+                    if frames > 0 {
+                        *px |= 1 << plane;
+                    }
+                }
+            }
+        }
+        display
+    }
+}
@@ -0,0 +1,345 @@
+// src/disasm.rs
+//! Disassembler for the opcode space understood by [`Emu`](crate::Emu).
+//!
+//! Mnemonics follow Cowgod's reference (`LD V3, 0x1F`, `DRW V0, V1, 5`), with
+//! the SUPER-CHIP and XO-CHIP additions named after their usual assemblers.
+use crate::constants::START_ADDR;
+use crate::quirks::Quirks;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+
+/// How an instruction passes control to the next one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Flow {
+    /// Continues with the following instruction.
+    Next,
+    /// Conditionally skips the following instruction.
+    Skip,
+    /// `1NNN`.
+    Jump(u16),
+    /// `2NNN`; execution continues after it once the subroutine returns.
+    Call(u16),
+    /// `00EE`.
+    Return,
+    /// `BNNN`/`BXNN`, a jump to a base address plus a register.
+    JumpOffset(u16),
+    /// `00FD` or an opcode the interpreter does not know.
+    Stop,
+}
+
+/// A single decoded instruction.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Instruction {
+    /// The first 16 bits of the instruction.
+    pub opcode: u16,
+    /// Size in bytes: 4 for XO-CHIP's `F000 NNNN`, otherwise 2.
+    pub len: u16,
+    pub mnemonic: String,
+    pub flow: Flow,
+    /// False when [`Emu`](crate::Emu) would fail with an unknown opcode.
+    pub valid: bool,
+}
+
+/// Decodes the instruction at `addr` in `memory`, or returns `None` if it
+/// runs past the end of memory.
+pub fn decode(memory: &[u8], addr: usize, quirks: &Quirks) -> Option<Instruction> {
+    decode_with(memory, addr, quirks, &|target| format!("0x{:03X}", target))
+}
+
+// `address` formats jump, call and I targets so the listing can print labels
+fn decode_with(
+    memory: &[u8],
+    addr: usize,
+    quirks: &Quirks,
+    address: &dyn Fn(u16) -> String,
+) -> Option<Instruction> {
+    let word = |at: usize| -> Option<u16> {
+        let bytes = memory.get(at..at + 2)?;
+        Some((bytes[0] as u16) << 8 | bytes[1] as u16)
+    };
+    let op = word(addr)?;
+
+    let digit1 = (op & 0xF000) >> 12;
+    let x = (op & 0x0F00) >> 8;
+    let y = (op & 0x00F0) >> 4;
+    let n = op & 0x000F;
+    let nn = op & 0x00FF;
+    let nnn = op & 0x0FFF;
+    let xo = quirks.xo_chip;
+
+    let mut len = 2;
+    let mut flow = Flow::Next;
+    let mnemonic = match (digit1, x, y, n) {
+        (0, 0, 0, 0) => "NOP".to_string(),
+        (0, 0, 0xE, 0) => "CLS".to_string(),
+        (0, 0, 0xE, 0xE) => {
+            flow = Flow::Return;
+            "RET".to_string()
+        }
+        (0, 0, 0xC, n) => format!("SCD {}", n),
+        (0, 0, 0xD, n) if xo => format!("SCU {}", n),
+        (0, 0, 0xF, 0xB) => "SCR".to_string(),
+        (0, 0, 0xF, 0xC) => "SCL".to_string(),
+        (0, 0, 0xF, 0xD) => {
+            flow = Flow::Stop;
+            "EXIT".to_string()
+        }
+        (0, 0, 0xF, 0xE) => "LOW".to_string(),
+        (0, 0, 0xF, 0xF) => "HIGH".to_string(),
+        (1, _, _, _) => {
+            flow = Flow::Jump(nnn);
+            format!("JP {}", address(nnn))
+        }
+        (2, _, _, _) => {
+            flow = Flow::Call(nnn);
+            format!("CALL {}", address(nnn))
+        }
+        (3, _, _, _) => {
+            flow = Flow::Skip;
+            format!("SE V{:X}, 0x{:02X}", x, nn)
+        }
+        (4, _, _, _) => {
+            flow = Flow::Skip;
+            format!("SNE V{:X}, 0x{:02X}", x, nn)
+        }
+        (5, _, _, 0) => {
+            flow = Flow::Skip;
+            format!("SE V{:X}, V{:X}", x, y)
+        }
+        (5, _, _, 2) if xo => format!("SAVE V{:X}-V{:X}", x, y),
+        (5, _, _, 3) if xo => format!("LOAD V{:X}-V{:X}", x, y),
+        (6, _, _, _) => format!("LD V{:X}, 0x{:02X}", x, nn),
+        (7, _, _, _) => format!("ADD V{:X}, 0x{:02X}", x, nn),
+        (8, _, _, 0) => format!("LD V{:X}, V{:X}", x, y),
+        (8, _, _, 1) => format!("OR V{:X}, V{:X}", x, y),
+        (8, _, _, 2) => format!("AND V{:X}, V{:X}", x, y),
+        (8, _, _, 3) => format!("XOR V{:X}, V{:X}", x, y),
+        (8, _, _, 4) => format!("ADD V{:X}, V{:X}", x, y),
+        (8, _, _, 5) => format!("SUB V{:X}, V{:X}", x, y),
+        (8, _, _, 6) if quirks.shift_uses_vy => format!("SHR V{:X}, V{:X}", x, y),
+        (8, _, _, 6) => format!("SHR V{:X}", x),
+        (8, _, _, 7) => format!("SUBN V{:X}, V{:X}", x, y),
+        (8, _, _, 0xE) if quirks.shift_uses_vy => format!("SHL V{:X}, V{:X}", x, y),
+        (8, _, _, 0xE) => format!("SHL V{:X}", x),
+        (9, _, _, 0) => {
+            flow = Flow::Skip;
+            format!("SNE V{:X}, V{:X}", x, y)
+        }
+        (0xA, _, _, _) => format!("LD I, {}", address(nnn)),
+        (0xB, _, _, _) => {
+            flow = Flow::JumpOffset(nnn);
+            if quirks.jump_uses_vx {
+                format!("JP V{:X}, {}", x, address(nnn))
+            } else {
+                format!("JP V0, {}", address(nnn))
+            }
+        }
+        (0xC, _, _, _) => format!("RND V{:X}, 0x{:02X}", x, nn),
+        (0xD, _, _, n) => format!("DRW V{:X}, V{:X}, {}", x, y, n),
+        (0xE, _, 9, 0xE) => {
+            flow = Flow::Skip;
+            format!("SKP V{:X}", x)
+        }
+        (0xE, _, 0xA, 1) => {
+            flow = Flow::Skip;
+            format!("SKNP V{:X}", x)
+        }
+        (0xF, 0, 0, 0) if xo => {
+            len = 4;
+            match word(addr + 2) {
+                Some(long) => format!("LD I, {}", address(long)),
+                None => return None,
+            }
+        }
+        (0xF, n, 0, 1) if xo => format!("PLANE {}", n),
+        (0xF, 0, 0, 2) if xo => "AUDIO".to_string(),
+        (0xF, _, 0, 7) => format!("LD V{:X}, DT", x),
+        (0xF, _, 0, 0xA) => format!("LD V{:X}, K", x),
+        (0xF, _, 1, 5) => format!("LD DT, V{:X}", x),
+        (0xF, _, 1, 8) => format!("LD ST, V{:X}", x),
+        (0xF, _, 1, 0xE) => format!("ADD I, V{:X}", x),
+        (0xF, _, 2, 9) => format!("LD F, V{:X}", x),
+        (0xF, _, 3, 0) => format!("LD HF, V{:X}", x),
+        (0xF, _, 3, 0xA) if xo => format!("PITCH V{:X}", x),
+        (0xF, _, 3, 3) => format!("LD B, V{:X}", x),
+        (0xF, _, 5, 5) => format!("LD [I], V{:X}", x),
+        (0xF, _, 6, 5) => format!("LD V{:X}, [I]", x),
+        (0xF, _, 7, 5) => format!("LD R, V{:X}", x),
+        (0xF, _, 8, 5) => format!("LD V{:X}, R", x),
+        _ => {
+            return Some(Instruction {
+                opcode: op,
+                len,
+                mnemonic: format!("DW 0x{:04X}", op),
+                flow: Flow::Stop,
+                valid: false,
+            });
+        }
+    };
+
+    Some(Instruction {
+        opcode: op,
+        len,
+        mnemonic,
+        flow,
+        valid: true,
+    })
+}
+
+/// One line of a [`Listing`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Line {
+    /// An instruction reachable from the entry point.
+    Code {
+        addr: u16,
+        bytes: Vec<u8>,
+        instruction: Instruction,
+    },
+    /// Bytes never reached as code: sprites, tables or unused space.
+    Data { addr: u16, bytes: Vec<u8> },
+}
+
+impl Line {
+    pub fn addr(&self) -> u16 {
+        match self {
+            Line::Code { addr, .. } | Line::Data { addr, .. } => *addr,
+        }
+    }
+}
+
+/// A disassembled ROM with labels for every jump and call target.
+#[derive(Debug, Clone)]
+pub struct Listing {
+    pub lines: Vec<Line>,
+    pub labels: BTreeMap<u16, String>,
+}
+
+// Data lines hold at most this many bytes
+const DATA_BYTES_PER_LINE: usize = 4;
+
+/// Disassembles a ROM loaded at 0x200.
+///
+/// Code is told apart from data by following every path reachable from the
+/// entry point; bytes no path reaches are listed as data. The targets of
+/// `BNNN` jump tables are treated as code as well.
+pub fn disassemble(rom: &[u8], quirks: &Quirks) -> Listing {
+    // Same layout as the emulator's memory so addresses line up
+    let mut memory = vec![0; START_ADDR as usize];
+    memory.extend_from_slice(rom);
+    let end = memory.len();
+
+    let mut code: BTreeMap<usize, Instruction> = BTreeMap::new();
+    let mut targets = BTreeSet::new();
+    let mut pending = vec![START_ADDR as usize];
+
+    while let Some(addr) = pending.pop() {
+        if addr < START_ADDR as usize || code.contains_key(&addr) {
+            continue;
+        }
+        let Some(instruction) = decode(&memory, addr, quirks) else {
+            continue;
+        };
+        if !instruction.valid {
+            continue;
+        }
+
+        let next = addr + instruction.len as usize;
+        match instruction.flow {
+            Flow::Next => pending.push(next),
+            Flow::Skip => {
+                pending.push(next);
+                // The skipped instruction may itself be 4 bytes long
+                let skipped = decode(&memory, next, quirks).map_or(2, |i| i.len as usize);
+                pending.push(next + skipped);
+            }
+            Flow::Jump(target) => {
+                targets.insert(target);
+                pending.push(target as usize);
+            }
+            Flow::Call(target) => {
+                targets.insert(target);
+                pending.push(target as usize);
+                pending.push(next);
+            }
+            Flow::JumpOffset(target) => {
+                targets.insert(target);
+                pending.push(target as usize);
+            }
+            Flow::Return | Flow::Stop => {}
+        }
+        code.insert(addr, instruction);
+    }
+
+    let labels: BTreeMap<u16, String> = targets
+        .into_iter()
+        .filter(|&target| (START_ADDR as usize..end).contains(&(target as usize)))
+        .map(|target| (target, format!("L{:03X}", target)))
+        .collect();
+    let address = |target: u16| {
+        labels
+            .get(&target)
+            .cloned()
+            .unwrap_or_else(|| format!("0x{:03X}", target))
+    };
+
+    let mut lines = Vec::new();
+    let mut addr = START_ADDR as usize;
+    while addr < end {
+        if code.contains_key(&addr) {
+            // Decode again so targets are printed as labels
+            if let Some(instruction) = decode_with(&memory, addr, quirks, &address) {
+                let len = instruction.len as usize;
+                lines.push(Line::Code {
+                    addr: addr as u16,
+                    bytes: memory[addr..addr + len].to_vec(),
+                    instruction,
+                });
+                addr += len;
+                continue;
+            }
+        }
+
+        // Data runs end at the next instruction or label
+        let start = addr;
+        addr += 1;
+        while addr < end
+            && addr - start < DATA_BYTES_PER_LINE
+            && !code.contains_key(&addr)
+            && !labels.contains_key(&(addr as u16))
+        {
+            addr += 1;
+        }
+        lines.push(Line::Data {
+            addr: start as u16,
+            bytes: memory[start..addr].to_vec(),
+        });
+    }
+
+    Listing { lines, labels }
+}
+
+impl fmt::Display for Listing {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for line in &self.lines {
+            if let Some(label) = self.labels.get(&line.addr()) {
+                writeln!(f, "{}:", label)?;
+            }
+
+            let (addr, bytes, text) = match line {
+                Line::Code {
+                    addr,
+                    bytes,
+                    instruction,
+                } => (*addr, bytes, instruction.mnemonic.clone()),
+                Line::Data { addr, bytes } => {
+                    let values: Vec<String> =
+                        bytes.iter().map(|b| format!("0x{:02X}", b)).collect();
+                    (*addr, bytes, format!("DB {}", values.join(", ")))
+                }
+            };
+            let hex: String = bytes.iter().map(|b| format!("{:02X}", b)).collect();
+            writeln!(f, "    {:04X}  {:<8}  {}", addr, hex, text)?;
+        }
+        Ok(())
+    }
+}
@@ -1,10 +1,27 @@
 // src/emu.rs
 use crate::constants::*; // constants.rs'deki verileri al
+use crate::disasm;
+use crate::error::Chip8Error;
+use crate::quirks::{IndexIncrement, Quirks};
+use crate::rng::Rng;
+use crate::state::{rom_hash, Snapshot, StateError};
+use crate::trace::{Registers, Tracer};
 
+/// A complete CHIP-8 machine: CPU registers, memory, display and timers.
+///
+/// SUPER-CHIP 1.1 instructions are always available; the display starts in
+/// 64x32 low resolution and `00FF` switches it to 128x64. XO-CHIP is enabled
+/// through [`Quirks::xo_chip`], which also grows memory to 64K.
+///
+/// The frontend drives it by calling [`Emu::tick`] a number of times per
+/// frame and [`Emu::tick_timers`] once per frame (60 Hz).
 pub struct Emu {
     pc: u16,
-    ram: [u8; RAM_SIZE],
-    screen: [bool; SCREEN_WIDTH * SCREEN_HEIGHT],
+    op_pc: u16, // Address of the instruction being executed
+    ram: Vec<u8>,
+    screen: Vec<u8>, // One bit per bitplane for every pixel
+    hires: bool,
+    planes: u8, // Bitplanes selected by FN01 (bit 0 = plane 1)
     v_reg: [u8; REGISTERS_COUNT],
     i_reg: u16,
     sp: u16,
@@ -12,14 +29,56 @@ pub struct Emu {
     keys: [bool; 16],
     delay_timer: u8,
     sound_timer: u8,
+    quirks: Quirks,
+    vblank: bool, // Set by tick_timers, consumed by DXYN under the display wait quirk
+    rpl_flags: [u8; RPL_FLAGS_COUNT],
+    exited: bool, // Set by 00FD
+    audio_pattern: [u8; AUDIO_PATTERN_SIZE],
+    pitch: u8,
+    rom_hash: u64, // Identifies the loaded ROM in save states
+    rng: Rng,
+    tracer: Option<Tracer>,              // Not part of the machine state
+    accesses: Option<Vec<MemoryAccess>>, // Data accesses of the last instruction, when recording
+}
+
+/// Whether an instruction read or wrote memory.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AccessKind {
+    Read,
+    Write,
+}
+
+/// A memory access made by an instruction, e.g. one of the bytes `FX55`
+/// stores or a sprite row `DXYN` draws. Instruction fetches are not included.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MemoryAccess {
+    pub addr: u16,
+    pub kind: AccessKind,
+    pub value: u8,
+}
+
+impl Default for Emu {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Emu {
+    /// Creates a machine with cleared memory and the font loaded, using the
+    /// default (modern) quirks.
     pub fn new() -> Self {
+        Self::with_quirks(Quirks::default())
+    }
+
+    /// Creates a machine that interprets ambiguous opcodes according to `quirks`.
+    pub fn with_quirks(quirks: Quirks) -> Self {
         let mut new_emu = Emu {
-            pc: 0x200,
-            ram: [0; RAM_SIZE],
-            screen: [false; SCREEN_WIDTH * SCREEN_HEIGHT],
+            pc: START_ADDR,
+            op_pc: START_ADDR,
+            ram: vec![0; Self::memory_size(&quirks)],
+            screen: vec![0; SCREEN_WIDTH * SCREEN_HEIGHT],
+            hires: false,
+            planes: 1,
             v_reg: [0; REGISTERS_COUNT],
             i_reg: 0,
             sp: 0,
@@ -27,10 +86,21 @@ impl Emu {
             keys: [false; 16],
             delay_timer: 0,
             sound_timer: 0,
+            quirks,
+            vblank: false,
+            rpl_flags: [0; RPL_FLAGS_COUNT],
+            exited: false,
+            audio_pattern: DEFAULT_AUDIO_PATTERN,
+            pitch: 64,
+            rom_hash: rom_hash(&[]),
+            rng: Rng::default(),
+            tracer: None,
+            accesses: None,
         };
 
         // Fontları yükle
         new_emu.ram[..80].copy_from_slice(&FONTSET);
+        new_emu.ram[BIG_FONT_ADDR..BIG_FONT_ADDR + BIG_FONTSET.len()].copy_from_slice(&BIG_FONTSET);
 
         // DİKKAT: Buradaki eski test kodlarını (0xC0, 0xFF vb.) SİLDİM.
         // Artık tertemiz bir hafıza ile başlıyor.
@@ -38,19 +108,128 @@ impl Emu {
         new_emu
     }
 
-    pub fn tick(&mut self) {
-        let op = self.fetch();
-        self.execute(op);
+    fn memory_size(quirks: &Quirks) -> usize {
+        if quirks.xo_chip {
+            XO_RAM_SIZE
+        } else {
+            RAM_SIZE
+        }
     }
 
-    fn fetch(&mut self) -> u16 {
-        let high_byte = self.ram[self.pc as usize] as u16;
-        let low_byte = self.ram[(self.pc + 1) as usize] as u16;
-        self.pc += 2;
-        (high_byte << 8) | low_byte
+    /// Fetches and executes a single instruction.
+    ///
+    /// On error the machine state is left as it was when the fault occurred;
+    /// calling `tick` again will generally fail the same way.
+    pub fn tick(&mut self) -> Result<(), Chip8Error> {
+        if self.exited {
+            return Ok(());
+        }
+        if let Some(accesses) = &mut self.accesses {
+            accesses.clear();
+        }
+        if self.tracer.as_ref().is_some_and(|t| t.wants(self.pc)) {
+            return self.traced_tick();
+        }
+        let op = self.fetch()?;
+        self.execute(op)
     }
 
-    fn execute(&mut self, op: u16) {
+    fn traced_tick(&mut self) -> Result<(), Chip8Error> {
+        let pc = self.pc;
+        let before = self.trace_registers();
+        // Decoded up front, the instruction may overwrite itself
+        let (opcode, mnemonic) = match disasm::decode(&self.ram, pc as usize, &self.quirks) {
+            Some(instruction) => (instruction.opcode, instruction.mnemonic),
+            None => (0, String::new()),
+        };
+
+        let result = self.fetch().and_then(|op| self.execute(op));
+
+        let after = self.trace_registers();
+        if let Some(tracer) = &mut self.tracer {
+            tracer.record(
+                pc,
+                opcode,
+                &mnemonic,
+                &before,
+                &after,
+                result.as_ref().err(),
+            );
+        }
+        result
+    }
+
+    fn trace_registers(&self) -> Registers {
+        Registers {
+            v: self.v_reg,
+            i: self.i_reg,
+            sp: self.sp,
+            delay_timer: self.delay_timer,
+            sound_timer: self.sound_timer,
+        }
+    }
+
+    fn fetch(&mut self) -> Result<u16, Chip8Error> {
+        self.op_pc = self.pc;
+        let high_byte = self.fetch_byte(self.pc as usize)? as u16;
+        let low_byte = self.fetch_byte(self.pc as usize + 1)? as u16;
+        self.pc = self.pc.wrapping_add(2);
+        Ok((high_byte << 8) | low_byte)
+    }
+
+    // Skips the next instruction, which is 4 bytes long if it is XO-CHIP's F000 NNNN
+    fn skip_next(&mut self) {
+        let pc = self.pc as usize;
+        let long = self.quirks.xo_chip
+            && self.ram.get(pc) == Some(&0xF0)
+            && self.ram.get(pc + 1) == Some(&0x00);
+        self.pc = self.pc.wrapping_add(if long { 4 } else { 2 });
+    }
+
+    // Reads part of an instruction, which does not count as a data access
+    fn fetch_byte(&self, addr: usize) -> Result<u8, Chip8Error> {
+        self.ram
+            .get(addr)
+            .copied()
+            .ok_or(Chip8Error::MemoryOutOfBounds {
+                addr,
+                pc: self.op_pc,
+            })
+    }
+
+    // All data reads and writes of `execute` go through these two so
+    // watchpoints can see them
+    fn read_ram(&mut self, addr: usize) -> Result<u8, Chip8Error> {
+        let value = self.fetch_byte(addr)?;
+        self.record_access(addr, AccessKind::Read, value);
+        Ok(value)
+    }
+
+    fn write_ram(&mut self, addr: usize, value: u8) -> Result<(), Chip8Error> {
+        match self.ram.get_mut(addr) {
+            Some(cell) => {
+                *cell = value;
+                self.record_access(addr, AccessKind::Write, value);
+                Ok(())
+            }
+            None => Err(Chip8Error::MemoryOutOfBounds {
+                addr,
+                pc: self.op_pc,
+            }),
+        }
+    }
+
+    fn record_access(&mut self, addr: usize, kind: AccessKind, value: u8) {
+        if let Some(accesses) = &mut self.accesses {
+            accesses.push(MemoryAccess {
+                addr: addr as u16,
+                kind,
+                value,
+            });
+        }
+    }
+
+    fn execute(&mut self, op: u16) -> Result<(), Chip8Error> {
         let digit1 = (op & 0xF000) >> 12;
         let digit2 = (op & 0x0F00) >> 8;
         let digit3 = (op & 0x00F0) >> 4;
@@ -58,17 +237,54 @@ impl Emu {
 
         match (digit1, digit2, digit3, digit4) {
             // 0000 - NOP (No Operation)
-            (0, 0, 0, 0) => return,
+            (0, 0, 0, 0) => (),
 
             // 00E0 - CLS (Clear Screen)
             (0, 0, 0xE, 0) => {
-                self.screen = [false; SCREEN_WIDTH * SCREEN_HEIGHT];
+                // Only the selected bitplanes are cleared
+                let planes = self.planes;
+                self.screen.iter_mut().for_each(|px| *px &= !planes);
             } // <-- VİRGÜLLER EKLENDİ
 
+            // 00CN - Scroll display down N pixels (SUPER-CHIP)
+            (0, 0, 0xC, n) => {
+                self.scroll(0, n as isize);
+            }
+
+            // 00DN - Scroll display up N pixels (XO-CHIP)
+            (0, 0, 0xD, n) if self.quirks.xo_chip => {
+                self.scroll(0, -(n as isize));
+            }
+
+            // 00FB - Scroll display right 4 pixels (SUPER-CHIP)
+            (0, 0, 0xF, 0xB) => {
+                self.scroll(4, 0);
+            }
+
+            // 00FC - Scroll display left 4 pixels (SUPER-CHIP)
+            (0, 0, 0xF, 0xC) => {
+                self.scroll(-4, 0);
+            }
+
+            // 00FD - Exit interpreter (SUPER-CHIP)
+            (0, 0, 0xF, 0xD) => {
+                self.exited = true;
+            }
+
+            // 00FE - Low resolution 64x32 (SUPER-CHIP)
+            (0, 0, 0xF, 0xE) => {
+                self.set_hires(false);
+            }
+
+            // 00FF - High resolution 128x64 (SUPER-CHIP)
+            (0, 0, 0xF, 0xF) => {
+                self.set_hires(true);
+            }
+
             // 00EE - RET (Return from subroutine)
             (0, 0, 0xE, 0xE) => {
                 if self.sp == 0 {
-                    panic!("Stack Underflow! No return address found.");
+                    return Err(Chip8Error::StackUnderflow { pc: self.op_pc });
                 }
                 self.sp -= 1;
                 self.pc = self.stack[self.sp as usize];
@@ -83,6 +299,9 @@ impl Emu {
             // 2NNN - CALL NNN (Call subroutine at NNN)
             (2, _, _, _) => {
                 let nnn = op & 0x0FFF;
+                if self.sp as usize >= STACK_SIZE {
+                    return Err(Chip8Error::StackOverflow { pc: self.op_pc });
+                }
                 self.stack[self.sp as usize] = self.pc;
                 self.sp += 1;
                 self.pc = nnn;
@@ -93,7 +312,7 @@ impl Emu {
                 let x = digit2 as usize;
                 let nn = (op & 0x00FF) as u8;
                 if self.v_reg[x] == nn {
-                    self.pc += 2;
+                    self.skip_next();
                 }
             }
 
@@ -102,7 +321,7 @@ impl Emu {
                 let x = digit2 as usize;
                 let nn = (op & 0x00FF) as u8;
                 if self.v_reg[x] != nn {
-                    self.pc += 2;
+                    self.skip_next();
                 }
             }
 
@@ -111,7 +330,23 @@ impl Emu {
                 let x = digit2 as usize;
                 let y = digit3 as usize;
                 if self.v_reg[x] == self.v_reg[y] {
-                    self.pc += 2;
+                    self.skip_next();
+                }
+            }
+
+            // 5XY2 - Save VX..VY to memory starting at I, I unchanged (XO-CHIP)
+            (5, _, _, 2) if self.quirks.xo_chip => {
+                let base = self.i_reg as usize;
+                for (offset, reg) in Self::register_range(digit2, digit3).enumerate() {
+                    self.write_ram(base + offset, self.v_reg[reg])?;
+                }
+            }
+
+            // 5XY3 - Load VX..VY from memory starting at I, I unchanged (XO-CHIP)
+            (5, _, _, 3) if self.quirks.xo_chip => {
+                let base = self.i_reg as usize;
+                for (offset, reg) in Self::register_range(digit2, digit3).enumerate() {
+                    self.v_reg[reg] = self.read_ram(base + offset)?;
                 }
             }
 
@@ -141,6 +376,9 @@ impl Emu {
                 let x = digit2 as usize;
                 let y = digit3 as usize;
                 self.v_reg[x] |= self.v_reg[y];
+                if self.quirks.vf_reset {
+                    self.v_reg[0xF] = 0;
+                }
             }
 
             // 8XY2 - AND VX, VY
@@ -148,6 +386,9 @@ impl Emu {
                 let x = digit2 as usize;
                 let y = digit3 as usize;
                 self.v_reg[x] &= self.v_reg[y];
+                if self.quirks.vf_reset {
+                    self.v_reg[0xF] = 0;
+                }
             }
 
             // 8XY3 - XOR VX, VY
@@ -155,6 +396,9 @@ impl Emu {
                 let x = digit2 as usize;
                 let y = digit3 as usize;
                 self.v_reg[x] ^= self.v_reg[y];
+                if self.quirks.vf_reset {
+                    self.v_reg[0xF] = 0;
+                }
             }
 
             // 8XY4 - ADD (Carry)
@@ -166,34 +410,49 @@ impl Emu {
                 self.v_reg[0xF] = if overflow { 1 } else { 0 };
             }
 
-            // 8XY5 - SUB (Borrow)
+            // 8XY5 - SUB, VF = 1 when there is no borrow (VX >= VY)
             (8, _, _, 5) => {
                 let x = digit2 as usize;
                 let y = digit3 as usize;
-                self.v_reg[0xF] = if self.v_reg[x] > self.v_reg[y] { 1 } else { 0 };
+                let no_borrow = self.v_reg[x] >= self.v_reg[y];
                 self.v_reg[x] = self.v_reg[x].wrapping_sub(self.v_reg[y]);
+                // Flag last, so it wins when X is F
+                self.v_reg[0xF] = if no_borrow { 1 } else { 0 };
             }
 
             // 8XY6 - SHR
             (8, _, _, 6) => {
                 let x = digit2 as usize;
-                self.v_reg[0xF] = self.v_reg[x] & 0x1;
-                self.v_reg[x] >>= 1;
+                let y = digit3 as usize;
+                let value = if self.quirks.shift_uses_vy {
+                    self.v_reg[y]
+                } else {
+                    self.v_reg[x]
+                };
+                self.v_reg[x] = value >> 1;
+                self.v_reg[0xF] = value & 0x1;
             }
 
-            // 8XY7 - SUBN
+            // 8XY7 - SUBN, VF = 1 when there is no borrow (VY >= VX)
             (8, _, _, 7) => {
                 let x = digit2 as usize;
                 let y = digit3 as usize;
-                self.v_reg[0xF] = if self.v_reg[y] > self.v_reg[x] { 1 } else { 0 };
+                let no_borrow = self.v_reg[y] >= self.v_reg[x];
                 self.v_reg[x] = self.v_reg[y].wrapping_sub(self.v_reg[x]);
+                self.v_reg[0xF] = if no_borrow { 1 } else { 0 };
             }
 
             // 8XYE - SHL
             (8, _, _, 0xE) => {
                 let x = digit2 as usize;
-                self.v_reg[0xF] = (self.v_reg[x] >> 7) & 0x1;
-                self.v_reg[x] <<= 1;
+                let y = digit3 as usize;
+                let value = if self.quirks.shift_uses_vy {
+                    self.v_reg[y]
+                } else {
+                    self.v_reg[x]
+                };
+                self.v_reg[x] = value << 1;
+                self.v_reg[0xF] = (value >> 7) & 0x1;
             }
 
             // 9XY0 - Skip next instruction if VX != VY
@@ -201,7 +460,7 @@ impl Emu {
                 let x = digit2 as usize;
                 let y = digit3 as usize;
                 if self.v_reg[x] != self.v_reg[y] {
-                    self.pc += 2;
+                    self.skip_next();
                 }
             }
 
@@ -211,60 +470,128 @@ impl Emu {
                 self.i_reg = nnn;
             }
 
+            // BNNN - Jump to NNN + V0 (BXNN - jump to XNN + VX with the jump quirk)
+            (0xB, _, _, _) => {
+                let nnn = op & 0x0FFF;
+                let offset = if self.quirks.jump_uses_vx {
+                    self.v_reg[digit2 as usize]
+                } else {
+                    self.v_reg[0]
+                };
+                self.pc = nnn + offset as u16;
+            }
+
             // CXNN - Random
             (0xC, _, _, _) => {
                 let x = digit2 as usize;
                 let nn = (op & 0x00FF) as u8;
-                let rng: u8 = rand::random(); // Yeni rand kullanımı
-                self.v_reg[x] = rng & nn;
+                self.v_reg[x] = self.rng.next_byte() & nn;
             }
 
-            // DXYN - DRAW
+            // DXYN - DRAW (DXY0 draws a 16x16 sprite, SUPER-CHIP)
             (0xD, _, _, _) => {
                 let x_idx = digit2 as usize;
                 let y_idx = digit3 as usize;
-                let height = digit4 as u16;
+                let (sprite_width, height) = if digit4 == 0 {
+                    (16, 16)
+                } else {
+                    (8, digit4 as usize)
+                };
 
-                let x_coord = self.v_reg[x_idx] as u16;
-                let y_coord = self.v_reg[y_idx] as u16;
+                // On the VIP the interpreter waits for the vertical blank interrupt
+                // before drawing, so at most one sprite is drawn per frame
+                if self.quirks.display_wait {
+                    if !self.vblank {
+                        self.pc = self.pc.wrapping_sub(2);
+                        return Ok(());
+                    }
+                    self.vblank = false;
+                }
+
+                let width = self.width();
+                let screen_height = self.height();
+
+                // The starting position always wraps; the sprite itself is then
+                // clipped or wrapped at the edges depending on the quirk
+                let x_coord = self.v_reg[x_idx] as usize % width;
+                let y_coord = self.v_reg[y_idx] as usize % screen_height;
 
                 self.v_reg[0xF] = 0;
 
-                for row in 0..height {
-                    let sprite_byte = self.ram[(self.i_reg + row) as usize];
-                    for col in 0..8 {
-                        let pixel_bit = (sprite_byte >> (7 - col)) & 1;
-                        if pixel_bit != 0 {
-                            let draw_x = (x_coord + col) as usize;
-                            let draw_y = (y_coord + row) as usize;
-
-                            if draw_x < SCREEN_WIDTH && draw_y < SCREEN_HEIGHT {
-                                let idx = draw_y * SCREEN_WIDTH + draw_x;
-                                if self.screen[idx] {
-                                    self.v_reg[0xF] = 1;
+                // Each selected bitplane takes its own sprite data, one after the other
+                let bytes_per_row = sprite_width / 8;
+                let mut addr = self.i_reg as usize;
+                for plane in 0..PLANE_COUNT {
+                    let mask = 1 << plane;
+                    if self.planes & mask == 0 {
+                        continue;
+                    }
+
+                    for row in 0..height {
+                        let row_addr = addr + row * bytes_per_row;
+                        let mut sprite_row = self.read_ram(row_addr)? as u16;
+                        if bytes_per_row == 2 {
+                            sprite_row = (sprite_row << 8) | self.read_ram(row_addr + 1)? as u16;
+                        }
+                        for col in 0..sprite_width {
+                            let pixel_bit = (sprite_row >> (sprite_width - 1 - col)) & 1;
+                            if pixel_bit != 0 {
+                                let mut draw_x = x_coord + col;
+                                let mut draw_y = y_coord + row;
+                                if self.quirks.wrap_sprites {
+                                    draw_x %= width;
+                                    draw_y %= screen_height;
+                                }
+
+                                if draw_x < width && draw_y < screen_height {
+                                    let idx = draw_y * width + draw_x;
+                                    if self.screen[idx] & mask != 0 {
+                                        self.v_reg[0xF] = 1;
+                                    }
+                                    self.screen[idx] ^= mask;
                                 }
-                                self.screen[idx] ^= true;
                             }
                         }
                     }
+                    addr += height * bytes_per_row;
                 }
             }
 
             // EX9E - Skip next instruction if key stored in VX is pressed
             (0xE, _, 9, 0xE) => {
                 let x = digit2 as usize;
-                let key = self.v_reg[x] as usize;
+                let key = (self.v_reg[x] & 0xF) as usize;
                 if self.keys[key] {
-                    self.pc += 2;
+                    self.skip_next();
                 }
             }
 
             // EXA1 - Skip next instruction if key stored in VX is NOT pressed
             (0xE, _, 0xA, 1) => {
                 let x = digit2 as usize;
-                let key = self.v_reg[x] as usize;
+                let key = (self.v_reg[x] & 0xF) as usize;
                 if !self.keys[key] {
-                    self.pc += 2;
+                    self.skip_next();
+                }
+            }
+
+            // F000 NNNN - Set I = NNNN, a 16-bit address (XO-CHIP)
+            (0xF, 0, 0, 0) if self.quirks.xo_chip => {
+                let high_byte = self.fetch_byte(self.pc as usize)? as u16;
+                let low_byte = self.fetch_byte(self.pc as usize + 1)? as u16;
+                self.pc = self.pc.wrapping_add(2);
+                self.i_reg = (high_byte << 8) | low_byte;
+            }
+
+            // FN01 - Select bitplanes N for drawing, clearing and scrolling (XO-CHIP)
+            (0xF, n, 0, 1) if self.quirks.xo_chip => {
+                self.planes = n as u8 & 0b11;
+            }
+
+            // F002 - Load the 16-byte audio pattern from memory at I (XO-CHIP)
+            (0xF, 0, 0, 2) if self.quirks.xo_chip => {
+                for i in 0..AUDIO_PATTERN_SIZE {
+                    self.audio_pattern[i] = self.read_ram(self.i_reg as usize + i)?;
                 }
             }
 
@@ -288,7 +615,7 @@ impl Emu {
                 // If no key is pressed, decrement PC to repeat this instruction
                 // Effectively pausing the CPU until a key is pressed
                 if !pressed {
-                    self.pc -= 2;
+                    self.pc = self.pc.wrapping_sub(2);
                 }
             }
 
@@ -313,62 +640,288 @@ impl Emu {
             // FX29 - Set I = location of sprite for digit VX
             (0xF, _, 2, 9) => {
                 let x = digit2 as usize;
-                let digit = self.v_reg[x] as u16;
+                let digit = (self.v_reg[x] & 0xF) as u16;
                 // Fonts are located at the start of RAM (0x000), 5 bytes each
                 self.i_reg = digit * 5;
             }
 
+            // FX30 - Set I = location of the large sprite for digit VX (SUPER-CHIP)
+            (0xF, _, 3, 0) => {
+                let x = digit2 as usize;
+                let digit = (self.v_reg[x] & 0xF) as usize;
+                self.i_reg = (BIG_FONT_ADDR + digit * 10) as u16;
+            }
+
+            // FX3A - Set audio pitch = VX (XO-CHIP)
+            (0xF, _, 3, 0xA) if self.quirks.xo_chip => {
+                self.pitch = self.v_reg[digit2 as usize];
+            }
+
             // FX33 - Store BCD representation of VX in memory locations I, I+1, and I+2
             // (Binary Coded Decimal: 156 -> 1, 5, 6)
             (0xF, _, 3, 3) => {
                 let x = digit2 as usize;
                 let value = self.v_reg[x];
+                let addr = self.i_reg as usize;
                 // Hundreds place
-                self.ram[self.i_reg as usize] = value / 100;
+                self.write_ram(addr, value / 100)?;
                 // Tens place
-                self.ram[(self.i_reg + 1) as usize] = (value / 10) % 10;
+                self.write_ram(addr + 1, (value / 10) % 10)?;
                 // Ones place
-                self.ram[(self.i_reg + 2) as usize] = value % 10;
+                self.write_ram(addr + 2, value % 10)?;
             }
 
             // FX55 - Store registers V0 through VX in memory starting at location I
             (0xF, _, 5, 5) => {
                 let x = digit2 as usize;
                 for i in 0..=x {
-                    self.ram[self.i_reg as usize + i] = self.v_reg[i];
+                    self.write_ram(self.i_reg as usize + i, self.v_reg[i])?;
                 }
+                self.advance_i_after_load_store(x);
             }
 
             // FX65 - Read registers V0 through VX from memory starting at location I
             (0xF, _, 6, 5) => {
                 let x = digit2 as usize;
                 for i in 0..=x {
-                    self.v_reg[i] = self.ram[self.i_reg as usize + i];
+                    self.v_reg[i] = self.read_ram(self.i_reg as usize + i)?;
                 }
+                self.advance_i_after_load_store(x);
+            }
+
+            // FX75 - Store V0 through VX in the RPL user flags (SUPER-CHIP)
+            (0xF, _, 7, 5) => {
+                let x = digit2 as usize;
+                self.rpl_flags[..=x].copy_from_slice(&self.v_reg[..=x]);
+            }
+
+            // FX85 - Read V0 through VX from the RPL user flags (SUPER-CHIP)
+            (0xF, _, 8, 5) => {
+                let x = digit2 as usize;
+                self.v_reg[..=x].copy_from_slice(&self.rpl_flags[..=x]);
             }
 
             // Unhandled Opcode
-            _ => println!("Unimplemented Opcode: {:04X}", op),
+            _ => {
+                return Err(Chip8Error::UnknownOpcode {
+                    opcode: op,
+                    pc: self.op_pc,
+                })
+            }
+        }
+
+        Ok(())
+    }
+
+    fn set_hires(&mut self, hires: bool) {
+        self.hires = hires;
+        self.screen = vec![0; self.width() * self.height()];
+    }
+
+    // Moves the selected bitplanes by (dx, dy) hi-res pixels; positive values
+    // scroll right/down
+    fn scroll(&mut self, dx: isize, dy: isize) {
+        // SUPER-CHIP 1.1 scrolls the 128x64 screen it always draws on, which is
+        // half as far in lo-res pixels; XO-CHIP scrolls whole lo-res pixels
+        let (dx, dy) = if self.hires || self.quirks.xo_chip {
+            (dx, dy)
+        } else {
+            (dx / 2, dy / 2)
+        };
+        let width = self.width() as isize;
+        let height = self.height() as isize;
+        let planes = self.planes;
+        let old = self.screen.clone();
+        for y in 0..height {
+            for x in 0..width {
+                let (src_x, src_y) = (x - dx, y - dy);
+                let src = if (0..width).contains(&src_x) && (0..height).contains(&src_y) {
+                    old[(src_y * width + src_x) as usize]
+                } else {
+                    0
+                };
+                let idx = (y * width + x) as usize;
+                self.screen[idx] = (old[idx] & !planes) | (src & planes);
+            }
         }
     }
 
-    pub fn get_display(&self) -> &[bool] {
+    // Registers touched by 5XY2/5XY3, in order from X to Y (which may count down)
+    fn register_range(x: u16, y: u16) -> Box<dyn Iterator<Item = usize>> {
+        let (x, y) = (x as usize, y as usize);
+        if x <= y {
+            Box::new(x..=y)
+        } else {
+            Box::new((y..=x).rev())
+        }
+    }
+
+    fn advance_i_after_load_store(&mut self, x: usize) {
+        match self.quirks.load_store {
+            IndexIncrement::Unchanged => {}
+            IndexIncrement::ByX => self.i_reg = self.i_reg.wrapping_add(x as u16),
+            IndexIncrement::ByXPlusOne => self.i_reg = self.i_reg.wrapping_add(x as u16 + 1),
+        }
+    }
+
+    /// Returns the framebuffer, row-major, `width() * height()` pixels.
+    ///
+    /// Each pixel is a colour index 0-3 with one bit per bitplane: 0 is off,
+    /// 1 is lit in plane 1. Plain CHIP-8 and SUPER-CHIP programs only
+    /// produce 0 and 1; XO-CHIP programs can light plane 2 (2) or both (3).
+    pub fn get_display(&self) -> &[u8] {
         &self.screen
     }
 
-    pub fn load_rom(&mut self, data: &[u8]) {
-        let start = 0x200;
-        let end = 0x200 + data.len();
+    /// Width of the active display mode in pixels.
+    pub fn width(&self) -> usize {
+        if self.hires {
+            HIRES_SCREEN_WIDTH
+        } else {
+            SCREEN_WIDTH
+        }
+    }
 
-        if end > RAM_SIZE {
-            panic!("ROM too large for RAM");
+    /// Height of the active display mode in pixels.
+    pub fn height(&self) -> usize {
+        if self.hires {
+            HIRES_SCREEN_HEIGHT
+        } else {
+            SCREEN_HEIGHT
+        }
+    }
+
+    /// Whether the SUPER-CHIP 128x64 mode is active.
+    pub fn is_hires(&self) -> bool {
+        self.hires
+    }
+
+    /// True once the program has executed `00FD`; further ticks do nothing.
+    pub fn has_exited(&self) -> bool {
+        self.exited
+    }
+
+    /// The SUPER-CHIP RPL user flags written by `FX75`.
+    ///
+    /// These survive between runs on the HP-48; frontends persist them and
+    /// restore them with [`Emu::set_rpl_flags`] before the program starts.
+    pub fn rpl_flags(&self) -> &[u8] {
+        &self.rpl_flags
+    }
+
+    /// Restores previously saved RPL user flags. Extra bytes are ignored.
+    pub fn set_rpl_flags(&mut self, flags: &[u8]) {
+        let n = flags.len().min(RPL_FLAGS_COUNT);
+        self.rpl_flags[..n].copy_from_slice(&flags[..n]);
+    }
+
+    /// Copies a program into memory at `START_ADDR`.
+    pub fn load_rom(&mut self, data: &[u8]) -> Result<(), Chip8Error> {
+        let start = START_ADDR as usize;
+        let end = start + data.len();
+
+        if end > self.ram.len() {
+            return Err(Chip8Error::RomTooLarge {
+                size: data.len(),
+                max: self.ram.len() - start,
+            });
         }
 
         self.ram[start..end].copy_from_slice(data);
+        self.rom_hash = rom_hash(data);
+        Ok(())
+    }
+
+    /// Hash of the loaded ROM (see [`crate::state::rom_hash`]).
+    pub fn rom_hash(&self) -> u64 {
+        self.rom_hash
+    }
+
+    /// Captures the complete machine state (everything except the quirks).
+    pub fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            rom_hash: self.rom_hash,
+            pc: self.pc,
+            i_reg: self.i_reg,
+            sp: self.sp,
+            v_reg: self.v_reg,
+            stack: self.stack,
+            delay_timer: self.delay_timer,
+            sound_timer: self.sound_timer,
+            keys: self.keys,
+            hires: self.hires,
+            planes: self.planes,
+            vblank: self.vblank,
+            exited: self.exited,
+            pitch: self.pitch,
+            audio_pattern: self.audio_pattern,
+            rpl_flags: self.rpl_flags,
+            width: self.width() as u16,
+            height: self.height() as u16,
+            screen: self.screen.clone(),
+            ram: self.ram.clone(),
+            rng: self.rng,
+        }
+    }
+
+    /// Restores a snapshot taken from this ROM. The machine is untouched if
+    /// the snapshot is rejected.
+    pub fn restore(&mut self, snapshot: Snapshot) -> Result<(), StateError> {
+        if snapshot.rom_hash != self.rom_hash {
+            return Err(StateError::RomMismatch {
+                expected: self.rom_hash,
+                found: snapshot.rom_hash,
+            });
+        }
+        if snapshot.ram.len() != self.ram.len() {
+            return Err(StateError::Corrupt("memory size does not match the quirks"));
+        }
+        let (width, height) = if snapshot.hires {
+            (HIRES_SCREEN_WIDTH, HIRES_SCREEN_HEIGHT)
+        } else {
+            (SCREEN_WIDTH, SCREEN_HEIGHT)
+        };
+        if (snapshot.width as usize, snapshot.height as usize) != (width, height) {
+            return Err(StateError::Corrupt(
+                "screen size does not match the display mode",
+            ));
+        }
+
+        self.pc = snapshot.pc;
+        self.op_pc = snapshot.pc;
+        self.i_reg = snapshot.i_reg;
+        self.sp = snapshot.sp;
+        self.v_reg = snapshot.v_reg;
+        self.stack = snapshot.stack;
+        self.delay_timer = snapshot.delay_timer;
+        self.sound_timer = snapshot.sound_timer;
+        self.keys = snapshot.keys;
+        self.hires = snapshot.hires;
+        self.planes = snapshot.planes;
+        self.vblank = snapshot.vblank;
+        self.exited = snapshot.exited;
+        self.pitch = snapshot.pitch;
+        self.audio_pattern = snapshot.audio_pattern;
+        self.rpl_flags = snapshot.rpl_flags;
+        self.screen = snapshot.screen;
+        self.ram = snapshot.ram;
+        self.rng = snapshot.rng;
+        Ok(())
+    }
+
+    /// Serializes the machine into the versioned save state format.
+    pub fn save_state(&self) -> Vec<u8> {
+        self.snapshot().encode()
+    }
+
+    /// Restores a state produced by [`Emu::save_state`] for the same ROM.
+    pub fn load_state(&mut self, data: &[u8]) -> Result<(), StateError> {
+        self.restore(Snapshot::decode(data)?)
     }
 
-    // Decrement timers (Should be called at 60Hz)
+    /// Decrements the delay and sound timers. Call once per frame (60 Hz).
     pub fn tick_timers(&mut self) {
+        self.vblank = true;
         if self.delay_timer > 0 {
             self.delay_timer -= 1;
         }
@@ -381,12 +934,125 @@ impl Emu {
         }
     }
 
-    // Update key state
+    /// Sets the state of hex key `idx` (0x0-0xF); other indices are ignored.
     pub fn keypress(&mut self, idx: usize, pressed: bool) {
-        self.keys[idx] = pressed;
+        if let Some(key) = self.keys.get_mut(idx) {
+            *key = pressed;
+        }
+    }
+
+    /// The quirks this machine runs with.
+    pub fn quirks(&self) -> &Quirks {
+        &self.quirks
+    }
+
+    /// Changes the quirks; takes effect from the next instruction.
+    ///
+    /// Toggling [`Quirks::xo_chip`] resizes memory, truncating anything past
+    /// 4K when it is turned off.
+    pub fn set_quirks(&mut self, quirks: Quirks) {
+        self.ram.resize(Self::memory_size(&quirks), 0);
+        self.quirks = quirks;
+    }
+
+    /// The random number generator used by `CXNN`.
+    pub fn rng(&self) -> &Rng {
+        &self.rng
+    }
+
+    /// Replaces the random number generator, e.g. to fix the seed so a run
+    /// can be reproduced. Machines start with [`Rng::default`] (seed 0).
+    pub fn set_rng(&mut self, rng: Rng) {
+        self.rng = rng;
     }
 
+    /// Attaches a tracer that logs every executed instruction, or detaches
+    /// it (which flushes its output) with `None`.
+    pub fn set_tracer(&mut self, tracer: Option<Tracer>) {
+        self.tracer = tracer;
+    }
+
+    /// The attached tracer, e.g. to switch it off and on at runtime.
+    pub fn tracer_mut(&mut self) -> Option<&mut Tracer> {
+        self.tracer.as_mut()
+    }
+
+    /// Starts or stops recording the memory accesses of each instruction,
+    /// see [`Emu::memory_accesses`].
+    pub fn set_access_recording(&mut self, enabled: bool) {
+        self.accesses = enabled.then(Vec::new);
+    }
+
+    /// The memory reads and writes of the last instruction executed by
+    /// [`Emu::tick`]; always empty unless recording is on.
+    pub fn memory_accesses(&self) -> &[MemoryAccess] {
+        self.accesses.as_deref().unwrap_or(&[])
+    }
+
+    /// The XO-CHIP audio pattern: 128 one-bit samples, played MSB first
+    /// while the sound timer is non-zero.
+    pub fn audio_pattern(&self) -> &[u8; AUDIO_PATTERN_SIZE] {
+        &self.audio_pattern
+    }
+
+    /// Rate at which the audio pattern is played, in bits per second,
+    /// as set by `FX3A` (4000 Hz at the default pitch of 64).
+    pub fn audio_playback_rate(&self) -> f32 {
+        4000.0 * 2f32.powf((self.pitch as f32 - 64.0) / 48.0)
+    }
+
+    /// Current sound timer; the buzzer sounds while it is non-zero.
     pub fn get_sound_timer(&self) -> u8 {
         self.sound_timer
     }
+
+    // --- Inspection (debugger) ---
+
+    /// Address of the next instruction to execute.
+    pub fn pc(&self) -> u16 {
+        self.pc
+    }
+
+    /// Address of the instruction executed last (or executing, during a tick).
+    pub fn op_pc(&self) -> u16 {
+        self.op_pc
+    }
+
+    pub fn i_reg(&self) -> u16 {
+        self.i_reg
+    }
+
+    /// Number of return addresses on the stack.
+    pub fn sp(&self) -> u16 {
+        self.sp
+    }
+
+    /// V0-VF.
+    pub fn v_regs(&self) -> &[u8; REGISTERS_COUNT] {
+        &self.v_reg
+    }
+
+    /// Return addresses currently on the stack, oldest first.
+    pub fn stack(&self) -> &[u16] {
+        &self.stack[..self.sp as usize]
+    }
+
+    pub fn delay_timer(&self) -> u8 {
+        self.delay_timer
+    }
+
+    /// Pressed state of hex keys 0x0-0xF.
+    pub fn keys(&self) -> &[bool; 16] {
+        &self.keys
+    }
+
+    /// The whole address space (4K, or 64K with XO-CHIP).
+    pub fn ram(&self) -> &[u8] {
+        &self.ram
+    }
+
+    /// XO-CHIP bitplanes selected by `FN01` (bit 0 = plane 1).
+    pub fn planes(&self) -> u8 {
+        self.planes
+    }
 }
@@ -0,0 +1,50 @@
+// src/error.rs
+use std::fmt;
+
+/// Errors raised by the interpreter while loading or running a program.
+///
+/// Runtime variants carry `pc`, the address of the instruction that failed,
+/// so a frontend can point at the offending code.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Chip8Error {
+    /// `2NNN` was executed with every stack slot already in use.
+    StackOverflow { pc: u16 },
+    /// `00EE` was executed with an empty stack.
+    StackUnderflow { pc: u16 },
+    /// An instruction fetch, read or write fell outside RAM.
+    MemoryOutOfBounds { addr: usize, pc: u16 },
+    /// The opcode is not part of the instruction set.
+    UnknownOpcode { opcode: u16, pc: u16 },
+    /// The ROM does not fit between the program start and the end of RAM.
+    RomTooLarge { size: usize, max: usize },
+}
+
+impl fmt::Display for Chip8Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Chip8Error::StackOverflow { pc } => write!(f, "stack overflow at PC {:#05X}", pc),
+            Chip8Error::StackUnderflow { pc } => {
+                write!(
+                    f,
+                    "stack underflow (RET with empty stack) at PC {:#05X}",
+                    pc
+                )
+            }
+            Chip8Error::MemoryOutOfBounds { addr, pc } => {
+                write!(
+                    f,
+                    "memory access out of bounds ({:#06X}) at PC {:#05X}",
+                    addr, pc
+                )
+            }
+            Chip8Error::UnknownOpcode { opcode, pc } => {
+                write!(f, "unknown opcode {:04X} at PC {:#05X}", opcode, pc)
+            }
+            Chip8Error::RomTooLarge { size, max } => {
+                write!(f, "ROM too large: {} bytes (max {})", size, max)
+            }
+        }
+    }
+}
+
+impl std::error::Error for Chip8Error {}
@@ -0,0 +1,210 @@
+// src/filter.rs
+//! CRT-style post-processing of the framebuffer: phosphor persistence,
+//! scanlines, a pixel grid and bloom.
+//!
+//! CHIP-8 programs erase and redraw their sprites with XOR, so on a screen
+//! with no afterglow they flicker. Persistence lets a pixel that turns off fade
+//! out over a few frames instead, as it did on the phosphor screens the games
+//! were written for. Everything runs on the CPU into a plain RGB buffer, so
+//! the filters work with any renderer, no GPU shaders needed.
+use crate::emu::Emu;
+use serde::{Deserialize, Serialize};
+
+/// Brightness left after the persistence time, relative to when the pixel
+/// turned off.
+const PERSISTENCE_FLOOR: f32 = 0.05;
+/// Brightness of the gaps between scanlines.
+const SCANLINE_LEVEL: f32 = 0.55;
+/// Brightness of the pixel grid lines.
+const GRID_LEVEL: f32 = 0.7;
+/// Light a lit pixel adds to the pixels next to it and diagonal to it.
+const BLOOM_NEAR: f32 = 0.12;
+const BLOOM_DIAGONAL: f32 = 0.05;
+
+/// Which filters are on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FilterSettings {
+    /// Frames a pixel takes to fade out after turning off; 0 turns it off at once.
+    pub persistence: u32,
+    /// Darkens the bottom of every pixel row, like the gaps between CRT scanlines.
+    pub scanlines: bool,
+    /// Darkens the edges of every pixel, like an LCD.
+    pub grid: bool,
+    /// Lets lit pixels glow onto their neighbours.
+    pub bloom: bool,
+}
+
+impl FilterSettings {
+    /// Whether any filter is on; if not, the screen can be drawn as is.
+    pub fn is_active(&self) -> bool {
+        self.persistence > 0 || self.scanlines || self.grid || self.bloom
+    }
+}
+
+/// The filtered screen, updated once per displayed frame.
+///
+/// ```
+/// use chip8_emu::filter::{Filter, FilterSettings};
+/// use chip8_emu::headless::DEFAULT_PALETTE;
+/// use chip8_emu::Emu;
+///
+/// let settings = FilterSettings { scanlines: true, ..Default::default() };
+/// let mut filter = Filter::new(settings);
+/// filter.update(&Emu::new(), &DEFAULT_PALETTE, 1);
+/// let rgb = filter.render(4);
+/// assert_eq!(rgb.len(), 64 * 4 * 32 * 4 * 3);
+/// ```
+#[derive(Debug, Clone)]
+pub struct Filter {
+    settings: FilterSettings,
+    width: usize,
+    height: usize,
+    glow: Vec<[f32; 3]>, // Colour of every CHIP-8 pixel, fading ones in between
+    background: [f32; 3],
+}
+
+impl Filter {
+    pub fn new(settings: FilterSettings) -> Self {
+        Self {
+            settings,
+            width: 0,
+            height: 0,
+            glow: Vec::new(),
+            background: [0.0; 3],
+        }
+    }
+
+    pub fn settings(&self) -> FilterSettings {
+        self.settings
+    }
+
+    /// Size of the screen taken by the last [`Filter::update`], in CHIP-8 pixels.
+    pub fn size(&self) -> (usize, usize) {
+        (self.width, self.height)
+    }
+
+    /// Takes the screen of `emu` in the colours of `palette` (indexed like
+    /// [`Emu::get_display`]) after `frames` emulated frames; 0 while paused,
+    /// so fading pixels hold still.
+    pub fn update(&mut self, emu: &Emu, palette: &[[u8; 3]; 4], frames: u32) {
+        self.update_display(emu.get_display(), emu.width(), palette, frames);
+    }
+
+    /// Like [`Filter::update`], for a screen `width` pixels wide that does not
+    /// come straight from an `Emu`, such as the output of
+    /// [`Deflicker`](crate::deflicker::Deflicker).
+    pub fn update_display(
+        &mut self,
+        display: &[u8],
+        width: usize,
+        palette: &[[u8; 3]; 4],
+        frames: u32,
+    ) {
+        let palette = palette.map(|c| c.map(|v| v as f32));
+        let height = display.len() / width.max(1);
+
+        // A new resolution starts without afterglow
+        if (self.width, self.height) != (width, height) {
+            self.width = width;
+            self.height = height;
+            self.glow = display
+                .iter()
+                .map(|&px| palette[(px & 3) as usize])
+                .collect();
+        }
+        self.background = palette[0];
+
+        let decay = if self.settings.persistence == 0 {
+            0.0
+        } else {
+            PERSISTENCE_FLOOR.powf(frames as f32 / self.settings.persistence as f32)
+        };
+        for (glow, &px) in self.glow.iter_mut().zip(display) {
+            let target = palette[(px & 3) as usize];
+            if px & 3 != 0 {
+                // Lighting up is instant
+                *glow = target;
+            } else {
+                for (g, t) in glow.iter_mut().zip(target) {
+                    *g = t + (*g - t) * decay;
+                }
+            }
+        }
+    }
+
+    /// The screen as RGB24 rows, each CHIP-8 pixel `scale` pixels wide.
+    pub fn render(&self, scale: usize) -> Vec<u8> {
+        let scale = scale.max(1);
+        let colors = if self.settings.bloom {
+            self.bloom()
+        } else {
+            self.glow.clone()
+        };
+
+        // Scanlines take the bottom third of a pixel row; lines need room
+        let dark_rows = match (self.settings.scanlines, scale) {
+            (true, 2..) => (scale / 3).max(1),
+            _ => 0,
+        };
+        let grid = self.settings.grid && scale >= 3;
+
+        let (width, height) = (self.width * scale, self.height * scale);
+        let mut out = Vec::with_capacity(width * height * 3);
+        for y in 0..height {
+            let row = y % scale;
+            let mut row_level = 1.0;
+            if row >= scale - dark_rows {
+                row_level *= SCANLINE_LEVEL;
+            }
+            if grid && row == scale - 1 {
+                row_level *= GRID_LEVEL;
+            }
+
+            let line = &colors[y / scale * self.width..][..self.width];
+            for x in 0..width {
+                let level = if grid && x % scale == scale - 1 {
+                    row_level * GRID_LEVEL
+                } else {
+                    row_level
+                };
+                let color = line[x / scale];
+                out.extend(color.map(|c| (c * level).round().clamp(0.0, 255.0) as u8));
+            }
+        }
+        out
+    }
+
+    // Adds the light of neighbouring pixels above the background
+    fn bloom(&self) -> Vec<[f32; 3]> {
+        let (width, height) = (self.width as isize, self.height as isize);
+        let light: Vec<[f32; 3]> = self
+            .glow
+            .iter()
+            .map(|c| [0, 1, 2].map(|i| (c[i] - self.background[i]).max(0.0)))
+            .collect();
+
+        let mut colors = self.glow.clone();
+        for y in 0..height {
+            for x in 0..width {
+                let color = &mut colors[(y * width + x) as usize];
+                for (dx, dy) in (-1..=1).flat_map(|dy| (-1..=1).map(move |dx| (dx, dy))) {
+                    let (nx, ny) = (x + dx, y + dy);
+                    if (dx, dy) == (0, 0) || nx < 0 || ny < 0 || nx >= width || ny >= height {
+                        continue;
+                    }
+                    let weight = if dx == 0 || dy == 0 {
+                        BLOOM_NEAR
+                    } else {
+                        BLOOM_DIAGONAL
+                    };
+                    let neighbour = light[(ny * width + nx) as usize];
+                    for (c, l) in color.iter_mut().zip(neighbour) {
+                        *c += l * weight;
+                    }
+                }
+            }
+        }
+        colors
+    }
+}
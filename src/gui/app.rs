@@ -1,11 +1,16 @@
 // src/gui/app.rs
+use chip8_emu::state::Snapshot;
+use chip8_emu::timing::{Timing, DEFAULT_IPS};
+use chip8_emu::QuirksProfile;
 use eframe::egui;
 use std::fs;
 use std::sync::{Arc, Mutex};
 
+use super::palette::{egui_colors, Palette};
 use super::settings::{Settings, ThemeType};
 use super::theme::{apply_custom_style, setup_custom_fonts, Theme};
 use super::widgets::{show_empty_state, show_footer, show_header, RomEntry};
+use crate::keymap::{Keymap, KEYPAD_LAYOUT};
 
 pub struct RomSelector {
     roms: Vec<RomEntry>,
@@ -13,6 +18,9 @@ pub struct RomSelector {
     theme: Theme,
     settings: Settings,
     show_settings: bool,
+    keymap_rom: Option<String>, // Key of the ROM whose keys are edited, None for the global keymap
+    capturing: Option<usize>,   // CHIP-8 key waiting for a key press
+    keymap_message: Option<String>,
 }
 
 impl RomSelector {
@@ -29,12 +37,45 @@ impl RomSelector {
         // Style'ı tema ile uygula
         apply_custom_style(&cc.egui_ctx, &theme);
 
-        Self {
+        let mut selector = Self {
             roms,
             selected_rom,
             theme,
             settings,
             show_settings: false,
+            keymap_rom: None,
+            capturing: None,
+            keymap_message: None,
+        };
+        selector.load_thumbnails(&cc.egui_ctx);
+        selector
+    }
+
+    /// Save state önizlemeleri - ekran renkleriyle çizilir
+    fn load_thumbnails(&mut self, ctx: &egui::Context) {
+        let palette = egui_colors(self.settings.display_colors());
+
+        for rom in &mut self.roms {
+            rom.thumbnails.clear();
+            for (slot, data) in crate::storage::list_state_slots(&rom.path) {
+                let Ok(snapshot) = Snapshot::decode(&data) else {
+                    continue;
+                };
+                let image = egui::ColorImage {
+                    size: [snapshot.width as usize, snapshot.height as usize],
+                    pixels: snapshot
+                        .screen
+                        .iter()
+                        .map(|&px| palette[(px & 3) as usize])
+                        .collect(),
+                };
+                let texture = ctx.load_texture(
+                    format!("{}-slot{}", rom.name, slot),
+                    image,
+                    egui::TextureOptions::NEAREST,
+                );
+                rom.thumbnails.push((slot, texture));
+            }
         }
     }
 
@@ -92,6 +133,7 @@ impl RomSelector {
                                     self.settings.theme = theme_type;
                                     self.theme = Theme::from_type(theme_type);
                                     apply_custom_style(ctx, &self.theme);
+                                    self.load_thumbnails(ctx);
                                     let _ = self.settings.save();
                                 }
 
@@ -125,6 +167,7 @@ impl RomSelector {
                                     self.settings.theme = theme_type;
                                     self.theme = Theme::from_type(theme_type);
                                     apply_custom_style(ctx, &self.theme);
+                                    self.load_thumbnails(ctx);
                                     let _ = self.settings.save();
                                 }
 
@@ -136,6 +179,74 @@ impl RomSelector {
                                 ui.add_space(5.0);
                             }
                         }
+
+                        ui.add_space(15.0);
+                        ui.label(
+                            egui::RichText::new("Display Colours")
+                                .size(16.0)
+                                .color(self.theme.primary)
+                                .strong(),
+                        );
+                        ui.add_space(5.0);
+                        self.show_palette_settings(ui);
+
+                        ui.add_space(15.0);
+                        ui.label(
+                            egui::RichText::new("Display Filter")
+                                .size(16.0)
+                                .color(self.theme.primary)
+                                .strong(),
+                        );
+                        ui.add_space(5.0);
+                        self.show_filter_settings(ui);
+
+                        ui.add_space(15.0);
+                        ui.label(
+                            egui::RichText::new("Quirks Profile")
+                                .size(16.0)
+                                .color(self.theme.primary)
+                                .strong(),
+                        );
+                        ui.add_space(5.0);
+
+                        for profile in QuirksProfile::all() {
+                            let is_selected = self.settings.quirks == profile;
+                            let button =
+                                egui::Button::new(egui::RichText::new(profile.name()).size(14.0))
+                                    .min_size(egui::vec2(350.0, 35.0));
+
+                            if ui.add(button).clicked() {
+                                self.settings.quirks = profile;
+                                let _ = self.settings.save();
+                            }
+
+                            if is_selected {
+                                ui.label(
+                                    egui::RichText::new("  ✓ Active").color(self.theme.primary),
+                                );
+                            }
+                            ui.add_space(5.0);
+                        }
+
+                        ui.add_space(15.0);
+                        ui.label(
+                            egui::RichText::new("CPU Speed")
+                                .size(16.0)
+                                .color(self.theme.primary)
+                                .strong(),
+                        );
+                        ui.add_space(5.0);
+                        self.show_timing_settings(ui);
+
+                        ui.add_space(15.0);
+                        ui.label(
+                            egui::RichText::new("Keyboard")
+                                .size(16.0)
+                                .color(self.theme.primary)
+                                .strong(),
+                        );
+                        ui.add_space(5.0);
+                        self.show_keymap_settings(ui);
                     });
 
                 ui.add_space(10.0);
@@ -147,6 +258,261 @@ impl RomSelector {
                 }
             });
     }
+
+    // Keypad grid; clicking a key waits for the keyboard key to bind to it
+    fn show_keymap_settings(&mut self, ui: &mut egui::Ui) {
+        let roms: Vec<(String, String)> = self
+            .roms
+            .iter()
+            .map(|rom| (rom.key.clone(), crate::storage::rom_stem(&rom.path)))
+            .collect();
+        let selected = roms
+            .iter()
+            .find(|(key, _)| Some(key) == self.keymap_rom.as_ref())
+            .map_or("All ROMs", |(_, stem)| stem)
+            .to_string();
+        egui::ComboBox::from_label("Keys for")
+            .selected_text(selected)
+            .show_ui(ui, |ui| {
+                let mut changed = ui
+                    .selectable_value(&mut self.keymap_rom, None, "All ROMs")
+                    .changed();
+                for (key, stem) in roms {
+                    changed |= ui
+                        .selectable_value(&mut self.keymap_rom, Some(key), stem)
+                        .changed();
+                }
+                if changed {
+                    self.capturing = None;
+                    self.keymap_message = None;
+                }
+            });
+        ui.add_space(5.0);
+
+        let keymap = match &self.keymap_rom {
+            None => Some(&self.settings.keymap),
+            Some(key) => self
+                .settings
+                .roms
+                .get(key)
+                .and_then(|rom| rom.keymap.as_ref()),
+        };
+        let Some(keymap) = keymap.cloned() else {
+            // ROM without its own keys
+            ui.label("Uses the keys for all ROMs");
+            if ui.button("Customize for this ROM").clicked() {
+                let key = self.keymap_rom.clone().unwrap_or_default();
+                let rom = self.settings.roms.entry(key).or_default();
+                rom.keymap = Some(self.settings.keymap.clone());
+                let _ = self.settings.save();
+            }
+            return;
+        };
+
+        if let Some(button) = self.capturing {
+            self.capture_key(ui, button);
+        }
+
+        egui::Grid::new("keymap")
+            .spacing([6.0, 6.0])
+            .show(ui, |ui| {
+                for row in KEYPAD_LAYOUT {
+                    for button in row {
+                        let name = if self.capturing == Some(button) {
+                            "…"
+                        } else {
+                            keymap.key(button)
+                        };
+                        let text = egui::RichText::new(format!("{:X}: {}", button, name))
+                            .monospace()
+                            .size(14.0);
+                        let widget = egui::Button::new(text)
+                            .min_size(egui::vec2(80.0, 30.0))
+                            .selected(self.capturing == Some(button));
+                        if ui.add(widget).clicked() {
+                            self.capturing = Some(button);
+                            self.keymap_message = Some("Press a key (Escape cancels)".into());
+                        }
+                    }
+                    ui.end_row();
+                }
+            });
+
+        ui.add_space(5.0);
+        let reset = match self.keymap_rom {
+            None => "Reset to 1234/QWER/ASDF/ZXCV",
+            Some(_) => "Use the keys for all ROMs",
+        };
+        if ui.button(reset).clicked() {
+            match &self.keymap_rom {
+                None => self.settings.keymap = Keymap::default(),
+                Some(key) => {
+                    if let Some(rom) = self.settings.roms.get_mut(key) {
+                        rom.keymap = None;
+                    }
+                }
+            }
+            self.capturing = None;
+            self.keymap_message = None;
+            let _ = self.settings.save();
+        }
+
+        if let Some(message) = &self.keymap_message {
+            ui.label(egui::RichText::new(message).color(self.theme.text_secondary));
+        }
+    }
+
+    fn capture_key(&mut self, ui: &mut egui::Ui, button: usize) {
+        let pressed = ui.input(|input| {
+            input.events.iter().find_map(|event| match event {
+                egui::Event::Key {
+                    key, pressed: true, ..
+                } => Some(*key),
+                _ => None,
+            })
+        });
+        let Some(key) = pressed else {
+            return;
+        };
+
+        self.capturing = None;
+        let name = key.name();
+        if key == egui::Key::Escape {
+            self.keymap_message = None;
+        } else if Keymap::is_reserved(name) {
+            self.keymap_message = Some(format!("{} is an emulator hotkey", name));
+        } else {
+            let keymap = match &self.keymap_rom {
+                None => &mut self.settings.keymap,
+                Some(rom_key) => self
+                    .settings
+                    .roms
+                    .entry(rom_key.clone())
+                    .or_default()
+                    .keymap
+                    .get_or_insert_with(Keymap::default),
+            };
+            keymap.bind(button, name);
+            self.keymap_message = None;
+            let _ = self.settings.save();
+        }
+    }
+
+    // Palette buttons with a preview of their colours; Custom shows colour pickers
+    fn show_palette_settings(&mut self, ui: &mut egui::Ui) {
+        let current = self.settings.display_colors();
+        let custom = match self.settings.palette {
+            Palette::Custom(colors) => Palette::Custom(colors),
+            _ => Palette::Custom(current),
+        };
+        let mut changed = false;
+
+        for palette in Palette::presets().into_iter().chain([custom]) {
+            let is_selected = self.settings.palette.id() == palette.id();
+            ui.horizontal(|ui| {
+                let button = egui::Button::new(egui::RichText::new(palette.name()).size(14.0))
+                    .min_size(egui::vec2(230.0, 35.0));
+                if ui.add(button).clicked() && !is_selected {
+                    self.settings.palette = palette;
+                    changed = true;
+                }
+                for color in egui_colors(palette.colors(self.settings.theme)) {
+                    let (rect, _) =
+                        ui.allocate_exact_size(egui::vec2(22.0, 22.0), egui::Sense::hover());
+                    ui.painter().rect_filled(rect, 3.0, color);
+                }
+            });
+            if is_selected {
+                ui.label(egui::RichText::new("  ✓ Active").color(self.theme.primary));
+            }
+            ui.add_space(5.0);
+        }
+
+        if let Palette::Custom(colors) = &mut self.settings.palette {
+            ui.horizontal(|ui| {
+                for (color, label) in colors.iter_mut().zip(["Off", "Plane 1", "Plane 2", "Both"]) {
+                    changed |= ui.color_edit_button_srgb(color).changed();
+                    ui.label(label);
+                }
+            });
+        }
+
+        if changed {
+            let _ = self.settings.save();
+            self.load_thumbnails(ui.ctx());
+        }
+    }
+
+    // CRT effects; persistence is in frames, 0 switches it off
+    fn show_filter_settings(&mut self, ui: &mut egui::Ui) {
+        let filter = &mut self.settings.filter;
+        let mut changed = false;
+
+        ui.horizontal(|ui| {
+            changed |= ui
+                .add(egui::Slider::new(&mut filter.persistence, 0..=30))
+                .changed();
+            ui.label("frames of phosphor persistence");
+        });
+        changed |= ui.checkbox(&mut filter.scanlines, "Scanlines").changed();
+        changed |= ui.checkbox(&mut filter.grid, "Pixel grid").changed();
+        changed |= ui.checkbox(&mut filter.bloom, "Bloom").changed();
+
+        // Per ROM, as some games flicker on purpose
+        ui.add_space(5.0);
+        egui::CollapsingHeader::new("Deflicker (fill in XOR redraws)")
+            .id_salt("deflicker")
+            .show(ui, |ui| {
+                for rom in &self.roms {
+                    let stem = crate::storage::rom_stem(&rom.path);
+                    let mut on = self
+                        .settings
+                        .roms
+                        .get(&rom.key)
+                        .is_some_and(|r| r.deflicker);
+                    if ui.checkbox(&mut on, stem).changed() {
+                        self.settings
+                            .roms
+                            .entry(rom.key.clone())
+                            .or_default()
+                            .deflicker = on;
+                        changed = true;
+                    }
+                }
+            });
+
+        if changed {
+            let _ = self.settings.save();
+        }
+    }
+
+    // Instructions per second, or the COSMAC VIP cycle model
+    fn show_timing_settings(&mut self, ui: &mut egui::Ui) {
+        let mut vip = self.settings.timing == Timing::CosmacVip;
+        let mut ips = match self.settings.timing {
+            Timing::Ips(ips) => ips,
+            Timing::CosmacVip => DEFAULT_IPS,
+        };
+
+        let mut changed = ui.checkbox(&mut vip, "COSMAC VIP cycle timing").changed();
+        ui.add_enabled_ui(!vip, |ui| {
+            ui.horizontal(|ui| {
+                changed |= ui
+                    .add(egui::DragValue::new(&mut ips).range(60..=100_000).speed(10))
+                    .changed();
+                ui.label("instructions per second");
+            });
+        });
+
+        if changed {
+            self.settings.timing = if vip {
+                Timing::CosmacVip
+            } else {
+                Timing::Ips(ips)
+            };
+            let _ = self.settings.save();
+        }
+    }
 }
 
 impl eframe::App for RomSelector {
@@ -0,0 +1,515 @@
+// src/gui/debugger.rs
+use chip8_emu::asm::SymbolMap;
+use chip8_emu::condition::Condition;
+use chip8_emu::debug::{Debugger, StopReason, WatchKind, Watchpoint};
+use chip8_emu::disasm;
+use chip8_emu::timing::{FrameClock, Timing};
+use chip8_emu::Emu;
+use eframe::egui;
+
+use super::palette::{egui_colors, Colors};
+use super::settings::Settings;
+use super::theme::{apply_custom_style, setup_custom_fonts, Theme};
+use crate::keymap::{Keymap, KEYPAD_LAYOUT};
+
+const MEMORY_VIEW_LINES: u16 = 24;
+
+pub struct DebuggerApp {
+    chip8: Emu,
+    debugger: Debugger,
+    clock: FrameClock,
+    keymap: Keymap,              // Same keys as the SDL window
+    palette: [egui::Color32; 4], // Same colours as the SDL window
+    theme: Theme,
+    screen_texture: Option<egui::TextureHandle>,
+    last_stop: Option<StopReason>,
+    breakpoint_input: String,
+    watch_input: String,
+    watch_kind: WatchKind,
+    condition_input: String,
+    input_error: Option<String>, // Why the last watchpoint or condition was rejected
+    symbols: SymbolMap,          // From the assembler, empty for plain ROMs
+}
+
+impl DebuggerApp {
+    pub fn new(
+        cc: &eframe::CreationContext<'_>,
+        chip8: Emu,
+        timing: Timing,
+        keymap: Keymap,
+        colors: Colors,
+        symbols: Option<SymbolMap>,
+    ) -> Self {
+        let settings = Settings::load();
+        setup_custom_fonts(&cc.egui_ctx);
+        let theme = Theme::from_type(settings.theme);
+        apply_custom_style(&cc.egui_ctx, &theme);
+
+        // Program starts paused so breakpoints can be set before the first instruction
+        let mut debugger = Debugger::new(timing);
+        debugger.pause();
+
+        // `:breakpoint` markers in the source become breakpoints
+        let symbols = symbols.unwrap_or_default();
+        for &addr in symbols.breakpoints.values() {
+            debugger.add_breakpoint(addr);
+        }
+
+        Self {
+            chip8,
+            debugger,
+            clock: FrameClock::new(),
+            keymap,
+            palette: egui_colors(colors),
+            theme,
+            screen_texture: None,
+            last_stop: None,
+            breakpoint_input: String::new(),
+            watch_input: String::new(),
+            watch_kind: WatchKind::Write,
+            condition_input: String::new(),
+            input_error: None,
+            symbols,
+        }
+    }
+
+    fn update_keys(&mut self, ctx: &egui::Context) {
+        // Keys typed into the breakpoint field are not game input
+        if ctx.wants_keyboard_input() {
+            return;
+        }
+        ctx.input(|input| {
+            for btn in 0..16 {
+                let down = egui::Key::from_name(self.keymap.key(btn))
+                    .is_some_and(|key| input.key_down(key));
+                self.chip8.keypress(btn, down);
+            }
+        });
+    }
+
+    fn show_controls(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            if self.debugger.is_paused() {
+                if ui.button("▶ Continue").clicked() {
+                    self.last_stop = None;
+                    self.debugger.resume();
+                }
+            } else if ui.button("⏸ Pause").clicked() {
+                self.debugger.pause();
+            }
+
+            ui.add_enabled_ui(self.debugger.is_paused(), |ui| {
+                if ui.button("Step").clicked() {
+                    self.last_stop = self.debugger.step_instruction(&mut self.chip8);
+                }
+                if ui.button("Step Frame").clicked() {
+                    self.last_stop = self.debugger.step_frame(&mut self.chip8);
+                }
+            });
+
+            // Only shown when started with --trace
+            if let Some(tracer) = self.chip8.tracer_mut() {
+                let mut enabled = tracer.is_enabled();
+                if ui.checkbox(&mut enabled, "Trace").changed() {
+                    tracer.set_enabled(enabled);
+                }
+            }
+        });
+
+        let status = match &self.last_stop {
+            Some(StopReason::Breakpoint(addr)) => format!("Breakpoint at {:#05X}", addr),
+            Some(StopReason::Error(e)) => format!("Crashed: {}", e),
+            Some(StopReason::Exited) => "Program exited (00FD)".to_string(),
+            Some(StopReason::Watchpoint {
+                watchpoint,
+                addr,
+                pc,
+            }) => format!(
+                "Watchpoint {} hit at {:#05X} by {:#05X}",
+                watchpoint.kind, addr, pc
+            ),
+            Some(StopReason::Condition { condition, pc }) => {
+                format!("Condition {} met after {:#05X}", condition, pc)
+            }
+            None if self.debugger.is_paused() => "Paused".to_string(),
+            None => "Running".to_string(),
+        };
+        let color = match self.last_stop {
+            Some(StopReason::Error(_)) => self.theme.error,
+            _ => self.theme.accent,
+        };
+        ui.label(egui::RichText::new(status).color(color).strong());
+    }
+
+    fn show_registers(&self, ui: &mut egui::Ui) {
+        ui.heading("Registers");
+        egui::Grid::new("v_registers")
+            .num_columns(8)
+            .spacing([10.0, 4.0])
+            .show(ui, |ui| {
+                for (i, value) in self.chip8.v_regs().iter().enumerate() {
+                    ui.monospace(format!("V{:X}", i));
+                    ui.monospace(
+                        egui::RichText::new(format!("{:02X}", value)).color(self.theme.primary),
+                    );
+                    if i % 4 == 3 {
+                        ui.end_row();
+                    }
+                }
+            });
+
+        ui.add_space(6.0);
+        egui::Grid::new("special_registers")
+            .num_columns(4)
+            .spacing([10.0, 4.0])
+            .show(ui, |ui| {
+                let fields = [
+                    ("PC", format!("{:04X}", self.chip8.pc())),
+                    ("I", format!("{:04X}", self.chip8.i_reg())),
+                    ("SP", format!("{:X}", self.chip8.sp())),
+                    ("DT", format!("{:02X}", self.chip8.delay_timer())),
+                    ("ST", format!("{:02X}", self.chip8.get_sound_timer())),
+                    ("Tick", format!("{}", self.debugger.frame_ticks())),
+                ];
+                for (i, (name, value)) in fields.iter().enumerate() {
+                    ui.monospace(*name);
+                    ui.monospace(egui::RichText::new(value).color(self.theme.primary));
+                    if i % 2 == 1 {
+                        ui.end_row();
+                    }
+                }
+            });
+
+        ui.add_space(6.0);
+        ui.heading("Stack");
+        if self.chip8.stack().is_empty() {
+            ui.label(egui::RichText::new("(empty)").color(self.theme.text_secondary));
+        }
+        for (depth, addr) in self.chip8.stack().iter().enumerate().rev() {
+            ui.monospace(format!("{:2}: {:04X}", depth, addr));
+        }
+    }
+
+    fn show_keypad(&self, ui: &mut egui::Ui) {
+        ui.heading("Keys");
+        egui::Grid::new("keypad")
+            .spacing([4.0, 4.0])
+            .show(ui, |ui| {
+                for row in KEYPAD_LAYOUT {
+                    for key in row {
+                        let pressed = self.chip8.keys()[key];
+                        let (bg, fg) = if pressed {
+                            (self.theme.primary, self.theme.background)
+                        } else {
+                            (self.theme.surface, self.theme.text_secondary)
+                        };
+                        egui::Frame::none()
+                            .fill(bg)
+                            .rounding(egui::Rounding::same(4.0))
+                            .inner_margin(egui::Margin::symmetric(10.0, 4.0))
+                            .show(ui, |ui| {
+                                ui.monospace(egui::RichText::new(format!("{:X}", key)).color(fg));
+                            });
+                    }
+                    ui.end_row();
+                }
+            });
+    }
+
+    fn show_breakpoints(&mut self, ui: &mut egui::Ui) {
+        ui.heading("Breakpoints");
+        ui.horizontal(|ui| {
+            let input = ui.add(
+                egui::TextEdit::singleline(&mut self.breakpoint_input)
+                    .hint_text("0x200 or label")
+                    .desired_width(120.0),
+            );
+            let submitted = input.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+            if ui.button("Add").clicked() || submitted {
+                if let Some(addr) = self.parse_address(&self.breakpoint_input) {
+                    self.debugger.add_breakpoint(addr);
+                    self.breakpoint_input.clear();
+                }
+            }
+        });
+
+        let mut remove = None;
+        for &addr in self.debugger.breakpoints() {
+            ui.horizontal(|ui| {
+                match self.symbols.label_at(addr) {
+                    Some(label) => ui.monospace(format!("● {:04X} {}", addr, label)),
+                    None => ui.monospace(format!("● {:04X}", addr)),
+                };
+                if ui.small_button("✖").clicked() {
+                    remove = Some(addr);
+                }
+            });
+        }
+        if let Some(addr) = remove {
+            self.debugger.remove_breakpoint(addr);
+        }
+    }
+
+    fn show_watchpoints(&mut self, ui: &mut egui::Ui) {
+        ui.heading("Watchpoints");
+        ui.horizontal(|ui| {
+            let input = ui.add(
+                egui::TextEdit::singleline(&mut self.watch_input)
+                    .hint_text("0x300-0x30F")
+                    .desired_width(100.0),
+            );
+            egui::ComboBox::from_id_salt("watch_kind")
+                .width(70.0)
+                .selected_text(self.watch_kind.id())
+                .show_ui(ui, |ui| {
+                    for kind in WatchKind::all() {
+                        ui.selectable_value(&mut self.watch_kind, kind, kind.id());
+                    }
+                });
+            let submitted = input.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+            if ui.button("Add").clicked() || submitted {
+                let text = self.watch_input.trim();
+                let range = match text.split_once('-') {
+                    Some((start, end)) => self
+                        .parse_address(start)
+                        .zip(self.parse_address(end))
+                        .map(|(start, end)| start..=end),
+                    None => self.parse_address(text).map(|addr| addr..=addr),
+                };
+                match range {
+                    Some(range) => {
+                        let watchpoint = Watchpoint {
+                            range,
+                            kind: self.watch_kind,
+                        };
+                        self.debugger.add_watchpoint(&mut self.chip8, watchpoint);
+                        self.watch_input.clear();
+                        self.input_error = None;
+                    }
+                    None => self.input_error = Some(format!("invalid range '{}'", text)),
+                }
+            }
+        });
+
+        let mut remove = None;
+        for (index, watchpoint) in self.debugger.watchpoints().iter().enumerate() {
+            ui.horizontal(|ui| {
+                ui.monospace(format!("◆ {}", watchpoint));
+                if ui.small_button("✖").clicked() {
+                    remove = Some(index);
+                }
+            });
+        }
+        if let Some(index) = remove {
+            self.debugger.remove_watchpoint(&mut self.chip8, index);
+        }
+
+        ui.add_space(6.0);
+        ui.label("Break when");
+        ui.horizontal(|ui| {
+            let input = ui.add(
+                egui::TextEdit::singleline(&mut self.condition_input)
+                    .hint_text("V3 == 0x10 && DT == 0")
+                    .desired_width(220.0),
+            );
+            let submitted = input.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+            if ui.button("Add").clicked() || submitted {
+                match self.condition_input.parse::<Condition>() {
+                    Ok(condition) => {
+                        self.debugger.add_condition(&self.chip8, condition);
+                        self.condition_input.clear();
+                        self.input_error = None;
+                    }
+                    Err(e) => self.input_error = Some(e),
+                }
+            }
+        });
+
+        let mut remove = None;
+        for (index, condition) in self.debugger.conditions().enumerate() {
+            ui.horizontal(|ui| {
+                ui.monospace(format!("? {}", condition));
+                if ui.small_button("✖").clicked() {
+                    remove = Some(index);
+                }
+            });
+        }
+        if let Some(index) = remove {
+            self.debugger.remove_condition(index);
+        }
+
+        if let Some(error) = &self.input_error {
+            ui.colored_label(self.theme.error, error);
+        }
+    }
+
+    // Hex address or label name
+    fn parse_address(&self, text: &str) -> Option<u16> {
+        let text = text.trim();
+        let hex = text
+            .strip_prefix("0x")
+            .or_else(|| text.strip_prefix("0X"))
+            .unwrap_or(text);
+        match self.symbols.labels.get(text) {
+            Some(&addr) => Some(addr),
+            None => u16::from_str_radix(hex, 16).ok(),
+        }
+    }
+
+    // Instructions around the PC; clicking a line toggles its breakpoint
+    fn show_memory(&mut self, ui: &mut egui::Ui) {
+        ui.heading("Memory");
+        let pc = self.chip8.pc();
+        let start = pc.saturating_sub(MEMORY_VIEW_LINES / 2 * 2) & !1;
+        let ram = self.chip8.ram();
+
+        let mut toggle = None;
+        for line in 0..MEMORY_VIEW_LINES {
+            let addr = start.wrapping_add(line * 2);
+            let (Some(&hi), Some(&lo)) = (ram.get(addr as usize), ram.get(addr as usize + 1))
+            else {
+                break;
+            };
+            if let Some(label) = self.symbols.label_at(addr) {
+                ui.monospace(egui::RichText::new(format!("{}:", label)).color(self.theme.accent));
+            }
+            let marker = if addr == pc { "▶" } else { " " };
+            let bp = if self.debugger.breakpoints().contains(&addr) {
+                "●"
+            } else {
+                " "
+            };
+            let mnemonic = disasm::decode(ram, addr as usize, self.chip8.quirks())
+                .map(|i| i.mnemonic)
+                .unwrap_or_default();
+            let text = egui::RichText::new(format!(
+                "{}{} {:04X}: {:02X}{:02X}  {}",
+                bp, marker, addr, hi, lo, mnemonic
+            ))
+            .monospace();
+            let text = if addr == pc {
+                text.color(self.theme.primary).strong()
+            } else {
+                text
+            };
+            if ui
+                .add(egui::Label::new(text).sense(egui::Sense::click()))
+                .clicked()
+            {
+                toggle = Some(addr);
+            }
+        }
+        if let Some(addr) = toggle {
+            self.debugger.toggle_breakpoint(addr);
+        }
+    }
+
+    fn show_screen(&mut self, ui: &mut egui::Ui) {
+        let palette = self.palette;
+        let image = egui::ColorImage {
+            size: [self.chip8.width(), self.chip8.height()],
+            pixels: self
+                .chip8
+                .get_display()
+                .iter()
+                .map(|&px| palette[(px & 3) as usize])
+                .collect(),
+        };
+        let texture = match &mut self.screen_texture {
+            Some(texture) => {
+                texture.set(image, egui::TextureOptions::NEAREST);
+                texture
+            }
+            None => self.screen_texture.insert(ui.ctx().load_texture(
+                "chip8-screen",
+                image,
+                egui::TextureOptions::NEAREST,
+            )),
+        };
+
+        // Keep the 2:1 aspect ratio inside the available space
+        let available = ui.available_size();
+        let width = available.x.min(available.y * 2.0);
+        ui.centered_and_justified(|ui| {
+            ui.image((texture.id(), egui::vec2(width, width / 2.0)));
+        });
+    }
+}
+
+impl eframe::App for DebuggerApp {
+    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        apply_custom_style(ctx, &self.theme);
+        self.update_keys(ctx);
+
+        // Frames due while paused are dropped
+        let frames = self.clock.frames_due();
+        if !self.debugger.is_paused() {
+            for _ in 0..frames {
+                if let Some(reason) = self.debugger.run_frame(&mut self.chip8) {
+                    self.last_stop = Some(reason);
+                    break;
+                }
+            }
+            ctx.request_repaint_after(self.clock.until_next_frame());
+        }
+
+        egui::SidePanel::right("debugger_panel")
+            .resizable(false)
+            .min_width(320.0)
+            .show(ctx, |ui| {
+                egui::ScrollArea::vertical().show(ui, |ui| {
+                    self.show_controls(ui);
+                    ui.separator();
+                    self.show_registers(ui);
+                    ui.separator();
+                    self.show_keypad(ui);
+                    ui.separator();
+                    self.show_breakpoints(ui);
+                    ui.separator();
+                    self.show_watchpoints(ui);
+                });
+            });
+
+        egui::SidePanel::left("memory_panel")
+            .resizable(false)
+            .min_width(260.0)
+            .show(ctx, |ui| {
+                self.show_memory(ui);
+            });
+
+        egui::CentralPanel::default().show(ctx, |ui| {
+            self.show_screen(ui);
+        });
+    }
+}
+
+/// Runs the ROM inside the debugger window instead of the SDL window.
+///
+/// `symbols` comes from the assembler and adds labels and breakpoints.
+pub fn show_debugger(
+    chip8: Emu,
+    timing: Timing,
+    keymap: Keymap,
+    colors: Colors,
+    symbols: Option<SymbolMap>,
+) -> Result<(), String> {
+    let options = eframe::NativeOptions {
+        viewport: egui::ViewportBuilder::default()
+            .with_inner_size([1280.0, 720.0])
+            .with_min_inner_size([900.0, 500.0])
+            .with_title("🐞 CHIP-8 Debugger"),
+        centered: true,
+        ..Default::default()
+    };
+
+    eframe::run_native(
+        "CHIP-8 Debugger",
+        options,
+        Box::new(move |cc| {
+            Ok(Box::new(DebuggerApp::new(
+                cc, chip8, timing, keymap, colors, symbols,
+            )))
+        }),
+    )
+    .map_err(|e| e.to_string())
+}
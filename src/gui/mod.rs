@@ -1,7 +1,10 @@
 // src/gui/mod.rs
 pub mod app;
+pub mod debugger;
+pub mod palette;
 pub mod settings;
 pub mod theme;
 pub mod widgets;
 
 pub use app::show_rom_selector;
+pub use debugger::show_debugger;
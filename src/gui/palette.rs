@@ -0,0 +1,171 @@
+// src/gui/palette.rs
+//! Colours of the CHIP-8 display, in the emulator window, the debugger,
+//! save state thumbnails, screenshots and recordings.
+use super::settings::ThemeType;
+use super::theme::Theme;
+use eframe::egui;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+/// RGB colours indexed like `Emu::get_display`: background, plane 1,
+/// plane 2 and both planes. Plain CHIP-8 only uses the first two.
+pub type Colors = [[u8; 3]; 4];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum Palette {
+    /// White on black.
+    #[default]
+    Classic,
+    /// The colours of the launcher theme.
+    Theme,
+    GreenPhosphor,
+    AmberPhosphor,
+    /// The four greens of the original Game Boy screen.
+    GameBoy,
+    Custom(Colors),
+}
+
+impl Palette {
+    /// The built-in palettes; `Custom` starts from the current colours.
+    pub fn presets() -> [Palette; 5] {
+        [
+            Palette::Classic,
+            Palette::Theme,
+            Palette::GreenPhosphor,
+            Palette::AmberPhosphor,
+            Palette::GameBoy,
+        ]
+    }
+
+    pub fn id(&self) -> &'static str {
+        match self {
+            Palette::Classic => "classic",
+            Palette::Theme => "theme",
+            Palette::GreenPhosphor => "green",
+            Palette::AmberPhosphor => "amber",
+            Palette::GameBoy => "gameboy",
+            Palette::Custom(_) => "custom",
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Palette::Classic => "Classic",
+            Palette::Theme => "Launcher theme",
+            Palette::GreenPhosphor => "Green phosphor",
+            Palette::AmberPhosphor => "Amber phosphor",
+            Palette::GameBoy => "Game Boy",
+            Palette::Custom(_) => "Custom",
+        }
+    }
+
+    pub fn colors(&self, theme: ThemeType) -> Colors {
+        match self {
+            Palette::Classic => [[0, 0, 0], [255, 255, 255], [170, 170, 170], [85, 85, 85]],
+            Palette::Theme => {
+                let theme = Theme::from_type(theme);
+                [
+                    theme.background,
+                    theme.primary,
+                    theme.accent,
+                    theme.text_secondary,
+                ]
+                .map(|c| [c.r(), c.g(), c.b()])
+            }
+            Palette::GreenPhosphor => [[4, 18, 6], [60, 255, 110], [25, 150, 60], [170, 255, 190]],
+            Palette::AmberPhosphor => [[20, 10, 0], [255, 176, 0], [160, 100, 0], [255, 225, 140]],
+            Palette::GameBoy => [[155, 188, 15], [15, 56, 15], [139, 172, 15], [48, 98, 48]],
+            Palette::Custom(colors) => *colors,
+        }
+    }
+}
+
+/// The colours for egui textures.
+pub fn egui_colors(colors: Colors) -> [egui::Color32; 4] {
+    colors.map(|[r, g, b]| egui::Color32::from_rgb(r, g, b))
+}
+
+impl fmt::Display for Palette {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for Palette {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Palette::presets()
+            .into_iter()
+            .find(|p| p.id() == s)
+            .ok_or_else(|| {
+                let ids: Vec<&str> = Palette::presets().iter().map(|p| p.id()).collect();
+                format!(
+                    "unknown palette '{}' (expected one of: {})",
+                    s,
+                    ids.join(", ")
+                )
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn presets_parse_from_their_ids() {
+        for palette in Palette::presets() {
+            assert_eq!(palette.id().parse(), Ok(palette));
+            assert_eq!(palette.to_string(), palette.name());
+        }
+        assert_eq!("classic".parse(), Ok(Palette::default()));
+        assert!("custom".parse::<Palette>().is_err());
+        assert!("Classic".parse::<Palette>().is_err());
+    }
+
+    #[test]
+    fn preset_pixels_stand_out_from_the_background() {
+        for theme in ThemeType::all_themes() {
+            for palette in Palette::presets() {
+                let colors = palette.colors(theme);
+                for plane in 1..4 {
+                    assert_ne!(colors[plane], colors[0], "{} on {:?}", palette.id(), theme);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn theme_palette_follows_the_launcher_theme() {
+        let neon = Palette::Theme.colors(ThemeType::RetroNeon);
+        let light = Palette::Theme.colors(ThemeType::SolarizedLight);
+        assert_ne!(neon, light);
+        assert_eq!(
+            Palette::Classic.colors(ThemeType::RetroNeon),
+            Palette::Classic.colors(ThemeType::SolarizedLight)
+        );
+    }
+
+    #[test]
+    fn custom_colours_are_used_as_is() {
+        let colors = [[1, 2, 3], [4, 5, 6], [7, 8, 9], [10, 11, 12]];
+        assert_eq!(Palette::Custom(colors).colors(ThemeType::Nord), colors);
+    }
+
+    #[test]
+    fn palettes_round_trip_through_json() {
+        let custom = Palette::Custom([[1, 2, 3], [4, 5, 6], [7, 8, 9], [10, 11, 12]]);
+        for palette in Palette::presets().into_iter().chain([custom]) {
+            let json = serde_json::to_string(&palette).unwrap();
+            assert_eq!(serde_json::from_str::<Palette>(&json).unwrap(), palette);
+        }
+        assert_eq!(
+            serde_json::to_string(&Palette::GameBoy).unwrap(),
+            r#""GameBoy""#
+        );
+        assert!(serde_json::from_str::<Palette>(r#""Sepia""#).is_err());
+        assert!(serde_json::from_str::<Palette>(r#"{"Custom":[[1,2,3]]}"#).is_err());
+    }
+}
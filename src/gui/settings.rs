@@ -1,5 +1,12 @@
 // src/gui/settings.rs
+use super::palette::{Colors, Palette};
+use crate::keymap::Keymap;
+use chip8_emu::filter::FilterSettings;
+use chip8_emu::pad::PadMap;
+use chip8_emu::timing::Timing;
+use chip8_emu::QuirksProfile;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::fs;
 use std::path::PathBuf;
 
@@ -73,6 +80,37 @@ pub struct Settings {
     pub theme: ThemeType,
     pub window_width: f32,
     pub window_height: f32,
+    #[serde(default)]
+    pub quirks: QuirksProfile,
+    #[serde(default)]
+    pub timing: Timing,
+    #[serde(default)]
+    pub keymap: Keymap,
+    #[serde(default)]
+    pub pad: PadMap,
+    /// Colours of the CHIP-8 display.
+    #[serde(default)]
+    pub palette: Palette,
+    /// CRT effects on the emulator window.
+    #[serde(default)]
+    pub filter: FilterSettings,
+    /// Overrides for single ROMs, keyed by `storage::rom_key`.
+    #[serde(default)]
+    pub roms: BTreeMap<String, RomSettings>,
+}
+
+/// Settings that differ for one ROM; unset fields use the global value.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RomSettings {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timing: Option<Timing>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub keymap: Option<Keymap>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pad: Option<PadMap>,
+    /// Keeps pixels that are erased and redrawn lit, to hide XOR flicker.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub deflicker: bool,
 }
 
 impl Default for Settings {
@@ -81,14 +119,20 @@ impl Default for Settings {
             theme: ThemeType::RetroNeon,
             window_width: 800.0,
             window_height: 900.0,
+            quirks: QuirksProfile::default(),
+            timing: Timing::default(),
+            keymap: Keymap::default(),
+            pad: PadMap::default(),
+            palette: Palette::default(),
+            filter: FilterSettings::default(),
+            roms: BTreeMap::new(),
         }
     }
 }
 
 impl Settings {
     fn config_path() -> PathBuf {
-        let mut path = dirs::config_dir().unwrap_or_else(|| PathBuf::from("."));
-        path.push("chip8_emu");
+        let mut path = crate::storage::data_dir();
         path.push("settings.json");
         path
     }
@@ -97,14 +141,51 @@ impl Settings {
         let path = Self::config_path();
 
         if let Ok(contents) = fs::read_to_string(&path) {
-            if let Ok(settings) = serde_json::from_str(&contents) {
-                return settings;
+            match serde_json::from_str(&contents) {
+                Ok(settings) => return settings,
+                Err(e) => eprintln!("Ignoring {}: {}", path.display(), e),
             }
         }
 
         Self::default()
     }
 
+    /// The display colours, resolved against the launcher theme.
+    pub fn display_colors(&self) -> Colors {
+        self.palette.colors(self.theme)
+    }
+
+    /// The overrides for `rom_path`, if there are any.
+    pub fn rom(&self, rom_path: &str) -> Option<&RomSettings> {
+        self.roms.get(&crate::storage::rom_key(rom_path))
+    }
+
+    /// The CPU speed for a ROM: its override, or the global setting.
+    pub fn timing_for(&self, rom_path: &str) -> Timing {
+        self.rom(rom_path)
+            .and_then(|rom| rom.timing)
+            .unwrap_or(self.timing)
+    }
+
+    /// The keymap for a ROM: its override, or the global keymap.
+    pub fn keymap_for(&self, rom_path: &str) -> Keymap {
+        self.rom(rom_path)
+            .and_then(|rom| rom.keymap.clone())
+            .unwrap_or_else(|| self.keymap.clone())
+    }
+
+    /// The controller bindings for a ROM: its override, or the global ones.
+    pub fn pad_for(&self, rom_path: &str) -> PadMap {
+        self.rom(rom_path)
+            .and_then(|rom| rom.pad.clone())
+            .unwrap_or_else(|| self.pad.clone())
+    }
+
+    /// Whether a ROM is shown with deflicker; off unless turned on for it.
+    pub fn deflicker_for(&self, rom_path: &str) -> bool {
+        self.rom(rom_path).is_some_and(|rom| rom.deflicker)
+    }
+
     pub fn save(&self) -> Result<(), String> {
         let path = Self::config_path();
 
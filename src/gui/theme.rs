@@ -8,6 +8,7 @@ pub struct Theme {
     pub surface: egui::Color32,
     pub surface_hover: egui::Color32,
     pub primary: egui::Color32,
+    #[allow(dead_code)]
     pub primary_hover: egui::Color32,
     pub text_primary: egui::Color32,
     pub text_secondary: egui::Color32,
@@ -240,6 +241,7 @@ pub fn apply_custom_style(ctx: &egui::Context, theme: &Theme) {
     ctx.set_style(style);
 }
 
+#[allow(dead_code)]
 pub fn heading_font() -> egui::TextStyle {
     egui::TextStyle::Name("Heading".into())
 }
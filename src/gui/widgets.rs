@@ -6,11 +6,18 @@ use eframe::egui;
 pub struct RomEntry {
     pub name: String,
     pub path: String,
+    pub key: String, // Key of its flags, states and settings
+    pub thumbnails: Vec<(u8, egui::TextureHandle)>, // Save state slot previews
 }
 
 impl RomEntry {
     pub fn new(name: String, path: String) -> Self {
-        Self { name, path }
+        Self {
+            name,
+            key: crate::storage::rom_key(&path),
+            path,
+            thumbnails: Vec::new(),
+        }
     }
 
     /// ROM kartı widget'ı - retro card tasarımı
@@ -110,12 +117,22 @@ impl RomEntry {
                 );
             });
 
-            // Sağ tarafta neon ok işareti (hover'da görünür)
-            if response.hovered() {
-                ui_content.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+            // Sağ tarafta neon ok işareti (hover'da görünür) ve kayıt slotları
+            ui_content.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                if response.hovered() {
                     ui.label(egui::RichText::new("▶▶").size(24.0).color(theme.accent));
-                });
-            }
+                }
+                for (slot, texture) in self.thumbnails.iter().rev() {
+                    ui.vertical(|ui| {
+                        ui.image((texture.id(), egui::vec2(64.0, 32.0)));
+                        ui.label(
+                            egui::RichText::new(format!("F{}", slot))
+                                .size(11.0)
+                                .color(theme.text_secondary),
+                        );
+                    });
+                }
+            });
         }
 
         response
@@ -191,6 +208,7 @@ pub fn show_footer(ui: &mut egui::Ui, theme: &Theme, rom_count: usize) {
 
     let top_left = screen.min + egui::vec2(10.0, 10.0);
 
+    #[allow(deprecated)]
     ui.allocate_ui_at_rect(
         egui::Rect::from_min_size(top_left, egui::vec2(200.0, 30.0)),
         |ui| {
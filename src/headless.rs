@@ -0,0 +1,282 @@
+// src/headless.rs
+//! Running a ROM without a window: scripted input, stop conditions and
+//! framebuffer/register dumps for CI and regression tests.
+use crate::emu::Emu;
+use crate::error::Chip8Error;
+use crate::timing::{Scheduler, Timing};
+use serde::Serialize;
+use std::fmt;
+use std::io::Write;
+use std::str::FromStr;
+
+/// When a headless run ends before its frame limit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Until {
+    /// The program executed `00FD`.
+    Exit,
+    /// The program is stuck on a jump to itself, how test ROMs usually end.
+    Halt,
+    /// The PC reached an address.
+    Pc(u16),
+}
+
+impl FromStr for Until {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "exit" => Ok(Until::Exit),
+            "halt" => Ok(Until::Halt),
+            _ => {
+                let addr = s
+                    .strip_prefix("pc=")
+                    .and_then(|a| a.strip_prefix("0x").or_else(|| a.strip_prefix("0X")))
+                    .and_then(|hex| u16::from_str_radix(hex, 16).ok())
+                    .ok_or_else(|| {
+                        format!(
+                            "unknown condition '{}' (expected exit, halt or pc=0xNNN)",
+                            s
+                        )
+                    })?;
+                Ok(Until::Pc(addr))
+            }
+        }
+    }
+}
+
+/// A key event at the start of a frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InputEvent {
+    pub frame: u64,
+    pub key: usize,
+    pub pressed: bool,
+}
+
+/// Key presses and releases to feed a headless run.
+///
+/// The text form has one event per line, `<frame> down <key>` or
+/// `<frame> up <key>`, with the key as a hex digit; `#` starts a comment.
+///
+/// ```
+/// use chip8_emu::headless::InputScript;
+///
+/// let script: InputScript = "30 down 5\n45 up 5  # release".parse().unwrap();
+/// assert_eq!(script.events().len(), 2);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct InputScript {
+    events: Vec<InputEvent>, // Sorted by frame
+}
+
+impl InputScript {
+    /// A script of `events`, in any order.
+    pub fn from_events(mut events: Vec<InputEvent>) -> Self {
+        events.sort_by_key(|e| e.frame);
+        InputScript { events }
+    }
+
+    pub fn events(&self) -> &[InputEvent] {
+        &self.events
+    }
+}
+
+impl FromStr for InputScript {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut events = Vec::new();
+        for (i, line) in s.lines().enumerate() {
+            let code = line.split('#').next().unwrap_or("");
+            let fields: Vec<&str> = code.split_whitespace().collect();
+            let (frame, action, key) = match fields.as_slice() {
+                [] => continue,
+                [frame, action, key] => (*frame, *action, *key),
+                _ => return Err(format!("line {}: expected '<frame> down|up <key>'", i + 1)),
+            };
+
+            let frame = frame
+                .parse()
+                .map_err(|_| format!("line {}: invalid frame '{}'", i + 1, frame))?;
+            let pressed = match action {
+                "down" => true,
+                "up" => false,
+                _ => return Err(format!("line {}: unknown action '{}'", i + 1, action)),
+            };
+            let key = match usize::from_str_radix(key, 16) {
+                Ok(k) if k < 16 && key.len() == 1 => k,
+                _ => return Err(format!("line {}: invalid key '{}'", i + 1, key)),
+            };
+            events.push(InputEvent {
+                frame,
+                key,
+                pressed,
+            });
+        }
+        // Stable, so events on the same frame keep their order
+        events.sort_by_key(|e| e.frame);
+        Ok(InputScript { events })
+    }
+}
+
+/// Settings of a headless run.
+#[derive(Debug, Clone)]
+pub struct RunConfig {
+    /// Upper bound on emulated frames.
+    pub max_frames: u64,
+    pub timing: Timing,
+    pub until: Option<Until>,
+    pub input: InputScript,
+}
+
+/// How a headless run ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RunOutcome {
+    /// Frames emulated, including the one the run stopped in.
+    pub frames: u64,
+    /// Whether the `until` condition was met; false if the frame limit was
+    /// hit first (or no condition was given).
+    pub condition_met: bool,
+}
+
+/// Runs `emu` frame by frame as the SDL frontend would, applying scripted
+/// input at the start of each frame.
+///
+/// On error the machine is left as it was when the fault occurred so it can
+/// still be dumped.
+pub fn run(emu: &mut Emu, config: &RunConfig) -> Result<RunOutcome, Chip8Error> {
+    let mut events = config.input.events().iter().peekable();
+    let mut scheduler = Scheduler::new(config.timing);
+
+    for frame in 0..config.max_frames {
+        while let Some(event) = events.next_if(|e| e.frame <= frame) {
+            emu.keypress(event.key, event.pressed);
+        }
+
+        scheduler.start_frame();
+        while !scheduler.frame_done() {
+            scheduler.tick(emu)?;
+            if config.until.is_some_and(|until| condition_met(emu, until)) {
+                return Ok(RunOutcome {
+                    frames: frame + 1,
+                    condition_met: true,
+                });
+            }
+            if emu.has_exited() {
+                break;
+            }
+        }
+        emu.tick_timers();
+
+        if emu.has_exited() {
+            return Ok(RunOutcome {
+                frames: frame + 1,
+                condition_met: false,
+            });
+        }
+    }
+
+    Ok(RunOutcome {
+        frames: config.max_frames,
+        condition_met: false,
+    })
+}
+
+fn condition_met(emu: &Emu, until: Until) -> bool {
+    match until {
+        Until::Exit => emu.has_exited(),
+        Until::Pc(addr) => emu.pc() == addr,
+        Until::Halt => {
+            // A 1NNN that jumped to its own address
+            let pc = emu.pc();
+            let ram = emu.ram();
+            emu.op_pc() == pc
+                && ram.get(pc as usize).is_some_and(|&hi| hi >> 4 == 1)
+                && ram
+                    .get(pc as usize..pc as usize + 2)
+                    .is_some_and(|op| ((op[0] as u16 & 0x0F) << 8 | op[1] as u16) == pc)
+        }
+    }
+}
+
+/// The framebuffer as text, one line per row: `.` for background, `#` for
+/// plane 1, `+` for plane 2 and `*` for both.
+pub fn display_text(emu: &Emu) -> String {
+    const CHARS: [char; 4] = ['.', '#', '+', '*'];
+    let mut text = String::with_capacity((emu.width() + 1) * emu.height());
+    for row in emu.get_display().chunks(emu.width()) {
+        text.extend(row.iter().map(|&px| CHARS[(px & 3) as usize]));
+        text.push('\n');
+    }
+    text
+}
+
+/// Default colours for PNG dumps, indexed like [`Emu::get_display`].
+pub const DEFAULT_PALETTE: [[u8; 3]; 4] =
+    [[0, 0, 0], [255, 255, 255], [170, 170, 170], [85, 85, 85]];
+
+/// Encodes the framebuffer as a PNG, each CHIP-8 pixel `scale` pixels wide.
+pub fn write_png(
+    emu: &Emu,
+    writer: impl Write,
+    scale: u32,
+    palette: &[[u8; 3]; 4],
+) -> Result<(), png::EncodingError> {
+    let scale = scale.max(1) as usize;
+    let (width, height) = (emu.width() * scale, emu.height() * scale);
+
+    let mut encoder = png::Encoder::new(writer, width as u32, height as u32);
+    encoder.set_color(png::ColorType::Indexed);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder.set_palette(palette.concat());
+    let mut png_writer = encoder.write_header()?;
+
+    let mut data = Vec::with_capacity(width * height);
+    for row in emu.get_display().chunks(emu.width()) {
+        let line: Vec<u8> = row
+            .iter()
+            .flat_map(|&px| std::iter::repeat_n(px & 3, scale))
+            .collect();
+        for _ in 0..scale {
+            data.extend_from_slice(&line);
+        }
+    }
+    png_writer.write_image_data(&data)?;
+    png_writer.finish()
+}
+
+/// Machine registers in the shape written by the JSON dump.
+#[derive(Debug, Clone, Serialize)]
+pub struct RegisterDump {
+    pub pc: u16,
+    pub i: u16,
+    pub sp: u16,
+    pub v: Vec<u8>,
+    pub stack: Vec<u16>,
+    pub delay_timer: u8,
+    pub sound_timer: u8,
+    pub hires: bool,
+    pub exited: bool,
+}
+
+impl RegisterDump {
+    pub fn new(emu: &Emu) -> Self {
+        RegisterDump {
+            pc: emu.pc(),
+            i: emu.i_reg(),
+            sp: emu.sp(),
+            v: emu.v_regs().to_vec(),
+            stack: emu.stack().to_vec(),
+            delay_timer: emu.delay_timer(),
+            sound_timer: emu.get_sound_timer(),
+            hires: emu.is_hires(),
+            exited: emu.has_exited(),
+        }
+    }
+}
+
+impl fmt::Display for RegisterDump {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let json = serde_json::to_string_pretty(self).map_err(|_| fmt::Error)?;
+        writeln!(f, "{}", json)
+    }
+}
@@ -0,0 +1,226 @@
+// src/keymap.rs
+//! Which keyboard key presses which CHIP-8 key.
+//!
+//! Keys are stored by name as egui spells them ("Q", "1", "Space", "Comma"),
+//! so the launcher can capture them and both the SDL window and the debugger
+//! can look them up.
+use serde::{Deserialize, Deserializer, Serialize};
+
+/// The hex keypad as it appears on the COSMAC VIP.
+pub const KEYPAD_LAYOUT: [[usize; 4]; 4] = [
+    [0x1, 0x2, 0x3, 0xC],
+    [0x4, 0x5, 0x6, 0xD],
+    [0x7, 0x8, 0x9, 0xE],
+    [0xA, 0x0, 0xB, 0xF],
+];
+
+/// Keys the SDL window uses for its own hotkeys; they cannot be bound.
+pub const RESERVED_KEYS: [&str; 17] = [
+    "Escape",
+    "Backspace",
+    "Tab",
+    "P",
+    "N",
+    "M",
+    "F1",
+    "F2",
+    "F3",
+    "F4",
+    "F5",
+    "F6",
+    "F7",
+    "F8",
+    "F9",
+    "F10",
+    "F12",
+];
+
+/// Key names for CHIP-8 keys 0-F, in that order. An empty name leaves the
+/// key unbound.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(transparent)]
+pub struct Keymap {
+    keys: [String; 16],
+}
+
+impl Default for Keymap {
+    /// The COSMAC VIP keypad on the left of a QWERTY keyboard:
+    /// 1234/QWER/ASDF/ZXCV.
+    fn default() -> Self {
+        let names = [
+            "X", "1", "2", "3", "Q", "W", "E", "A", "S", "D", "Z", "C", "4", "R", "F", "V",
+        ];
+        Self {
+            keys: names.map(String::from),
+        }
+    }
+}
+
+// Settings files can be edited by hand, so bindings are checked on load
+impl<'de> Deserialize<'de> for Keymap {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let (keymap, problems) = Keymap::from_names(<[String; 16]>::deserialize(deserializer)?);
+        for problem in problems {
+            eprintln!("Ignoring keymap binding: {}", problem);
+        }
+        Ok(keymap)
+    }
+}
+
+impl Keymap {
+    /// Builds a keymap from key names for CHIP-8 keys 0-F. Reserved keys and
+    /// keys bound a second time are left unbound, and the returned messages
+    /// say which.
+    pub fn from_names(names: [String; 16]) -> (Self, Vec<String>) {
+        let mut keymap = Self {
+            keys: Default::default(),
+        };
+        let mut problems = Vec::new();
+        for (button, name) in names.into_iter().enumerate() {
+            if name.is_empty() {
+                continue;
+            }
+            if Self::is_reserved(&name) {
+                problems.push(format!("'{}' is a hotkey (key {:X})", name, button));
+            } else if let Some(other) = keymap.button(&name) {
+                problems.push(format!(
+                    "'{}' is already bound to key {:X} (key {:X})",
+                    name, other, button
+                ));
+            } else {
+                keymap.keys[button] = name;
+            }
+        }
+        (keymap, problems)
+    }
+
+    /// Name of the key bound to CHIP-8 key `button`, empty if none.
+    pub fn key(&self, button: usize) -> &str {
+        &self.keys[button]
+    }
+
+    /// The CHIP-8 key a keyboard key is bound to.
+    pub fn button(&self, name: &str) -> Option<usize> {
+        if name.is_empty() {
+            return None;
+        }
+        self.keys.iter().position(|key| key == name)
+    }
+
+    /// Binds `name` to `button`, taking it away from any other CHIP-8 key.
+    pub fn bind(&mut self, button: usize, name: &str) {
+        for key in self.keys.iter_mut().filter(|key| *key == name) {
+            key.clear();
+        }
+        self.keys[button] = name.to_string();
+    }
+
+    pub fn is_reserved(name: &str) -> bool {
+        RESERVED_KEYS.contains(&name)
+    }
+}
+
+/// Converts an SDL key name to the name used in keymaps; SDL spells some
+/// keys differently (`Return`, `,`).
+pub fn from_sdl_name(name: &str) -> &str {
+    match name {
+        "Return" => "Enter",
+        "," => "Comma",
+        "-" => "Minus",
+        "." => "Period",
+        "=" => "Equals",
+        ";" => "Semicolon",
+        "\\" => "Backslash",
+        "/" => "Slash",
+        "[" => "OpenBracket",
+        "]" => "CloseBracket",
+        "`" => "Backtick",
+        "'" => "Quote",
+        _ => name,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(keys: [&str; 16]) -> [String; 16] {
+        keys.map(String::from)
+    }
+
+    #[test]
+    fn default_keys_are_distinct_and_bindable() {
+        let keymap = Keymap::default();
+        for button in 0..16 {
+            let key = keymap.key(button);
+            assert!(!Keymap::is_reserved(key), "{}", key);
+            assert_eq!(keymap.button(key), Some(button));
+        }
+        assert_eq!(keymap.button(""), None);
+        assert_eq!(keymap.button("Space"), None);
+    }
+
+    #[test]
+    fn binding_takes_the_key_from_other_buttons() {
+        let mut keymap = Keymap::default();
+        keymap.bind(0x0, "Q");
+        assert_eq!(keymap.button("Q"), Some(0x0));
+        assert_eq!(keymap.key(0x4), "");
+    }
+
+    #[test]
+    fn round_trips_through_json() {
+        let mut keymap = Keymap::default();
+        keymap.bind(0xF, "Space");
+        let json = serde_json::to_string(&keymap).unwrap();
+        assert!(json.starts_with(r#"["X","1","2""#));
+        assert_eq!(serde_json::from_str::<Keymap>(&json).unwrap(), keymap);
+    }
+
+    #[test]
+    fn reserved_and_repeated_keys_are_unbound_on_load() {
+        let (keymap, problems) = Keymap::from_names(names([
+            "X", "1", "2", "3", "Q", "W", "E", "A", "S", "D", "Z", "C", "4", "Escape", "P", "X",
+        ]));
+        assert_eq!(keymap.key(0xD), "");
+        assert_eq!(keymap.key(0xE), "");
+        assert_eq!(keymap.key(0xF), "");
+        assert_eq!(keymap.button("X"), Some(0x0));
+        assert_eq!(
+            problems,
+            [
+                "'Escape' is a hotkey (key D)",
+                "'P' is a hotkey (key E)",
+                "'X' is already bound to key 0 (key F)",
+            ]
+        );
+
+        let json = r#"["F1","1","2","3","Q","W","E","A","S","D","Z","C","4","R","F","V"]"#;
+        let keymap: Keymap = serde_json::from_str(json).unwrap();
+        assert_eq!(keymap.key(0x0), "");
+        assert_eq!(keymap.key(0x1), "1");
+    }
+
+    #[test]
+    fn valid_keymaps_load_unchanged() {
+        let (keymap, problems) = Keymap::from_names(names([
+            "", "1", "2", "3", "Q", "W", "E", "A", "S", "D", "Z", "C", "4", "R", "F", "Space",
+        ]));
+        assert!(problems.is_empty());
+        assert_eq!(keymap.key(0x0), "");
+        assert_eq!(keymap.button("Space"), Some(0xF));
+    }
+
+    #[test]
+    fn keymaps_of_the_wrong_length_are_rejected() {
+        assert!(serde_json::from_str::<Keymap>(r#"["X","1"]"#).is_err());
+        assert!(serde_json::from_str::<Keymap>(r#""X""#).is_err());
+    }
+
+    #[test]
+    fn sdl_names_match_keymap_names() {
+        assert_eq!(from_sdl_name("Return"), "Enter");
+        assert_eq!(from_sdl_name(","), "Comma");
+        assert_eq!(from_sdl_name("Q"), "Q");
+    }
+}
@@ -0,0 +1,45 @@
+// src/lib.rs
+//! CHIP-8 interpreter core.
+//!
+//! The crate is split in two: this library holds the machine itself (CPU,
+//! memory, display buffer, timers) with no dependency on any frontend, and the
+//! `chip8_emu` binary drives it with SDL2 and the egui ROM launcher.
+//!
+//! ```no_run
+//! use chip8_emu::Emu;
+//!
+//! let rom = std::fs::read("assets/ibm.ch8").unwrap();
+//! let mut chip8 = Emu::new();
+//! chip8.load_rom(&rom).unwrap();
+//!
+//! // One 60 Hz frame
+//! for _ in 0..10 {
+//!     chip8.tick().unwrap();
+//! }
+//! chip8.tick_timers();
+//! ```
+
+pub mod asm;
+pub mod condition;
+pub mod constants;
+pub mod debug;
+pub mod deflicker;
+pub mod disasm;
+pub mod emu;
+pub mod error;
+pub mod filter;
+pub mod headless;
+pub mod movie;
+pub mod pad;
+pub mod quirks;
+pub mod record;
+pub mod rewind;
+pub mod rng;
+pub mod state;
+pub mod timing;
+pub mod trace;
+
+pub use emu::Emu;
+pub use error::Chip8Error;
+pub use quirks::{Quirks, QuirksProfile};
+pub use state::StateError;
@@ -1,37 +1,126 @@
 // src/main.rs
 
 mod audio;
-mod constants;
-mod emu;
+mod cli;
+mod controller;
 mod gui; // GUI module
+mod keymap;
+mod recording;
+mod speed;
+mod storage;
 
 use std::env;
 use std::fs::File;
-use std::io::Read;
+use std::io::{BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process;
+use std::time::Instant;
 
 use audio::SquareWave;
-use constants::*;
-use emu::Emu; // Using the struct from audio.rs
+use chip8_emu::asm::{self, SymbolMap};
+use chip8_emu::constants::*;
+use chip8_emu::deflicker::Deflicker;
+use chip8_emu::disasm;
+use chip8_emu::filter::Filter;
+use chip8_emu::headless::{self, InputScript, RegisterDump, RunConfig};
+use chip8_emu::movie::{self, Movie};
+use chip8_emu::rewind::RewindBuffer;
+use chip8_emu::rng::Rng;
+use chip8_emu::timing::{FrameClock, Scheduler, Timing, FRAME_DURATION};
+use chip8_emu::trace::Tracer;
+use chip8_emu::{Chip8Error, Emu, QuirksProfile};
+use cli::{AsmOptions, Options, USAGE};
+use controller::Controllers;
+use gui::settings::Settings;
+use keymap::Keymap;
+use recording::{RecordOptions, Recording};
+use speed::{Mode, SpeedControl};
 
 use sdl2::audio::AudioSpecDesired;
 use sdl2::event::Event;
-use sdl2::keyboard::Keycode;
-use sdl2::pixels::Color;
+use sdl2::keyboard::{Keycode, Mod};
+use sdl2::pixels::{Color, PixelFormatEnum};
 use sdl2::rect::Rect;
-use std::time::Duration;
+use sdl2::render::Texture;
 
-const SCALE: u32 = 15;
+// A multiple of 2 so hi-res pixels come out square and even too
+const SCALE: u32 = 16;
 const WINDOW_WIDTH: u32 = (SCREEN_WIDTH as u32) * SCALE;
 const WINDOW_HEIGHT: u32 = (SCREEN_HEIGHT as u32) * SCALE;
-const TICKS_PER_FRAME: usize = 10; // CPU speed multiplier
+const WINDOW_TITLE: &str = "Chippy - CHIP-8 Emulator v1.1";
+const HEADLESS_FRAMES: u64 = 600; // 10 seconds when --frames is not given
+const REWIND_KEY: Keycode = Keycode::Backspace; // Hold to play frames backwards
+const TRACE_KEY: Keycode = Keycode::F9; // Toggles --trace output
+const PAUSE_KEY: Keycode = Keycode::P;
+const FRAME_ADVANCE_KEY: Keycode = Keycode::N; // Runs one frame, pausing first
+const FAST_FORWARD_KEY: Keycode = Keycode::Tab; // Hold
+const SLOW_MOTION_KEY: Keycode = Keycode::M; // Toggles
+const SCREENSHOT_KEY: Keycode = Keycode::F12;
+const RECORD_KEY: Keycode = Keycode::F10; // Starts and stops a recording
+const RECORD_SCALE: u32 = 4;
+const INDICATOR_COLOR: Color = Color::RGB(255, 200, 0);
+
+// Colours after a crash, for pixel values 0-3 like the display palette
+const CRASH_PALETTE: [Color; 4] = [
+    Color::RGB(40, 0, 0),
+    Color::RGB(255, 80, 80),
+    Color::RGB(200, 60, 60),
+    Color::RGB(140, 40, 40),
+];
 
 fn main() -> Result<(), String> {
     // 1. Parse Command Line Arguments or Show GUI
-    let args: Vec<String> = env::args().collect();
+    let args: Vec<String> = env::args().skip(1).collect();
+    let options = Options::parse(&args).map_err(|e| format!("{}\n\n{}", e, USAGE))?;
+    if options.help {
+        println!("{}", USAGE);
+        return Ok(());
+    }
+
+    if let Some(asm_options) = &options.asm {
+        return assemble(asm_options);
+    }
+
+    if let Some(path) = &options.disassemble {
+        let rom = std::fs::read(path).map_err(|e| format!("{}: {}", path, e))?;
+        let profile = options.quirks.unwrap_or(Settings::load().quirks);
+        print!("{}", disasm::disassemble(&rom, &profile.quirks()));
+        return Ok(());
+    }
 
-    let rom_path = if args.len() >= 2 {
+    // eframe cannot open a second window after the launcher has closed
+    if options.debug && options.rom_path.is_none() {
+        return Err(format!("--debug needs a ROM path\n\n{}", USAGE));
+    }
+    if options.headless && options.rom_path.is_none() {
+        return Err(format!("--headless needs a ROM path\n\n{}", USAGE));
+    }
+    if options.verify && options.play.is_none() {
+        return Err(format!("--verify needs --play\n\n{}", USAGE));
+    }
+    if options.play.is_some() && (options.input.is_some() || options.record_movie.is_some()) {
+        return Err(format!(
+            "--play cannot be combined with --input or --record-movie\n\n{}",
+            USAGE
+        ));
+    }
+    // --verify replays the movie without a window
+    let headless = options.headless || options.verify;
+
+    // An input movie brings the settings it was recorded with
+    let movie = match &options.play {
+        Some(path) => Some(
+            std::fs::read_to_string(path)
+                .map_err(|e| format!("{}: {}", path, e))?
+                .parse::<Movie>()
+                .map_err(|e| format!("{}: {}", path, e))?,
+        ),
+        None => None,
+    };
+
+    let rom_path = if let Some(path) = options.rom_path.clone() {
         // If a ROM path is provided as argument, use it directly
-        args[1].clone()
+        path
     } else {
         // Otherwise, show GUI to select ROM
         match gui::show_rom_selector()? {
@@ -43,13 +132,111 @@ fn main() -> Result<(), String> {
         }
     };
 
-    // 2. Initialize SDL2 Subsystems
+    // 2. Initialize Emulator & Load ROM
+    // The quirks profile comes from the command line, falling back to the launcher setting.
+    // Headless runs ignore the settings so results do not depend on the machine.
+    let profile = match (&movie, options.quirks) {
+        (Some(movie), _) => movie.quirks,
+        (None, Some(profile)) => profile,
+        (None, None) if headless => QuirksProfile::default(),
+        (None, None) => Settings::load().quirks,
+    };
+    let mut chip8 = Emu::with_quirks(profile.quirks());
+
+    let mut rom_file = File::open(&rom_path).map_err(|e| e.to_string())?;
+    let mut buffer = Vec::new();
+    rom_file
+        .read_to_end(&mut buffer)
+        .map_err(|e| e.to_string())?;
+
+    chip8.load_rom(&buffer).map_err(|e| e.to_string())?;
+    if let Some(movie) = &movie {
+        movie.check_rom(&buffer)?;
+    }
+
+    // A fixed seed makes CXNN reproducible; print it so a run can be repeated
+    let (rng_mode, seed) = match (&movie, options.seed) {
+        (Some(movie), _) => (movie.rng_mode, movie.seed),
+        (None, Some(seed)) => (options.rng_mode, seed),
+        (None, None) if headless => (options.rng_mode, 0),
+        (None, None) => (options.rng_mode, rand::random()),
+    };
+    chip8.set_rng(Rng::new(rng_mode, seed));
+
+    // CPU speed: command line, then the ROM's own setting, then the global one
+    let timing = match (&movie, options.timing) {
+        (Some(movie), _) => movie.timing,
+        (None, Some(timing)) => timing,
+        (None, None) if headless => Timing::default(),
+        (None, None) => Settings::load().timing_for(&rom_path),
+    };
+
+    if let Some(path) = &options.trace {
+        let file = File::create(path).map_err(|e| format!("{}: {}", path, e))?;
+        let mut tracer = Tracer::new(BufWriter::new(file), options.trace_format);
+        if let Some(range) = &options.trace_range {
+            tracer = tracer.with_range(range.clone());
+        }
+        chip8.set_tracer(Some(tracer));
+    }
+
+    if headless {
+        return run_headless(&options, &mut chip8, timing, movie.as_ref());
+    }
+    println!(
+        "🚀 ROM Loaded: {} ({} quirks, {})",
+        &rom_path, profile, timing
+    );
+    println!("🎲 RNG: {} (seed {})", rng_mode, seed);
+
+    // Input movies start at power-on; the player takes over the keypad until
+    // the movie ends
+    let mut playing = movie.map(|movie| {
+        println!("🎬 Playing movie ({} frames)", movie.len());
+        (movie, 0)
+    });
+    let mut recording_movie = options.record_movie.as_ref().map(|path| {
+        println!("🎬 Recording movie to {}", path);
+        let movie = Movie::new(&buffer, profile, rng_mode, seed, timing);
+        (path.clone(), movie)
+    });
+
+    // The ROM's own keymap and controller bindings, if it has them
+    let settings = Settings::load();
+    let keymap = settings.keymap_for(&rom_path);
+    let pad_map = settings.pad_for(&rom_path);
+    // Display colours for pixel values 0-3 (XO-CHIP bitplanes: none, plane 1, plane 2, both)
+    let colors = match options.palette {
+        Some(palette) => palette.colors(settings.theme),
+        None => settings.display_colors(),
+    };
+    let display_palette = colors.map(|[r, g, b]| Color::RGB(r, g, b));
+
+    // SUPER-CHIP RPL flags persist between runs of the same ROM, except in
+    // movies, which always start from a cleared machine
+    let movie_active = playing.is_some() || recording_movie.is_some();
+    let saved_flags = if movie_active {
+        None
+    } else {
+        storage::load_rpl_flags(&rom_path)
+    };
+    if let Some(flags) = &saved_flags {
+        chip8.set_rpl_flags(flags);
+    }
+
+    // The debugger window replaces the SDL frontend entirely
+    if options.debug {
+        let symbols = load_symbols(&rom_path);
+        return gui::show_debugger(chip8, timing, keymap, colors, symbols);
+    }
+
+    // 3. Initialize SDL2 Subsystems
     let sdl_context = sdl2::init()?;
     let video_subsystem = sdl_context.video()?;
     let audio_subsystem = sdl_context.audio()?; // Initialize Audio
 
     let window = video_subsystem
-        .window("Chippy - CHIP-8 Emulator v1.1", WINDOW_WIDTH, WINDOW_HEIGHT)
+        .window(WINDOW_TITLE, WINDOW_WIDTH, WINDOW_HEIGHT)
         .position_centered()
         .opengl()
         .build()
@@ -61,7 +248,19 @@ fn main() -> Result<(), String> {
         .build()
         .map_err(|e| e.to_string())?;
 
+    // With display filters on, the screen is drawn on the CPU and shown as a texture
+    let texture_creator = canvas.texture_creator();
+    let mut filter = Filter::new(settings.filter);
+    let mut filter_texture: Option<Texture> = None;
+    // Whole window pixels per CHIP-8 pixel at both resolutions, with borders
+    // if the window does not divide evenly (e.g. on high-DPI screens)
+    canvas.set_integer_scale(true)?;
+    // Blends XOR erase/redraw pairs, for the ROMs it is turned on for
+    let mut deflicker = settings.deflicker_for(&rom_path).then(Deflicker::new);
+
     let mut event_pump = sdl_context.event_pump()?;
+    // Controllers are opened as SDL reports them, including those already plugged in
+    let mut controllers = Controllers::new(sdl_context.game_controller()?);
 
     // --- AUDIO SETUP START ---
     let desired_spec = AudioSpecDesired {
@@ -71,33 +270,69 @@ fn main() -> Result<(), String> {
     };
 
     // Open the audio device using the SquareWave struct from audio.rs
-    let device = audio_subsystem
+    let mut device = audio_subsystem
         .open_playback(None, &desired_spec, |spec| {
             SquareWave {
                 phase_inc: 440.0 / spec.freq as f32, // 440Hz (A4 Note)
                 phase: 0.0,
                 volume: 0.15, // Low volume to be safe
+                pattern: None,
+                pattern_inc: 0.0,
+                pattern_pos: 0.0,
             }
         })
         .map_err(|e| e.to_string())?;
+    let sample_rate = device.spec().freq as f32;
     // --- AUDIO SETUP END ---
 
-    // 3. Initialize Emulator & Load ROM
-    let mut chip8 = Emu::new();
+    // Set when the program faults; the CPU stays frozen and the crash screen is shown
+    let mut crash: Option<Chip8Error> = None;
 
-    let mut rom_file = File::open(&rom_path).map_err(|e| e.to_string())?;
-    let mut buffer = Vec::new();
-    rom_file
-        .read_to_end(&mut buffer)
-        .map_err(|e| e.to_string())?;
+    // Per-frame history for hold-to-rewind
+    let mut rewind = RewindBuffer::default();
+    let mut rewinding = false;
+
+    // Frames are emulated as wall time passes, independent of how long drawing takes
+    let mut clock = FrameClock::new();
+    let mut scheduler = Scheduler::new(timing);
+    let mut speed = SpeedControl::new(options.fast_forward);
+    let mut shown_mode = Mode::Normal; // Mode in the window title
 
-    chip8.load_rom(&buffer);
-    println!("🚀 ROM Loaded: {}", &rom_path);
+    let screenshot_dir = options
+        .screenshot_dir
+        .as_ref()
+        .map(PathBuf::from)
+        .unwrap_or_else(storage::screenshots_dir);
+    let screenshot_scale = options.screenshot_scale.unwrap_or(SCALE);
+
+    let record_options = RecordOptions {
+        dir: options
+            .record_dir
+            .as_ref()
+            .map(PathBuf::from)
+            .unwrap_or_else(storage::recordings_dir),
+        format: options.record_format,
+        colors,
+        scale: options.record_scale.unwrap_or(RECORD_SCALE),
+        wav: options.record_wav,
+    };
+    let mut recording = None;
+    if options.record {
+        recording = start_recording(&record_options, &rom_path, &chip8);
+    }
 
     // 4. Main Game Loop
     'running: loop {
+        // Set when an older state was loaded this frame
+        let mut restored = false;
+        // Frames emulated or rewound this iteration, for phosphor fading
+        let mut advanced = 0;
+
         // A. Input Handling
         for event in event_pump.poll_iter() {
+            if controllers.handle(&event, &pad_map, &mut chip8) {
+                continue;
+            }
             match event {
                 Event::Quit { .. }
                 | Event::KeyDown {
@@ -107,16 +342,114 @@ fn main() -> Result<(), String> {
                     break 'running;
                 }
                 Event::KeyDown {
-                    keycode: Some(key), ..
+                    keycode: Some(REWIND_KEY),
+                    repeat,
+                    ..
                 } => {
-                    if let Some(k) = key2btn(key) {
+                    if playing.is_none() && recording_movie.is_none() {
+                        rewinding = true;
+                    } else if !repeat {
+                        println!("⏪ Rewind is off while a movie is played or recorded");
+                    }
+                }
+                Event::KeyDown {
+                    keycode: Some(TRACE_KEY),
+                    repeat: false,
+                    ..
+                } => {
+                    if let Some(tracer) = chip8.tracer_mut() {
+                        let enabled = !tracer.is_enabled();
+                        tracer.set_enabled(enabled);
+                        println!("📝 Trace {}", if enabled { "on" } else { "off" });
+                    }
+                }
+                Event::KeyUp {
+                    keycode: Some(REWIND_KEY),
+                    ..
+                } => rewinding = false,
+                Event::KeyDown {
+                    keycode: Some(PAUSE_KEY),
+                    repeat: false,
+                    ..
+                } => speed.toggle_pause(),
+                Event::KeyDown {
+                    keycode: Some(FRAME_ADVANCE_KEY),
+                    ..
+                } => speed.advance_frame(),
+                Event::KeyDown {
+                    keycode: Some(FAST_FORWARD_KEY),
+                    repeat: false,
+                    ..
+                } => speed.set_fast_forward(true),
+                Event::KeyUp {
+                    keycode: Some(FAST_FORWARD_KEY),
+                    ..
+                } => speed.set_fast_forward(false),
+                Event::KeyDown {
+                    keycode: Some(SLOW_MOTION_KEY),
+                    repeat: false,
+                    ..
+                } => speed.toggle_slow_motion(),
+                Event::KeyDown {
+                    keycode: Some(RECORD_KEY),
+                    repeat: false,
+                    ..
+                } => match recording.take() {
+                    Some(rec) => stop_recording(rec),
+                    None => recording = start_recording(&record_options, &rom_path, &chip8),
+                },
+                Event::KeyDown {
+                    keycode: Some(SCREENSHOT_KEY),
+                    repeat: false,
+                    ..
+                } => {
+                    // Same colours as on screen, without the speed indicator
+                    let palette = if crash.is_some() {
+                        CRASH_PALETTE
+                    } else {
+                        display_palette
+                    };
+                    let path = storage::timestamped_path(&screenshot_dir, &rom_path, "png");
+                    match save_screenshot(&chip8, &path, screenshot_scale, &palette) {
+                        Ok(()) => println!("📷 Screenshot saved to {}", path.display()),
+                        Err(e) => eprintln!("Could not save screenshot: {}", e),
+                    }
+                }
+                Event::KeyDown {
+                    keycode: Some(key),
+                    keymod,
+                    ..
+                } => {
+                    if let Some(slot) = key2slot(key) {
+                        // Shift+F1-F8 saves, F1-F8 loads
+                        if keymod.intersects(Mod::LSHIFTMOD | Mod::RSHIFTMOD) {
+                            match storage::save_state_slot(&rom_path, slot, &chip8.save_state()) {
+                                Ok(()) => println!("💾 Saved state to slot {}", slot),
+                                Err(e) => eprintln!("Could not save slot {}: {}", slot, e),
+                            }
+                        } else if playing.is_some() || recording_movie.is_some() {
+                            println!("States cannot be loaded while a movie is played or recorded");
+                        } else {
+                            let loaded =
+                                storage::load_state_slot(&rom_path, slot).and_then(|data| {
+                                    chip8.load_state(&data).map_err(|e| e.to_string())
+                                });
+                            match loaded {
+                                Ok(()) => {
+                                    println!("📂 Loaded state from slot {}", slot);
+                                    restored = true;
+                                }
+                                Err(e) => eprintln!("Could not load slot {}: {}", slot, e),
+                            }
+                        }
+                    } else if let Some(k) = key2btn(&keymap, key) {
                         chip8.keypress(k, true);
                     }
                 }
                 Event::KeyUp {
                     keycode: Some(key), ..
                 } => {
-                    if let Some(k) = key2btn(key) {
+                    if let Some(k) = key2btn(&keymap, key) {
                         chip8.keypress(k, false);
                     }
                 }
@@ -124,65 +457,419 @@ fn main() -> Result<(), String> {
             }
         }
 
-        // B. CPU Cycles
-        for _ in 0..TICKS_PER_FRAME {
-            chip8.tick();
+        // 00FD - the program asked to exit
+        if chip8.has_exited() {
+            break 'running;
+        }
+
+        let due = clock.frames_due();
+        if rewinding {
+            // Step back one frame for every frame due instead of emulating
+            for _ in 0..due {
+                if let Some(state) = rewind.rewind() {
+                    chip8.load_state(state).map_err(|e| e.to_string())?;
+                    restored = true;
+                    advanced += 1;
+                }
+            }
+        } else if crash.is_none() {
+            // B. CPU Cycles and C. Timers
+            // Uncapped fast-forward runs frames for the length of one displayed frame
+            let (frames, deadline) = match speed.frames(due) {
+                Some(frames) => (frames, None),
+                None => (u32::MAX, Some(Instant::now() + FRAME_DURATION)),
+            };
+            for _ in 0..frames {
+                if deadline.is_some_and(|d| Instant::now() >= d) {
+                    break;
+                }
+                if let Some((movie, frame)) = &mut playing {
+                    if movie.apply_frame(*frame, &mut chip8) {
+                        *frame += 1;
+                    } else {
+                        finish_playback(movie, &chip8);
+                        playing = None;
+                        sync_keys(&mut chip8, &keymap, &event_pump);
+                        controllers.sync(&mut chip8);
+                    }
+                }
+                if let Some((_, movie)) = &mut recording_movie {
+                    movie.record_frame(&chip8);
+                }
+                if let Err(e) = scheduler.run_frame(&mut chip8) {
+                    eprintln!("💥 Emulator crashed: {}", e);
+                    canvas
+                        .window_mut()
+                        .set_title(&format!("{} - CRASHED: {}", WINDOW_TITLE, e))
+                        .map_err(|e| e.to_string())?;
+                    crash = Some(e);
+                    break;
+                }
+                rewind.push(chip8.save_state());
+                advanced += 1;
+                if let Some(deflicker) = &mut deflicker {
+                    deflicker.push(&chip8);
+                }
+                if let Some(rec) = &mut recording {
+                    if let Err(e) = rec.add_frame(&chip8) {
+                        eprintln!("Recording stopped: {}", e);
+                        recording = None;
+                    }
+                }
+                if chip8.has_exited() {
+                    break;
+                }
+            }
         }
 
-        // C. Timers
-        chip8.tick_timers();
+        if restored {
+            if let Some(deflicker) = &mut deflicker {
+                deflicker.reset();
+            }
+            sync_keys(&mut chip8, &keymap, &event_pump);
+            controllers.sync(&mut chip8);
+            if crash.take().is_some() {
+                canvas
+                    .window_mut()
+                    .set_title(WINDOW_TITLE)
+                    .map_err(|e| e.to_string())?;
+                shown_mode = Mode::Normal;
+            }
+        }
 
         // --- AUDIO CONTROL ---
         // If the sound timer > 0, play sound. Otherwise, pause.
-        if chip8.get_sound_timer() > 0 {
+        if crash.is_none() && !rewinding && !speed.is_paused() && chip8.get_sound_timer() > 0 {
+            // XO-CHIP programs play their own 1-bit pattern at a programmable pitch
+            if chip8.quirks().xo_chip {
+                let mut wave = device.lock();
+                wave.pattern = Some(*chip8.audio_pattern());
+                wave.pattern_inc = chip8.audio_playback_rate() / sample_rate;
+            }
             device.resume();
         } else {
             device.pause();
         }
 
         // D. Render
-        canvas.set_draw_color(Color::RGB(0, 0, 0));
+        // The logical size follows the active resolution (64x32 or 128x64),
+        // SDL scales it to the window
+        let (width, height) = (chip8.width() as u32, chip8.height() as u32);
+        if canvas.logical_size() != (width, height) {
+            canvas
+                .set_logical_size(width, height)
+                .map_err(|e| e.to_string())?;
+        }
+
+        // After a crash the last frame stays visible, tinted red, with a red frame around it
+        let palette = if crash.is_some() {
+            CRASH_PALETTE
+        } else {
+            display_palette
+        };
+        canvas.set_draw_color(palette[0]);
         canvas.clear();
 
-        let screen_buf = chip8.get_display();
-        canvas.set_draw_color(Color::RGB(255, 255, 255));
+        let screen_buf = match &deflicker {
+            Some(deflicker) => deflicker.display(&chip8),
+            None => chip8.get_display().to_vec(),
+        };
+        if filter.settings().is_active() {
+            // A loaded state shows at once, even while paused
+            let frames = if restored { advanced.max(1) } else { advanced };
+            let colors = palette.map(|c| [c.r, c.g, c.b]);
+            filter.update_display(&screen_buf, width as usize, &colors, frames);
 
-        for (i, pixel) in screen_buf.iter().enumerate() {
-            if *pixel {
-                let x = (i % SCREEN_WIDTH) as u32;
-                let y = (i / SCREEN_WIDTH) as u32;
-                let rect = Rect::new((x * SCALE) as i32, (y * SCALE) as i32, SCALE, SCALE);
-                canvas.fill_rect(rect)?;
+            // One texture pixel per window pixel, so scanlines and the grid stay
+            // sharp
+            let (window_width, window_height) = canvas.output_size()?;
+            let scale = (window_width / width).min(window_height / height).max(1);
+            let size = (width * scale, height * scale);
+            if filter_texture
+                .as_ref()
+                .map(|t| (t.query().width, t.query().height))
+                != Some(size)
+            {
+                filter_texture = Some(
+                    texture_creator
+                        .create_texture_streaming(PixelFormatEnum::RGB24, size.0, size.1)
+                        .map_err(|e| e.to_string())?,
+                );
             }
+            if let Some(texture) = &mut filter_texture {
+                texture
+                    .update(None, &filter.render(scale as usize), size.0 as usize * 3)
+                    .map_err(|e| e.to_string())?;
+                canvas.copy(texture, None, None)?;
+            }
+        } else {
+            for (i, pixel) in screen_buf.iter().enumerate() {
+                if *pixel != 0 {
+                    let x = (i as u32) % width;
+                    let y = (i as u32) / width;
+                    canvas.set_draw_color(palette[*pixel as usize]);
+                    canvas.fill_rect(Rect::new(x as i32, y as i32, 1, 1))?;
+                }
+            }
+        }
+        if crash.is_some() {
+            canvas.set_draw_color(palette[1]);
+            canvas.draw_rect(Rect::new(0, 0, width, height))?;
+        }
+
+        // Pause/fast-forward/slow motion icon in the top right corner
+        let mode = speed.mode();
+        if let Some(icon) = mode.indicator() {
+            canvas.set_draw_color(INDICATOR_COLOR);
+            let left = width as i32 - icon[0].len() as i32 - 1;
+            for (y, row) in icon.iter().enumerate() {
+                for (x, _) in row.char_indices().filter(|&(_, c)| c == '#') {
+                    canvas.fill_rect(Rect::new(left + x as i32, 1 + y as i32, 1, 1))?;
+                }
+            }
+        }
+        if mode != shown_mode && crash.is_none() {
+            let title = match mode {
+                Mode::Normal => WINDOW_TITLE.to_string(),
+                _ => format!("{} - {}", WINDOW_TITLE, mode),
+            };
+            canvas
+                .window_mut()
+                .set_title(&title)
+                .map_err(|e| e.to_string())?;
+            shown_mode = mode;
         }
         canvas.present();
 
-        // 60 FPS Delay
-        ::std::thread::sleep(Duration::from_micros(16600));
+        // vsync usually paces the loop; without it, wait for the next frame
+        if due == 0 {
+            std::thread::sleep(clock.until_next_frame());
+        }
+    }
+
+    if let Some(rec) = recording {
+        stop_recording(rec);
+    }
+    if let Some((path, mut movie)) = recording_movie {
+        movie.finish(&chip8);
+        match std::fs::write(&path, movie.to_string()) {
+            Ok(()) => println!("🎬 Saved {} frames to {}", movie.len(), path),
+            Err(e) => eprintln!("Could not save movie {}: {}", path, e),
+        }
+    }
+
+    if !movie_active && saved_flags.as_deref() != Some(chip8.rpl_flags()) {
+        if let Err(e) = storage::save_rpl_flags(&rom_path, chip8.rpl_flags()) {
+            eprintln!("Could not save RPL flags: {}", e);
+        }
     }
 
     Ok(())
 }
 
-// Keyboard Mapping Helper
-fn key2btn(key: Keycode) -> Option<usize> {
+// Runs without SDL and exits with 1 on an emulator error, 2 if --until was not
+// reached, 3 if --verify found a different final screen
+fn run_headless(
+    options: &Options,
+    chip8: &mut Emu,
+    timing: Timing,
+    movie: Option<&Movie>,
+) -> Result<(), String> {
+    let input = match (&options.input, movie) {
+        (_, Some(movie)) => movie.input_script(),
+        (Some(path), None) => std::fs::read_to_string(path)
+            .map_err(|e| format!("{}: {}", path, e))?
+            .parse()
+            .map_err(|e| format!("{}: {}", path, e))?,
+        (None, None) => InputScript::default(),
+    };
+    // A movie runs to its end unless --frames says otherwise
+    let frames = match movie {
+        Some(movie) => movie.len() as u64,
+        None => HEADLESS_FRAMES,
+    };
+    let config = RunConfig {
+        max_frames: options.frames.unwrap_or(frames),
+        timing,
+        until: options.until,
+        input,
+    };
+
+    let result = headless::run(chip8, &config);
+
+    // Dumps are written even after a crash; they show where it happened
+    if let Some(path) = &options.dump_text {
+        write_output(path, headless::display_text(chip8).as_bytes())?;
+    }
+    if let Some(path) = &options.dump_json {
+        write_output(path, RegisterDump::new(chip8).to_string().as_bytes())?;
+    }
+    if let Some(path) = &options.dump_png {
+        let file = File::create(path).map_err(|e| format!("{}: {}", path, e))?;
+        let scale = options.screenshot_scale.unwrap_or(1);
+        // Settings are ignored here too; only --palette changes the colours
+        let colors = match options.palette {
+            Some(palette) => palette.colors(Settings::load().theme),
+            None => headless::DEFAULT_PALETTE,
+        };
+        headless::write_png(chip8, file, scale, &colors).map_err(|e| format!("{}: {}", path, e))?;
+    }
+
+    // process::exit skips destructors, so flush the trace now
+    chip8.set_tracer(None);
+
+    if let (true, Some(movie)) = (options.verify, movie) {
+        let expected = movie
+            .final_hash
+            .ok_or("the movie has no final screen hash")?;
+        let actual = movie::display_hash(chip8);
+        if actual != expected {
+            eprintln!(
+                "❌ Final screen differs from the movie (hash {:016x}, expected {:016x})",
+                actual, expected
+            );
+            process::exit(3);
+        }
+        eprintln!("✅ Final screen matches the movie");
+    }
+
+    match result {
+        Err(e) => {
+            eprintln!("💥 Emulator crashed: {}", e);
+            process::exit(1);
+        }
+        Ok(outcome) if options.until.is_some() && !outcome.condition_met => {
+            eprintln!("⏱️ Condition not reached after {} frames", outcome.frames);
+            process::exit(2);
+        }
+        Ok(outcome) => {
+            eprintln!("✅ Ran {} frames", outcome.frames);
+            Ok(())
+        }
+    }
+}
+
+// The current screen as PNG; creates the folder if needed
+fn save_screenshot(
+    chip8: &Emu,
+    path: &Path,
+    scale: u32,
+    palette: &[Color; 4],
+) -> Result<(), String> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+    }
+    let file = File::create(path).map_err(|e| e.to_string())?;
+    let colors = palette.map(|c| [c.r, c.g, c.b]);
+    headless::write_png(chip8, BufWriter::new(file), scale, &colors).map_err(|e| e.to_string())
+}
+
+// Reports the end of a movie and whether it ended on the recorded screen
+fn finish_playback(movie: &Movie, chip8: &Emu) {
+    println!("🎬 Movie finished after {} frames", movie.len());
+    match movie.final_hash {
+        Some(hash) if hash == movie::display_hash(chip8) => {
+            println!("✅ Final screen matches the movie");
+        }
+        Some(_) => println!("❌ Final screen differs from the movie"),
+        None => {}
+    }
+}
+
+fn start_recording(options: &RecordOptions, rom_path: &str, chip8: &Emu) -> Option<Recording> {
+    match Recording::start(options, rom_path, chip8) {
+        Ok(rec) => {
+            println!("⏺️ Recording to {}", rec.path().display());
+            Some(rec)
+        }
+        Err(e) => {
+            eprintln!("Could not start recording: {}", e);
+            None
+        }
+    }
+}
+
+fn stop_recording(rec: Recording) {
+    let path = rec.path().to_path_buf();
+    let frames = rec.frames();
+    match rec.stop() {
+        Ok(()) => println!("⏹️ Saved {} frames to {}", frames, path.display()),
+        Err(e) => eprintln!("Could not finish recording: {}", e),
+    }
+}
+
+// `-` writes to stdout
+fn write_output(path: &str, data: &[u8]) -> Result<(), String> {
+    if path == "-" {
+        std::io::stdout().write_all(data).map_err(|e| e.to_string())
+    } else {
+        std::fs::write(path, data).map_err(|e| format!("{}: {}", path, e))
+    }
+}
+
+// `asm` subcommand: Octo source to ROM plus a symbol map for the debugger
+fn assemble(options: &AsmOptions) -> Result<(), String> {
+    let source = std::fs::read_to_string(&options.source)
+        .map_err(|e| format!("{}: {}", options.source, e))?;
+    let program = asm::assemble(&source).map_err(|e| format!("{}:{}", options.source, e))?;
+
+    let output = match &options.output {
+        Some(path) => PathBuf::from(path),
+        None => Path::new(&options.source).with_extension("ch8"),
+    };
+    std::fs::write(&output, &program.rom).map_err(|e| e.to_string())?;
+    std::fs::write(output.with_extension("sym"), program.symbols.to_string())
+        .map_err(|e| e.to_string())?;
+    println!(
+        "🔧 Assembled {} ({} bytes)",
+        output.display(),
+        program.rom.len()
+    );
+    Ok(())
+}
+
+// Symbol map written by `asm` next to the ROM, if there is one
+fn load_symbols(rom_path: &str) -> Option<SymbolMap> {
+    let path = Path::new(rom_path).with_extension("sym");
+    let text = std::fs::read_to_string(&path).ok()?;
+    match text.parse() {
+        Ok(symbols) => Some(symbols),
+        Err(e) => {
+            eprintln!("Ignoring {}: {}", path.display(), e);
+            None
+        }
+    }
+}
+
+// A restored state carries the key state from when it was taken; replace it
+// with the keys that are actually held down now
+fn sync_keys(chip8: &mut Emu, keymap: &Keymap, event_pump: &sdl2::EventPump) {
+    for k in 0..16 {
+        chip8.keypress(k, false);
+    }
+    for scancode in event_pump.keyboard_state().pressed_scancodes() {
+        if let Some(k) = Keycode::from_scancode(scancode).and_then(|key| key2btn(keymap, key)) {
+            chip8.keypress(k, true);
+        }
+    }
+}
+
+// Save state slot for F1-F8
+fn key2slot(key: Keycode) -> Option<u8> {
     match key {
-        Keycode::Num1 => Some(0x1),
-        Keycode::Num2 => Some(0x2),
-        Keycode::Num3 => Some(0x3),
-        Keycode::Num4 => Some(0xC),
-        Keycode::Q => Some(0x4),
-        Keycode::W => Some(0x5),
-        Keycode::E => Some(0x6),
-        Keycode::R => Some(0xD),
-        Keycode::A => Some(0x7),
-        Keycode::S => Some(0x8),
-        Keycode::D => Some(0x9),
-        Keycode::F => Some(0xE),
-        Keycode::Z => Some(0xA),
-        Keycode::X => Some(0x0),
-        Keycode::C => Some(0xB),
-        Keycode::V => Some(0xF),
+        Keycode::F1 => Some(1),
+        Keycode::F2 => Some(2),
+        Keycode::F3 => Some(3),
+        Keycode::F4 => Some(4),
+        Keycode::F5 => Some(5),
+        Keycode::F6 => Some(6),
+        Keycode::F7 => Some(7),
+        Keycode::F8 => Some(8),
         _ => None,
     }
 }
+
+// Keyboard Mapping Helper
+fn key2btn(keymap: &Keymap, key: Keycode) -> Option<usize> {
+    keymap.button(keymap::from_sdl_name(&key.name()))
+}
@@ -0,0 +1,249 @@
+// src/movie.rs
+//! Input movies: the keys held in every frame of a run, plus everything else
+//! needed to repeat it exactly (ROM, quirks profile, RNG seed, CPU speed).
+//!
+//! A movie starts at power-on. Replaying it on the same ROM with the same
+//! settings feeds the same keys into the same frames, so the run ends on the
+//! same screen; [`display_hash`] of that screen is stored to check this.
+//!
+//! Movies are text files (`.c8m`), one line per frame with the held keys as
+//! hex digits and `.` for keys that are up, so runs can be edited by hand:
+//!
+//! ```text
+//! c8m 1
+//! rom 9c3aa8cb4dce2a11
+//! quirks modern
+//! rng xorshift
+//! seed 42
+//! timing 600
+//! hash 5bd1e9955bd1e995
+//! input
+//! ................
+//! .....5..........
+//! ```
+use crate::emu::Emu;
+use crate::headless::{InputEvent, InputScript};
+use crate::quirks::QuirksProfile;
+use crate::rng::RngMode;
+use crate::state::rom_hash;
+use crate::timing::Timing;
+use std::fmt;
+use std::str::FromStr;
+
+/// FNV-1a hash of the framebuffer, to check that a replay ended where the
+/// recording did.
+pub fn display_hash(emu: &Emu) -> u64 {
+    rom_hash(emu.get_display())
+}
+
+/// A recorded run.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Movie {
+    pub rom_hash: u64,
+    pub quirks: QuirksProfile,
+    pub rng_mode: RngMode,
+    pub seed: u64,
+    pub timing: Timing,
+    /// [`display_hash`] after the last frame, once the recording is finished.
+    pub final_hash: Option<u64>,
+    frames: Vec<u16>, // Held keys, bit N for key N
+}
+
+impl Movie {
+    /// An empty movie for a run of `rom` with these settings.
+    pub fn new(
+        rom: &[u8],
+        quirks: QuirksProfile,
+        rng_mode: RngMode,
+        seed: u64,
+        timing: Timing,
+    ) -> Self {
+        Self {
+            rom_hash: rom_hash(rom),
+            quirks,
+            rng_mode,
+            seed,
+            timing,
+            final_hash: None,
+            frames: Vec::new(),
+        }
+    }
+
+    /// Number of recorded frames.
+    pub fn len(&self) -> usize {
+        self.frames.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+
+    /// Records the keys held during the next frame; call before running it.
+    pub fn record_frame(&mut self, emu: &Emu) {
+        let mask = emu
+            .keys()
+            .iter()
+            .enumerate()
+            .filter(|(_, &held)| held)
+            .fold(0, |mask, (key, _)| mask | 1 << key);
+        self.frames.push(mask);
+    }
+
+    /// Sets the keypad of `emu` to the keys of `frame`; call before running
+    /// it. Returns false once the movie is over.
+    pub fn apply_frame(&self, frame: usize, emu: &mut Emu) -> bool {
+        match self.frames.get(frame) {
+            Some(&mask) => {
+                for key in 0..16 {
+                    emu.keypress(key, mask & 1 << key != 0);
+                }
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Stores the hash of the screen after the last frame.
+    pub fn finish(&mut self, emu: &Emu) {
+        self.final_hash = Some(display_hash(emu));
+    }
+
+    /// Checks that the movie was made with `rom`.
+    pub fn check_rom(&self, rom: &[u8]) -> Result<(), String> {
+        let hash = rom_hash(rom);
+        if hash == self.rom_hash {
+            Ok(())
+        } else {
+            Err(format!(
+                "movie is for another ROM (hash {:016x}, loaded ROM is {:016x})",
+                self.rom_hash, hash
+            ))
+        }
+    }
+
+    /// The key changes of the movie, for a headless run.
+    pub fn input_script(&self) -> InputScript {
+        let mut events = Vec::new();
+        let mut held = 0u16;
+        for (frame, &mask) in self.frames.iter().enumerate() {
+            for key in (0..16).filter(|key| (mask ^ held) & 1 << key != 0) {
+                events.push(InputEvent {
+                    frame: frame as u64,
+                    key,
+                    pressed: mask & 1 << key != 0,
+                });
+            }
+            held = mask;
+        }
+        InputScript::from_events(events)
+    }
+}
+
+impl fmt::Display for Movie {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "c8m 1")?;
+        writeln!(f, "rom {:016x}", self.rom_hash)?;
+        writeln!(f, "quirks {}", self.quirks.id())?;
+        writeln!(f, "rng {}", self.rng_mode)?;
+        writeln!(f, "seed {}", self.seed)?;
+        match self.timing {
+            Timing::Ips(ips) => writeln!(f, "timing {}", ips)?,
+            Timing::CosmacVip => writeln!(f, "timing vip")?,
+        }
+        if let Some(hash) = self.final_hash {
+            writeln!(f, "hash {:016x}", hash)?;
+        }
+        writeln!(f, "input")?;
+        for &mask in &self.frames {
+            let line: String = (0..16)
+                .map(|key| {
+                    if mask & 1 << key != 0 {
+                        char::from_digit(key, 16).unwrap().to_ascii_uppercase()
+                    } else {
+                        '.'
+                    }
+                })
+                .collect();
+            writeln!(f, "{}", line)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Movie {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut lines = s.lines().enumerate();
+        let mut movie = Movie::new(
+            &[],
+            QuirksProfile::default(),
+            RngMode::default(),
+            0,
+            Timing::default(),
+        );
+        let mut rom = None;
+
+        match lines.next() {
+            Some((_, "c8m 1")) => {}
+            _ => return Err("not a c8m movie (version 1)".to_string()),
+        }
+
+        // Header up to `input`
+        let mut input = false;
+        for (n, line) in lines.by_ref() {
+            let err = |e: String| format!("line {}: {}", n + 1, e);
+            if line == "input" {
+                input = true;
+                break;
+            }
+            let (name, value) = line
+                .split_once(' ')
+                .ok_or_else(|| err(format!("expected '<name> <value>', got '{}'", line)))?;
+            match name {
+                "rom" => rom = Some(parse_hash(value).map_err(err)?),
+                "quirks" => movie.quirks = value.parse().map_err(err)?,
+                "rng" => movie.rng_mode = value.parse().map_err(err)?,
+                "seed" => {
+                    movie.seed = value
+                        .parse()
+                        .map_err(|_| err(format!("invalid seed '{}'", value)))?;
+                }
+                "timing" => movie.timing = value.parse().map_err(err)?,
+                "hash" => movie.final_hash = Some(parse_hash(value).map_err(err)?),
+                _ => return Err(err(format!("unknown header '{}'", name))),
+            }
+        }
+        movie.rom_hash = rom.ok_or("movie has no rom hash")?;
+        if !input {
+            return Err("movie has no 'input' line".to_string());
+        }
+
+        for (n, line) in lines {
+            let mut mask = 0u16;
+            if line.len() != 16 {
+                return Err(format!("line {}: expected 16 keys, got '{}'", n + 1, line));
+            }
+            for (key, c) in line.chars().enumerate() {
+                match c {
+                    '.' => {}
+                    c if c.to_digit(16) == Some(key as u32) => mask |= 1 << key,
+                    c => {
+                        return Err(format!(
+                            "line {}: '{}' in the column of key {:X}",
+                            n + 1,
+                            c,
+                            key
+                        ))
+                    }
+                }
+            }
+            movie.frames.push(mask);
+        }
+        Ok(movie)
+    }
+}
+
+fn parse_hash(s: &str) -> Result<u64, String> {
+    u64::from_str_radix(s, 16).map_err(|_| format!("invalid hash '{}'", s))
+}
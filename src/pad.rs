@@ -0,0 +1,177 @@
+// src/pad.rs
+//! Game controllers on the hex keypad.
+//!
+//! Controller inputs are named as in SDL's game controller database: buttons
+//! such as `a`, `start` or `dpup`, and stick or trigger directions as the axis
+//! name plus a sign, e.g. `leftx-` (left) or `lefty+` (down). The frontend
+//! turns its controller events into [`PadEvent`]s, which also makes the
+//! mapping easy to drive with synthetic events.
+use crate::emu::Emu;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// How far a stick or trigger must move before it counts as pressed.
+pub const AXIS_THRESHOLD: i16 = 16_000;
+
+/// Something a controller did.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PadEvent {
+    Button {
+        pad: u32,
+        button: String,
+        pressed: bool,
+    },
+    Axis {
+        pad: u32,
+        axis: String,
+        value: i16,
+    },
+    /// The controller was unplugged; everything it held is released.
+    Removed {
+        pad: u32,
+    },
+}
+
+/// Controller inputs bound to CHIP-8 keys.
+///
+/// ```
+/// use chip8_emu::pad::PadMap;
+///
+/// let map = PadMap::default();
+/// assert_eq!(map.key("dpup"), Some(0x5));
+/// assert_eq!(map.key("leftx-"), Some(0x7));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "BTreeMap<String, usize>", into = "BTreeMap<String, usize>")]
+pub struct PadMap {
+    bindings: BTreeMap<String, usize>,
+}
+
+impl TryFrom<BTreeMap<String, usize>> for PadMap {
+    type Error = String;
+
+    /// Rejects bindings to keys outside 0-F.
+    fn try_from(bindings: BTreeMap<String, usize>) -> Result<Self, Self::Error> {
+        match bindings.iter().find(|(_, &key)| key > 0xF) {
+            Some((input, key)) => Err(format!("'{}' is bound to key {}, expected 0-F", input, key)),
+            None => Ok(Self { bindings }),
+        }
+    }
+}
+
+impl From<PadMap> for BTreeMap<String, usize> {
+    fn from(map: PadMap) -> Self {
+        map.bindings
+    }
+}
+
+impl Default for PadMap {
+    /// D-pad and left stick on 5/7/8/9 (up, left, down, right), the layout
+    /// many games use, with A on 6 and B on 4.
+    fn default() -> Self {
+        let bindings = [
+            ("dpup", 0x5),
+            ("dpleft", 0x7),
+            ("dpdown", 0x8),
+            ("dpright", 0x9),
+            ("lefty-", 0x5),
+            ("leftx-", 0x7),
+            ("lefty+", 0x8),
+            ("leftx+", 0x9),
+            ("a", 0x6),
+            ("b", 0x4),
+        ];
+        Self {
+            bindings: bindings
+                .into_iter()
+                .map(|(input, key)| (input.to_string(), key))
+                .collect(),
+        }
+    }
+}
+
+impl PadMap {
+    /// A map with nothing bound.
+    pub fn empty() -> Self {
+        Self {
+            bindings: BTreeMap::new(),
+        }
+    }
+
+    /// The CHIP-8 key an input is bound to.
+    pub fn key(&self, input: &str) -> Option<usize> {
+        self.bindings.get(input).copied()
+    }
+
+    pub fn bind(&mut self, input: &str, key: usize) {
+        self.bindings.insert(input.to_string(), key & 0xF);
+    }
+
+    pub fn unbind(&mut self, input: &str) {
+        self.bindings.remove(input);
+    }
+
+    /// All (input, CHIP-8 key) pairs.
+    pub fn bindings(&self) -> impl Iterator<Item = (&str, usize)> {
+        self.bindings
+            .iter()
+            .map(|(input, &key)| (input.as_str(), key))
+    }
+}
+
+/// Which controller inputs are held, across all connected controllers.
+#[derive(Debug, Clone, Default)]
+pub struct PadState {
+    active: BTreeSet<(u32, String)>,
+    held: [bool; 16], // CHIP-8 keys currently held by a controller
+}
+
+impl PadState {
+    /// Applies an event to the keypad of `emu`. A key stays down while any
+    /// input bound to it is held on any controller.
+    pub fn apply(&mut self, map: &PadMap, event: &PadEvent, emu: &mut Emu) {
+        match event {
+            PadEvent::Button {
+                pad,
+                button,
+                pressed,
+            } => self.set(*pad, button.clone(), *pressed),
+            PadEvent::Axis { pad, axis, value } => {
+                self.set(*pad, format!("{}-", axis), *value <= -AXIS_THRESHOLD);
+                self.set(*pad, format!("{}+", axis), *value >= AXIS_THRESHOLD);
+            }
+            PadEvent::Removed { pad } => self.active.retain(|(p, _)| p != pad),
+        }
+
+        let mut held = [false; 16];
+        for (_, input) in &self.active {
+            if let Some(key) = map.key(input) {
+                held[key] = true;
+            }
+        }
+        for (key, (&now, was)) in held.iter().zip(self.held.iter_mut()).enumerate() {
+            if now != *was {
+                emu.keypress(key, now);
+                *was = now;
+            }
+        }
+    }
+
+    /// Presses the keys controllers hold again, after the keypad of `emu`
+    /// was reset (e.g. by loading a state).
+    pub fn sync(&self, emu: &mut Emu) {
+        for (key, &held) in self.held.iter().enumerate() {
+            if held {
+                emu.keypress(key, true);
+            }
+        }
+    }
+
+    fn set(&mut self, pad: u32, input: String, active: bool) {
+        if active {
+            self.active.insert((pad, input));
+        } else {
+            self.active.remove(&(pad, input));
+        }
+    }
+}
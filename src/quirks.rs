@@ -0,0 +1,171 @@
+// src/quirks.rs
+//! Behaviour switches for the opcodes that historical interpreters disagree on.
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+/// How `FX55`/`FX65` leave the I register after a register dump/load.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum IndexIncrement {
+    /// I is not modified (SUPER-CHIP 1.1 and most modern interpreters).
+    Unchanged,
+    /// I is advanced by X (CHIP-48, SUPER-CHIP 1.0).
+    ByX,
+    /// I is advanced by X + 1, ending just past the last register (COSMAC VIP).
+    ByXPlusOne,
+}
+
+/// Interpretation of every ambiguous opcode.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Quirks {
+    /// `8XY6`/`8XYE` shift VY into VX instead of shifting VX in place.
+    pub shift_uses_vy: bool,
+    /// What `FX55`/`FX65` do to I.
+    pub load_store: IndexIncrement,
+    /// `8XY1`/`8XY2`/`8XY3` reset VF to 0.
+    pub vf_reset: bool,
+    /// Jump with offset is `BXNN` (XNN + VX) instead of `BNNN` (NNN + V0).
+    pub jump_uses_vx: bool,
+    /// Sprites crossing the screen edge wrap around instead of being clipped.
+    pub wrap_sprites: bool,
+    /// `DXYN` waits for the next vertical blank, limiting draws to one per frame.
+    pub display_wait: bool,
+    /// Enables the XO-CHIP extensions: 64K address space (`F000 NNNN`), two
+    /// display bitplanes (`FN01`), `5XY2`/`5XY3` and the audio pattern
+    /// (`F002`, `FX3A`). Without it those opcodes are unknown.
+    pub xo_chip: bool,
+}
+
+impl Default for Quirks {
+    fn default() -> Self {
+        QuirksProfile::default().quirks()
+    }
+}
+
+/// Named quirk presets matching well-known interpreters.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum QuirksProfile {
+    /// The original 1977 interpreter on the RCA COSMAC VIP.
+    CosmacVip,
+    /// CHIP-48 on the HP-48 calculators.
+    Chip48,
+    /// SUPER-CHIP 1.1 on the HP-48.
+    SuperChip,
+    /// XO-CHIP, as defined by Octo.
+    XoChip,
+    /// The behaviour most modern ROMs and interpreters assume.
+    #[default]
+    Modern,
+}
+
+impl QuirksProfile {
+    pub fn all() -> Vec<QuirksProfile> {
+        vec![
+            QuirksProfile::CosmacVip,
+            QuirksProfile::Chip48,
+            QuirksProfile::SuperChip,
+            QuirksProfile::XoChip,
+            QuirksProfile::Modern,
+        ]
+    }
+
+    /// Human readable name, for menus.
+    pub fn name(&self) -> &'static str {
+        match self {
+            QuirksProfile::CosmacVip => "COSMAC VIP",
+            QuirksProfile::Chip48 => "CHIP-48",
+            QuirksProfile::SuperChip => "SUPER-CHIP",
+            QuirksProfile::XoChip => "XO-CHIP",
+            QuirksProfile::Modern => "Modern",
+        }
+    }
+
+    /// Short identifier accepted on the command line.
+    pub fn id(&self) -> &'static str {
+        match self {
+            QuirksProfile::CosmacVip => "vip",
+            QuirksProfile::Chip48 => "chip48",
+            QuirksProfile::SuperChip => "schip",
+            QuirksProfile::XoChip => "xochip",
+            QuirksProfile::Modern => "modern",
+        }
+    }
+
+    pub fn quirks(&self) -> Quirks {
+        match self {
+            QuirksProfile::CosmacVip => Quirks {
+                shift_uses_vy: true,
+                load_store: IndexIncrement::ByXPlusOne,
+                vf_reset: true,
+                jump_uses_vx: false,
+                wrap_sprites: false,
+                display_wait: true,
+                xo_chip: false,
+            },
+            QuirksProfile::Chip48 => Quirks {
+                shift_uses_vy: false,
+                load_store: IndexIncrement::ByX,
+                vf_reset: false,
+                jump_uses_vx: true,
+                wrap_sprites: false,
+                display_wait: false,
+                xo_chip: false,
+            },
+            QuirksProfile::SuperChip => Quirks {
+                shift_uses_vy: false,
+                load_store: IndexIncrement::Unchanged,
+                vf_reset: false,
+                jump_uses_vx: true,
+                wrap_sprites: false,
+                display_wait: false,
+                xo_chip: false,
+            },
+            QuirksProfile::XoChip => Quirks {
+                shift_uses_vy: true,
+                load_store: IndexIncrement::ByXPlusOne,
+                vf_reset: false,
+                jump_uses_vx: false,
+                wrap_sprites: true,
+                display_wait: false,
+                xo_chip: true,
+            },
+            QuirksProfile::Modern => Quirks {
+                shift_uses_vy: false,
+                load_store: IndexIncrement::Unchanged,
+                vf_reset: false,
+                jump_uses_vx: false,
+                wrap_sprites: false,
+                display_wait: false,
+                xo_chip: false,
+            },
+        }
+    }
+}
+
+impl fmt::Display for QuirksProfile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for QuirksProfile {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "vip" | "cosmac-vip" | "chip8" | "chip-8" => Ok(QuirksProfile::CosmacVip),
+            "chip48" | "chip-48" => Ok(QuirksProfile::Chip48),
+            "schip" | "superchip" | "super-chip" => Ok(QuirksProfile::SuperChip),
+            "xochip" | "xo-chip" => Ok(QuirksProfile::XoChip),
+            "modern" => Ok(QuirksProfile::Modern),
+            _ => {
+                let ids: Vec<&str> = QuirksProfile::all().iter().map(|p| p.id()).collect();
+                Err(format!(
+                    "unknown quirks profile '{}' (expected one of: {})",
+                    s,
+                    ids.join(", ")
+                ))
+            }
+        }
+    }
+}
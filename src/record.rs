@@ -0,0 +1,339 @@
+// src/record.rs
+//! Gameplay recordings: the screen as an animated GIF or a raw Y4M video, the
+//! beeper as a WAV file.
+//!
+//! Recorders take one frame per emulated frame, after the timers ticked, so a
+//! recording always plays back at 60 fps, whatever speed it was made at.
+use crate::emu::Emu;
+use std::borrow::Cow;
+use std::fmt;
+use std::io::{self, Seek, SeekFrom, Write};
+use std::str::FromStr;
+
+/// Frames per second of every recording.
+pub const FRAME_RATE: u32 = 60;
+/// Samples per second of WAV recordings.
+pub const SAMPLE_RATE: u32 = 44_100;
+/// Pitch of the beeper outside XO-CHIP, as played by the SDL frontend.
+pub const BEEP_FREQUENCY: f32 = 440.0;
+
+const BEEP_AMPLITUDE: f32 = 0.15 * i16::MAX as f32;
+
+/// File format of a video recording.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum VideoFormat {
+    /// Animated GIF with the 4-colour palette; tiny for CHIP-8 screens.
+    #[default]
+    Gif,
+    /// Uncompressed YUV4MPEG2 (4:4:4), which ffmpeg and most players read.
+    Y4m,
+}
+
+impl VideoFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            VideoFormat::Gif => "gif",
+            VideoFormat::Y4m => "y4m",
+        }
+    }
+}
+
+impl fmt::Display for VideoFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.extension())
+    }
+}
+
+impl FromStr for VideoFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "gif" => Ok(VideoFormat::Gif),
+            "y4m" => Ok(VideoFormat::Y4m),
+            _ => Err(format!(
+                "unknown video format '{}' (expected gif or y4m)",
+                s
+            )),
+        }
+    }
+}
+
+enum Video<W: Write> {
+    Gif {
+        encoder: gif::Encoder<W>,
+        pending: Vec<u8>, // Last frame, written once it changes
+        pending_since: u64,
+    },
+    Y4m {
+        writer: W,
+        palette: [[u8; 3]; 4], // Y, Cb, Cr
+    },
+}
+
+/// Writes the screen of every emulated frame to a video file.
+///
+/// The size is fixed when the recording starts; frames in the other
+/// resolution (lores/hires) are scaled to fit.
+pub struct VideoRecorder<W: Write> {
+    video: Video<W>,
+    width: usize,
+    height: usize,
+    scale: usize,
+    frames: u64,
+}
+
+impl<W: Write> VideoRecorder<W> {
+    /// Starts a recording of `width` x `height` CHIP-8 pixels, each `scale`
+    /// pixels wide, in the colours of `palette` (indexed like
+    /// [`Emu::get_display`]).
+    pub fn new(
+        mut writer: W,
+        format: VideoFormat,
+        (width, height): (usize, usize),
+        scale: u32,
+        palette: &[[u8; 3]; 4],
+    ) -> io::Result<Self> {
+        let scale = scale.max(1) as usize;
+        let (out_width, out_height) = (width * scale, height * scale);
+        let video = match format {
+            VideoFormat::Gif => {
+                let mut encoder = gif::Encoder::new(
+                    writer,
+                    out_width as u16,
+                    out_height as u16,
+                    &palette.concat(),
+                )
+                .map_err(gif_error)?;
+                encoder
+                    .set_repeat(gif::Repeat::Infinite)
+                    .map_err(gif_error)?;
+                Video::Gif {
+                    encoder,
+                    pending: Vec::new(),
+                    pending_since: 0,
+                }
+            }
+            VideoFormat::Y4m => {
+                writeln!(
+                    writer,
+                    "YUV4MPEG2 W{} H{} F{}:1 Ip A1:1 C444",
+                    out_width, out_height, FRAME_RATE
+                )?;
+                Video::Y4m {
+                    writer,
+                    palette: palette.map(rgb_to_ycbcr),
+                }
+            }
+        };
+        Ok(Self {
+            video,
+            width,
+            height,
+            scale,
+            frames: 0,
+        })
+    }
+
+    /// Adds the current screen of `emu` as the next frame.
+    pub fn add_frame(&mut self, emu: &Emu) -> io::Result<()> {
+        let pixels = self.pixels(emu);
+        let frame = self.frames;
+        self.frames += 1;
+
+        match &mut self.video {
+            Video::Gif {
+                encoder,
+                pending,
+                pending_since,
+            } => {
+                if frame == 0 {
+                    *pending = pixels;
+                } else if pixels != *pending {
+                    // GIF delays are in 1/100 s and viewers slow down anything
+                    // under 2/100 s, so a shorter frame is replaced by the next
+                    if centiseconds(frame) - centiseconds(*pending_since) >= 2 {
+                        let (width, height) = (self.width * self.scale, self.height * self.scale);
+                        write_gif_frame(encoder, pending, width, height, *pending_since, frame)?;
+                        *pending_since = frame;
+                    }
+                    *pending = pixels;
+                }
+                Ok(())
+            }
+            Video::Y4m { writer, palette } => {
+                writer.write_all(b"FRAME\n")?;
+                // Planar: all Y, then all Cb, then all Cr
+                for plane in 0..3 {
+                    let data: Vec<u8> = pixels
+                        .iter()
+                        .map(|&px| palette[px as usize])
+                        .map(|ycbcr| ycbcr[plane])
+                        .collect();
+                    writer.write_all(&data)?;
+                }
+                Ok(())
+            }
+        }
+    }
+
+    /// Frames recorded so far.
+    pub fn frames(&self) -> u64 {
+        self.frames
+    }
+
+    /// Writes what is still buffered and returns the writer.
+    pub fn finish(self) -> io::Result<W> {
+        match self.video {
+            Video::Gif {
+                mut encoder,
+                pending,
+                pending_since,
+            } => {
+                if self.frames > 0 {
+                    let (width, height) = (self.width * self.scale, self.height * self.scale);
+                    let end = self.frames.max(pending_since + 2);
+                    write_gif_frame(&mut encoder, &pending, width, height, pending_since, end)?;
+                }
+                encoder.into_inner()
+            }
+            Video::Y4m { mut writer, .. } => {
+                writer.flush()?;
+                Ok(writer)
+            }
+        }
+    }
+
+    // The screen at the recording size, one palette index per output pixel
+    fn pixels(&self, emu: &Emu) -> Vec<u8> {
+        let display = emu.get_display();
+        let (src_width, src_height) = (emu.width(), emu.height());
+        let (out_width, out_height) = (self.width * self.scale, self.height * self.scale);
+
+        let mut pixels = Vec::with_capacity(out_width * out_height);
+        for y in 0..out_height {
+            let src_y = y / self.scale * src_height / self.height;
+            let row = &display[src_y * src_width..][..src_width];
+            pixels.extend((0..out_width).map(|x| row[x / self.scale * src_width / self.width] & 3));
+        }
+        pixels
+    }
+}
+
+// Time of frame `n` in 1/100 s, rounded down
+fn centiseconds(frame: u64) -> u64 {
+    frame * 100 / FRAME_RATE as u64
+}
+
+fn write_gif_frame<W: Write>(
+    encoder: &mut gif::Encoder<W>,
+    pixels: &[u8],
+    width: usize,
+    height: usize,
+    start: u64,
+    end: u64,
+) -> io::Result<()> {
+    let frame = gif::Frame {
+        width: width as u16,
+        height: height as u16,
+        delay: (centiseconds(end) - centiseconds(start)).min(u16::MAX as u64) as u16,
+        buffer: Cow::Borrowed(pixels),
+        ..gif::Frame::default()
+    };
+    encoder.write_frame(&frame).map_err(gif_error)
+}
+
+fn gif_error(e: gif::EncodingError) -> io::Error {
+    match e {
+        gif::EncodingError::Io(e) => e,
+        e => io::Error::new(io::ErrorKind::InvalidInput, e),
+    }
+}
+
+// BT.601 limited range, as Y4M players expect
+fn rgb_to_ycbcr([r, g, b]: [u8; 3]) -> [u8; 3] {
+    let (r, g, b) = (r as f32, g as f32, b as f32);
+    let y = 16.0 + (65.738 * r + 129.057 * g + 25.064 * b) / 256.0;
+    let cb = 128.0 + (-37.945 * r - 74.494 * g + 112.439 * b) / 256.0;
+    let cr = 128.0 + (112.439 * r - 94.154 * g - 18.285 * b) / 256.0;
+    [y, cb, cr].map(|c| c.round().clamp(0.0, 255.0) as u8)
+}
+
+/// Writes the beeper to a 16-bit mono WAV file: a tone while the sound timer
+/// is running, silence otherwise.
+///
+/// XO-CHIP programs are recorded with their own audio pattern and pitch.
+pub struct AudioRecorder<W: Write + Seek> {
+    writer: W,
+    samples: u32,
+    phase: f32, // Position in the tone (0-1) or XO-CHIP pattern (0-128)
+}
+
+impl<W: Write + Seek> AudioRecorder<W> {
+    pub fn new(mut writer: W) -> io::Result<Self> {
+        write_wav_header(&mut writer, 0)?;
+        Ok(Self {
+            writer,
+            samples: 0,
+            phase: 0.0,
+        })
+    }
+
+    /// Adds one frame (1/60 s) of sound for the current state of `emu`.
+    pub fn add_frame(&mut self, emu: &Emu) -> io::Result<()> {
+        let count = SAMPLE_RATE / FRAME_RATE;
+        let mut data = Vec::with_capacity(count as usize * 2);
+
+        for _ in 0..count {
+            let sample = if emu.get_sound_timer() == 0 {
+                0.0
+            } else if emu.quirks().xo_chip {
+                let pattern = emu.audio_pattern();
+                let bit = self.phase as usize;
+                self.phase = (self.phase + emu.audio_playback_rate() / SAMPLE_RATE as f32) % 128.0;
+                if (pattern[bit / 8] >> (7 - bit % 8)) & 1 != 0 {
+                    BEEP_AMPLITUDE
+                } else {
+                    -BEEP_AMPLITUDE
+                }
+            } else {
+                let high = self.phase <= 0.5;
+                self.phase = (self.phase + BEEP_FREQUENCY / SAMPLE_RATE as f32) % 1.0;
+                if high {
+                    BEEP_AMPLITUDE
+                } else {
+                    -BEEP_AMPLITUDE
+                }
+            };
+            data.extend_from_slice(&(sample as i16).to_le_bytes());
+        }
+
+        self.writer.write_all(&data)?;
+        self.samples += count;
+        Ok(())
+    }
+
+    /// Fills in the sizes in the header and returns the writer.
+    pub fn finish(mut self) -> io::Result<W> {
+        self.writer.seek(SeekFrom::Start(0))?;
+        write_wav_header(&mut self.writer, self.samples * 2)?;
+        self.writer.seek(SeekFrom::End(0))?;
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+}
+
+fn write_wav_header(writer: &mut impl Write, data_size: u32) -> io::Result<()> {
+    writer.write_all(b"RIFF")?;
+    writer.write_all(&(36 + data_size).to_le_bytes())?;
+    writer.write_all(b"WAVEfmt ")?;
+    writer.write_all(&16u32.to_le_bytes())?; // fmt chunk size
+    writer.write_all(&1u16.to_le_bytes())?; // PCM
+    writer.write_all(&1u16.to_le_bytes())?; // Mono
+    writer.write_all(&SAMPLE_RATE.to_le_bytes())?;
+    writer.write_all(&(SAMPLE_RATE * 2).to_le_bytes())?; // Bytes per second
+    writer.write_all(&2u16.to_le_bytes())?; // Bytes per sample
+    writer.write_all(&16u16.to_le_bytes())?; // Bits per sample
+    writer.write_all(b"data")?;
+    writer.write_all(&data_size.to_le_bytes())
+}
@@ -0,0 +1,82 @@
+// src/recording.rs
+//! The F10 recording toggle: a video of every emulated frame, with the beeper
+//! in a WAV file next to it if asked for.
+use crate::gui::palette::Colors;
+use crate::storage;
+use chip8_emu::record::{AudioRecorder, VideoFormat, VideoRecorder};
+use chip8_emu::Emu;
+use std::fs::{self, File};
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+
+/// How recordings are made, from the command line.
+#[derive(Debug, Clone)]
+pub struct RecordOptions {
+    pub dir: PathBuf,
+    pub format: VideoFormat,
+    pub scale: u32,
+    pub wav: bool,
+    pub colors: Colors,
+}
+
+pub struct Recording {
+    video: VideoRecorder<BufWriter<File>>,
+    audio: Option<AudioRecorder<BufWriter<File>>>,
+    path: PathBuf,
+}
+
+impl Recording {
+    /// Starts a recording at the current screen resolution.
+    pub fn start(options: &RecordOptions, rom_path: &str, chip8: &Emu) -> Result<Self, String> {
+        fs::create_dir_all(&options.dir).map_err(|e| e.to_string())?;
+        let path = storage::timestamped_path(&options.dir, rom_path, options.format.extension());
+        let create = |path: &Path| {
+            File::create(path)
+                .map(BufWriter::new)
+                .map_err(|e| format!("{}: {}", path.display(), e))
+        };
+
+        let video = VideoRecorder::new(
+            create(&path)?,
+            options.format,
+            (chip8.width(), chip8.height()),
+            options.scale,
+            &options.colors,
+        )
+        .map_err(|e| e.to_string())?;
+        let audio = if options.wav {
+            let file = create(&path.with_extension("wav"))?;
+            Some(AudioRecorder::new(file).map_err(|e| e.to_string())?)
+        } else {
+            None
+        };
+
+        Ok(Self { video, audio, path })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn frames(&self) -> u64 {
+        self.video.frames()
+    }
+
+    /// Records the frame that was just emulated.
+    pub fn add_frame(&mut self, chip8: &Emu) -> Result<(), String> {
+        self.video.add_frame(chip8).map_err(|e| e.to_string())?;
+        if let Some(audio) = &mut self.audio {
+            audio.add_frame(chip8).map_err(|e| e.to_string())?;
+        }
+        Ok(())
+    }
+
+    /// Finishes the files; they are not playable before this.
+    pub fn stop(self) -> Result<(), String> {
+        self.video.finish().map_err(|e| e.to_string())?;
+        if let Some(audio) = self.audio {
+            audio.finish().map_err(|e| e.to_string())?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,159 @@
+// src/rewind.rs
+//! Ring buffer of past machine states for rewinding.
+//!
+//! Only the newest state is kept in full. Every older frame is stored as the
+//! XOR of itself with the frame after it, run-length encoded: a CHIP-8 state
+//! barely changes from one frame to the next, so most deltas shrink to a few
+//! dozen bytes. Stepping back XORs the newest delta into the current state.
+use std::collections::VecDeque;
+
+/// One minute at 60 frames per second.
+pub const DEFAULT_REWIND_FRAMES: usize = 60 * 60;
+
+enum Entry {
+    /// XOR against the next (newer) state, zero-run encoded.
+    Delta(Vec<u8>),
+    /// The whole state, used when its size differs from the next one
+    /// (e.g. across a lo-res/hi-res switch).
+    Full(Vec<u8>),
+}
+
+impl Entry {
+    fn size(&self) -> usize {
+        match self {
+            Entry::Delta(data) | Entry::Full(data) => data.len(),
+        }
+    }
+}
+
+/// Holds up to `capacity` frames of history behind the current state.
+pub struct RewindBuffer {
+    entries: VecDeque<Entry>,
+    head: Option<Vec<u8>>,
+    capacity: usize,
+}
+
+impl Default for RewindBuffer {
+    fn default() -> Self {
+        Self::new(DEFAULT_REWIND_FRAMES)
+    }
+}
+
+impl RewindBuffer {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            entries: VecDeque::new(),
+            head: None,
+            capacity,
+        }
+    }
+
+    /// Records the state of a newly emulated frame (from [`crate::Emu::save_state`]).
+    pub fn push(&mut self, state: Vec<u8>) {
+        if let Some(prev) = self.head.take() {
+            let entry = if prev.len() == state.len() {
+                let xor: Vec<u8> = prev.iter().zip(&state).map(|(a, b)| a ^ b).collect();
+                Entry::Delta(encode_zero_runs(&xor))
+            } else {
+                Entry::Full(prev)
+            };
+            self.entries.push_back(entry);
+            if self.entries.len() > self.capacity {
+                self.entries.pop_front();
+            }
+        }
+        self.head = Some(state);
+    }
+
+    /// Steps one frame back and returns that state, ready for
+    /// [`crate::Emu::load_state`]. Returns `None` once the history is used up.
+    pub fn rewind(&mut self) -> Option<&[u8]> {
+        let entry = self.entries.pop_back()?;
+        let head = self.head.as_mut()?;
+        match entry {
+            Entry::Delta(delta) => decode_zero_runs_xor(&delta, head),
+            Entry::Full(state) => *head = state,
+        }
+        self.head.as_deref()
+    }
+
+    /// Number of frames that can be rewound.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.head = None;
+    }
+
+    /// Approximate heap usage in bytes.
+    pub fn memory_usage(&self) -> usize {
+        self.entries.iter().map(Entry::size).sum::<usize>() + self.head.as_ref().map_or(0, Vec::len)
+    }
+}
+
+// Encodes as repeated (zero run length, literal length, literal bytes), both
+// lengths as LEB128 varints
+fn encode_zero_runs(data: &[u8]) -> Vec<u8> {
+    let mut out = Vec::new();
+    let mut pos = 0;
+    while pos < data.len() {
+        let zeros = data[pos..].iter().take_while(|&&b| b == 0).count();
+        pos += zeros;
+        let literals = data[pos..].iter().take_while(|&&b| b != 0).count();
+        push_varint(&mut out, zeros);
+        push_varint(&mut out, literals);
+        out.extend_from_slice(&data[pos..pos + literals]);
+        pos += literals;
+    }
+    out
+}
+
+// XORs an encoded delta into `target` in place
+fn decode_zero_runs_xor(encoded: &[u8], target: &mut [u8]) {
+    let mut src = 0;
+    let mut pos = 0;
+    while src < encoded.len() {
+        pos += read_varint(encoded, &mut src);
+        let literals = read_varint(encoded, &mut src);
+        for (dst, byte) in target[pos..pos + literals]
+            .iter_mut()
+            .zip(&encoded[src..src + literals])
+        {
+            *dst ^= byte;
+        }
+        src += literals;
+        pos += literals;
+    }
+}
+
+fn push_varint(out: &mut Vec<u8>, mut value: usize) {
+    loop {
+        let byte = (value & 0x7F) as u8;
+        value >>= 7;
+        if value == 0 {
+            out.push(byte);
+            return;
+        }
+        out.push(byte | 0x80);
+    }
+}
+
+fn read_varint(data: &[u8], pos: &mut usize) -> usize {
+    let mut value = 0;
+    let mut shift = 0;
+    loop {
+        let byte = data[*pos];
+        *pos += 1;
+        value |= ((byte & 0x7F) as usize) << shift;
+        if byte & 0x80 == 0 {
+            return value;
+        }
+        shift += 7;
+    }
+}
@@ -0,0 +1,107 @@
+// src/rng.rs
+//! Seedable random number source for `CXNN`.
+//!
+//! The generator is part of the machine state, so a run started from the
+//! same seed (or restored from a save state) produces the same random bytes.
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+/// Algorithm used to produce random bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum RngMode {
+    /// xorshift64*: long period, well distributed.
+    #[default]
+    Xorshift,
+}
+
+impl RngMode {
+    pub fn id(&self) -> &'static str {
+        match self {
+            RngMode::Xorshift => "xorshift",
+        }
+    }
+}
+
+impl fmt::Display for RngMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.id())
+    }
+}
+
+impl FromStr for RngMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "xorshift" => Ok(RngMode::Xorshift),
+            _ => Err(format!("unknown RNG mode '{}' (expected xorshift)", s)),
+        }
+    }
+}
+
+/// The random number generator owned by [`crate::Emu`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rng {
+    pub(crate) mode: RngMode,
+    pub(crate) seed: u64,
+    pub(crate) state: u64,
+}
+
+impl Default for Rng {
+    fn default() -> Self {
+        Self::new(RngMode::default(), 0)
+    }
+}
+
+impl Rng {
+    pub fn new(mode: RngMode, seed: u64) -> Self {
+        let mut rng = Rng {
+            mode,
+            seed,
+            state: 0,
+        };
+        rng.reseed(seed);
+        rng
+    }
+
+    /// Restarts the sequence from `seed`.
+    pub fn reseed(&mut self, seed: u64) {
+        self.seed = seed;
+        self.state = match self.mode {
+            // xorshift must never be seeded with zero; splitmix64 spreads the
+            // seed bits and practically never yields it
+            RngMode::Xorshift => splitmix64(seed).max(1),
+        };
+    }
+
+    pub fn mode(&self) -> RngMode {
+        self.mode
+    }
+
+    /// The seed the current sequence started from.
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Next random byte.
+    pub fn next_byte(&mut self) -> u8 {
+        match self.mode {
+            RngMode::Xorshift => {
+                let mut x = self.state;
+                x ^= x >> 12;
+                x ^= x << 25;
+                x ^= x >> 27;
+                self.state = x;
+                (x.wrapping_mul(0x2545_F491_4F6C_DD1D) >> 56) as u8
+            }
+        }
+    }
+}
+
+fn splitmix64(seed: u64) -> u64 {
+    let mut z = seed.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
@@ -0,0 +1,265 @@
+// src/speed.rs
+//! Runtime speed control for the SDL frontend: pause, frame advance,
+//! fast-forward and slow motion.
+//!
+//! Everything here only changes how many emulated frames run per displayed
+//! frame. Each emulated frame still ticks the timers once, so DT and ST stay
+//! in step with the program at any speed.
+use std::fmt;
+use std::str::FromStr;
+
+/// Emulated frames per displayed frame in slow motion is 1/this.
+pub const SLOW_MOTION_DIVISOR: u32 = 4;
+/// Largest fast-forward factor; beyond it `max` is the better choice anyway.
+pub const MAX_FAST_FORWARD: u32 = 64;
+
+/// How fast fast-forward runs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FastForward {
+    /// N emulated frames per displayed frame.
+    Times(u32),
+    /// As many frames as fit in the time of one displayed frame.
+    Uncapped,
+}
+
+impl Default for FastForward {
+    fn default() -> Self {
+        FastForward::Times(4)
+    }
+}
+
+impl fmt::Display for FastForward {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FastForward::Times(n) => write!(f, "{}x", n),
+            FastForward::Uncapped => f.write_str("max"),
+        }
+    }
+}
+
+impl FromStr for FastForward {
+    type Err = String;
+
+    /// A factor such as `4`, or `max` for uncapped.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s == "max" {
+            return Ok(FastForward::Uncapped);
+        }
+        match s.trim_end_matches('x').parse() {
+            Ok(n) if (2..=MAX_FAST_FORWARD).contains(&n) => Ok(FastForward::Times(n)),
+            _ => Err(format!(
+                "invalid fast-forward speed '{}' (expected a factor from 2 to {}, or max)",
+                s, MAX_FAST_FORWARD
+            )),
+        }
+    }
+}
+
+/// What the emulator is doing right now, for the indicator and window title.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    Normal,
+    Paused,
+    FastForward(FastForward),
+    SlowMotion,
+}
+
+impl Mode {
+    /// Pixel art shown in the corner of the screen, `#` for lit pixels.
+    pub fn indicator(&self) -> Option<&'static [&'static str]> {
+        match self {
+            Mode::Normal => None,
+            Mode::Paused => Some(&["##.##", "##.##", "##.##", "##.##", "##.##"]),
+            Mode::FastForward(FastForward::Times(_)) => {
+                Some(&["#...#..", "##..##.", "###.###", "##..##.", "#...#.."])
+            }
+            Mode::FastForward(FastForward::Uncapped) => {
+                Some(&["#..#..#", "##.##.#", "#######", "##.##.#", "#..#..#"])
+            }
+            Mode::SlowMotion => Some(&["#......", "##.....", "###.#.#", "##.....", "#......"]),
+        }
+    }
+}
+
+impl fmt::Display for Mode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Mode::Normal => f.write_str("Running"),
+            Mode::Paused => f.write_str("Paused"),
+            Mode::FastForward(speed) => write!(f, "Fast-forward {}", speed),
+            Mode::SlowMotion => write!(f, "Slow motion 1/{}x", SLOW_MOTION_DIVISOR),
+        }
+    }
+}
+
+/// The state behind the speed hotkeys.
+#[derive(Debug, Default)]
+pub struct SpeedControl {
+    paused: bool,
+    fast_forward: Option<FastForward>, // Set while the key is held
+    slow_motion: bool,
+    slow_frames: u32, // Displayed frames since the last emulated one in slow motion
+    advance_pending: u32, // Frame advances requested while paused
+    fast_forward_speed: FastForward,
+}
+
+impl SpeedControl {
+    pub fn new(fast_forward_speed: FastForward) -> Self {
+        Self {
+            fast_forward_speed,
+            ..Self::default()
+        }
+    }
+
+    pub fn mode(&self) -> Mode {
+        if self.paused {
+            Mode::Paused
+        } else if let Some(speed) = self.fast_forward {
+            Mode::FastForward(speed)
+        } else if self.slow_motion {
+            Mode::SlowMotion
+        } else {
+            Mode::Normal
+        }
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    pub fn toggle_pause(&mut self) {
+        self.paused = !self.paused;
+        self.advance_pending = 0;
+    }
+
+    /// Runs a single frame; pauses first if running.
+    pub fn advance_frame(&mut self) {
+        self.paused = true;
+        self.advance_pending += 1;
+    }
+
+    pub fn set_fast_forward(&mut self, held: bool) {
+        self.fast_forward = held.then_some(self.fast_forward_speed);
+    }
+
+    pub fn toggle_slow_motion(&mut self) {
+        self.slow_motion = !self.slow_motion;
+        self.slow_frames = 0;
+    }
+
+    /// Emulated frames to run for `due` displayed frames; `None` means
+    /// uncapped, i.e. as many as fit in the time of one displayed frame.
+    pub fn frames(&mut self, due: u32) -> Option<u32> {
+        match self.mode() {
+            Mode::Paused => Some(std::mem::take(&mut self.advance_pending)),
+            Mode::FastForward(FastForward::Times(n)) => Some(due.saturating_mul(n)),
+            Mode::FastForward(FastForward::Uncapped) if due > 0 => None,
+            Mode::FastForward(FastForward::Uncapped) => Some(0),
+            Mode::SlowMotion => {
+                self.slow_frames += due;
+                let frames = self.slow_frames / SLOW_MOTION_DIVISOR;
+                self.slow_frames %= SLOW_MOTION_DIVISOR;
+                Some(frames)
+            }
+            Mode::Normal => Some(due),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normal_speed_runs_one_frame_per_displayed_frame() {
+        let mut speed = SpeedControl::new(FastForward::default());
+        assert_eq!(speed.frames(1), Some(1));
+        assert_eq!(speed.frames(3), Some(3));
+        assert_eq!(speed.frames(0), Some(0));
+    }
+
+    #[test]
+    fn slow_motion_carries_partial_frames_over() {
+        let mut speed = SpeedControl::new(FastForward::default());
+        speed.toggle_slow_motion();
+        assert_eq!(speed.mode(), Mode::SlowMotion);
+        let frames: Vec<_> = (0..8).map(|_| speed.frames(1).unwrap()).collect();
+        assert_eq!(frames, [0, 0, 0, 1, 0, 0, 0, 1]);
+
+        // A late displayed frame counts for all the frames it covers
+        assert_eq!(speed.frames(2), Some(0));
+        assert_eq!(speed.frames(7), Some(2));
+
+        // Switching off and on starts counting again
+        speed.frames(3);
+        speed.toggle_slow_motion();
+        speed.toggle_slow_motion();
+        assert_eq!(speed.frames(3), Some(0));
+    }
+
+    #[test]
+    fn frame_advance_runs_one_frame_per_request_while_paused() {
+        let mut speed = SpeedControl::new(FastForward::default());
+        speed.advance_frame();
+        assert!(speed.is_paused());
+        assert_eq!(speed.frames(1), Some(1));
+        assert_eq!(speed.frames(1), Some(0));
+
+        speed.advance_frame();
+        speed.advance_frame();
+        assert_eq!(speed.frames(5), Some(2));
+
+        // Unpausing drops advances that have not run yet
+        speed.advance_frame();
+        speed.toggle_pause();
+        assert!(!speed.is_paused());
+        assert_eq!(speed.frames(1), Some(1));
+    }
+
+    #[test]
+    fn pause_wins_over_fast_forward_and_slow_motion() {
+        let mut speed = SpeedControl::new(FastForward::default());
+        speed.set_fast_forward(true);
+        speed.toggle_slow_motion();
+        speed.toggle_pause();
+        assert_eq!(speed.mode(), Mode::Paused);
+        assert_eq!(speed.frames(4), Some(0));
+    }
+
+    #[test]
+    fn fast_forward_multiplies_while_held() {
+        let mut speed = SpeedControl::new(FastForward::Times(3));
+        speed.toggle_slow_motion();
+        speed.set_fast_forward(true);
+        assert_eq!(speed.mode(), Mode::FastForward(FastForward::Times(3)));
+        assert_eq!(speed.frames(1), Some(3));
+        assert_eq!(speed.frames(2), Some(6));
+        assert_eq!(speed.frames(u32::MAX), Some(u32::MAX));
+
+        speed.set_fast_forward(false);
+        assert_eq!(speed.mode(), Mode::SlowMotion);
+    }
+
+    #[test]
+    fn uncapped_fast_forward_only_runs_when_a_frame_is_due() {
+        let mut speed = SpeedControl::new(FastForward::Uncapped);
+        speed.set_fast_forward(true);
+        assert_eq!(speed.frames(1), None);
+        assert_eq!(speed.frames(0), Some(0));
+    }
+
+    #[test]
+    fn fast_forward_speeds_parse() {
+        assert_eq!("4".parse(), Ok(FastForward::Times(4)));
+        assert_eq!("8x".parse(), Ok(FastForward::Times(8)));
+        assert_eq!("max".parse(), Ok(FastForward::Uncapped));
+        assert_eq!("64".parse(), Ok(FastForward::Times(MAX_FAST_FORWARD)));
+        assert!("1".parse::<FastForward>().is_err());
+        assert!("65".parse::<FastForward>().is_err());
+        assert!("4294967295".parse::<FastForward>().is_err());
+        assert!("fast".parse::<FastForward>().is_err());
+        for speed in [FastForward::Times(6), FastForward::Uncapped] {
+            assert_eq!(speed.to_string().parse(), Ok(speed));
+        }
+    }
+}
@@ -0,0 +1,249 @@
+// src/state.rs
+//! Versioned binary snapshots of the whole machine.
+//!
+//! Layout (little endian): the magic `C8ST`, a `u16` format version and the
+//! `u64` hash of the ROM the state was taken with, followed by the machine
+//! fields in the order of [`Snapshot`]. Variable-length parts (RAM, screen)
+//! are prefixed with their length.
+use crate::constants::*;
+use crate::rng::{Rng, RngMode};
+use std::fmt;
+
+/// First four bytes of every save state.
+pub const STATE_MAGIC: [u8; 4] = *b"C8ST";
+/// Current format version; bumped whenever the layout changes.
+pub const STATE_VERSION: u16 = 2;
+
+/// FNV-1a hash identifying a ROM image, stored in each save state header.
+pub fn rom_hash(data: &[u8]) -> u64 {
+    data.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &byte| {
+        (hash ^ byte as u64).wrapping_mul(0x0000_0100_0000_01b3)
+    })
+}
+
+/// Reasons a save state cannot be restored.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StateError {
+    /// The data does not start with `STATE_MAGIC`.
+    NotAState,
+    /// The state was written by an incompatible version of the format.
+    UnsupportedVersion(u16),
+    /// The state belongs to a different ROM than the one loaded.
+    RomMismatch { expected: u64, found: u64 },
+    /// The data ended early or holds impossible values.
+    Corrupt(&'static str),
+}
+
+impl fmt::Display for StateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StateError::NotAState => write!(f, "not a save state"),
+            StateError::UnsupportedVersion(v) => {
+                write!(
+                    f,
+                    "unsupported save state version {} (expected {})",
+                    v, STATE_VERSION
+                )
+            }
+            StateError::RomMismatch { expected, found } => write!(
+                f,
+                "save state is for another ROM (hash {:016x}, loaded ROM is {:016x})",
+                found, expected
+            ),
+            StateError::Corrupt(what) => write!(f, "corrupt save state: {}", what),
+        }
+    }
+}
+
+impl std::error::Error for StateError {}
+
+/// Every piece of machine state, decoupled from [`crate::Emu`] so it can be
+/// encoded, decoded and inspected (e.g. for thumbnails) on its own.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Snapshot {
+    pub rom_hash: u64,
+    pub pc: u16,
+    pub i_reg: u16,
+    pub sp: u16,
+    pub v_reg: [u8; REGISTERS_COUNT],
+    pub stack: [u16; STACK_SIZE],
+    pub delay_timer: u8,
+    pub sound_timer: u8,
+    pub keys: [bool; 16],
+    pub hires: bool,
+    pub planes: u8,
+    pub vblank: bool,
+    pub exited: bool,
+    pub pitch: u8,
+    pub audio_pattern: [u8; AUDIO_PATTERN_SIZE],
+    pub rpl_flags: [u8; RPL_FLAGS_COUNT],
+    pub width: u16,
+    pub height: u16,
+    pub screen: Vec<u8>,
+    pub ram: Vec<u8>,
+    pub rng: Rng,
+}
+
+impl Snapshot {
+    pub fn encode(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(64 + self.screen.len() + self.ram.len());
+        out.extend_from_slice(&STATE_MAGIC);
+        out.extend_from_slice(&STATE_VERSION.to_le_bytes());
+        out.extend_from_slice(&self.rom_hash.to_le_bytes());
+
+        out.extend_from_slice(&self.pc.to_le_bytes());
+        out.extend_from_slice(&self.i_reg.to_le_bytes());
+        out.extend_from_slice(&self.sp.to_le_bytes());
+        out.extend_from_slice(&self.v_reg);
+        for addr in self.stack {
+            out.extend_from_slice(&addr.to_le_bytes());
+        }
+        out.push(self.delay_timer);
+        out.push(self.sound_timer);
+        out.extend(self.keys.iter().map(|&k| k as u8));
+        out.push(self.hires as u8);
+        out.push(self.planes);
+        out.push(self.vblank as u8);
+        out.push(self.exited as u8);
+        out.push(self.pitch);
+        out.extend_from_slice(&self.audio_pattern);
+        out.extend_from_slice(&self.rpl_flags);
+
+        out.extend_from_slice(&self.width.to_le_bytes());
+        out.extend_from_slice(&self.height.to_le_bytes());
+        out.extend_from_slice(&self.screen);
+        out.extend_from_slice(&(self.ram.len() as u32).to_le_bytes());
+        out.extend_from_slice(&self.ram);
+
+        out.push(match self.rng.mode {
+            RngMode::Xorshift => 0,
+        });
+        out.extend_from_slice(&self.rng.seed.to_le_bytes());
+        out.extend_from_slice(&self.rng.state.to_le_bytes());
+        out
+    }
+
+    pub fn decode(data: &[u8]) -> Result<Self, StateError> {
+        let mut r = Reader { data, pos: 0 };
+        if r.take(4)? != STATE_MAGIC {
+            return Err(StateError::NotAState);
+        }
+        let version = r.u16()?;
+        if version != STATE_VERSION {
+            return Err(StateError::UnsupportedVersion(version));
+        }
+        let rom_hash = r.u64()?;
+
+        let pc = r.u16()?;
+        let i_reg = r.u16()?;
+        let sp = r.u16()?;
+        let v_reg = r.array()?;
+        let mut stack = [0u16; STACK_SIZE];
+        for addr in stack.iter_mut() {
+            *addr = r.u16()?;
+        }
+        let delay_timer = r.u8()?;
+        let sound_timer = r.u8()?;
+        let mut keys = [false; 16];
+        for key in keys.iter_mut() {
+            *key = r.bool()?;
+        }
+        let hires = r.bool()?;
+        let planes = r.u8()?;
+        let vblank = r.bool()?;
+        let exited = r.bool()?;
+        let pitch = r.u8()?;
+        let audio_pattern = r.array()?;
+        let rpl_flags = r.array()?;
+
+        let width = r.u16()?;
+        let height = r.u16()?;
+        let screen = r.take(width as usize * height as usize)?.to_vec();
+        let ram_len = r.u32()? as usize;
+        let ram = r.take(ram_len)?.to_vec();
+
+        let mode = match r.u8()? {
+            0 => RngMode::Xorshift,
+            _ => return Err(StateError::Corrupt("unknown RNG mode")),
+        };
+        let rng = Rng {
+            mode,
+            seed: r.u64()?,
+            state: r.u64()?,
+        };
+
+        if sp as usize > stack.len() {
+            return Err(StateError::Corrupt("stack pointer out of range"));
+        }
+        if r.pos != data.len() {
+            return Err(StateError::Corrupt("trailing data"));
+        }
+
+        Ok(Snapshot {
+            rom_hash,
+            pc,
+            i_reg,
+            sp,
+            v_reg,
+            stack,
+            delay_timer,
+            sound_timer,
+            keys,
+            hires,
+            planes,
+            vblank,
+            exited,
+            pitch,
+            audio_pattern,
+            rpl_flags,
+            width,
+            height,
+            screen,
+            ram,
+            rng,
+        })
+    }
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, n: usize) -> Result<&'a [u8], StateError> {
+        let end = self.pos + n;
+        let bytes = self
+            .data
+            .get(self.pos..end)
+            .ok_or(StateError::Corrupt("unexpected end of data"))?;
+        self.pos = end;
+        Ok(bytes)
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N], StateError> {
+        let mut out = [0; N];
+        out.copy_from_slice(self.take(N)?);
+        Ok(out)
+    }
+
+    fn u8(&mut self) -> Result<u8, StateError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn bool(&mut self) -> Result<bool, StateError> {
+        Ok(self.u8()? != 0)
+    }
+
+    fn u16(&mut self) -> Result<u16, StateError> {
+        Ok(u16::from_le_bytes(self.array()?))
+    }
+
+    fn u32(&mut self) -> Result<u32, StateError> {
+        Ok(u32::from_le_bytes(self.array()?))
+    }
+
+    fn u64(&mut self) -> Result<u64, StateError> {
+        Ok(u64::from_le_bytes(self.array()?))
+    }
+}
@@ -0,0 +1,144 @@
+// src/storage.rs
+//! Files the frontend keeps between runs, all under the per-user config directory.
+use chip8_emu::state::rom_hash;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// `~/.config/chip8_emu` (or the platform equivalent).
+pub fn data_dir() -> PathBuf {
+    let mut path = dirs::config_dir().unwrap_or_else(|| PathBuf::from("."));
+    path.push("chip8_emu");
+    path
+}
+
+/// File name of a ROM without its extension; names its screenshots and recordings.
+pub fn rom_stem(rom_path: &str) -> String {
+    Path::new(rom_path)
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_else(|| "rom".to_string())
+}
+
+/// Names the flags, states and settings of a ROM: its stem plus the hash of
+/// its contents, so ROMs with the same name in different folders stay apart.
+pub fn rom_key(rom_path: &str) -> String {
+    // An unreadable ROM never runs; the path keeps it apart from the others
+    let hash = match fs::read(rom_path) {
+        Ok(data) => rom_hash(&data),
+        Err(_) => rom_hash(rom_path.as_bytes()),
+    };
+    format!("{}-{:016x}", rom_stem(rom_path), hash)
+}
+
+fn rpl_flags_path(rom_path: &str) -> PathBuf {
+    let mut path = data_dir();
+    path.push("flags");
+    path.push(format!("{}.flags", rom_key(rom_path)));
+    path
+}
+
+/// SUPER-CHIP RPL flags saved by a previous run of this ROM.
+pub fn load_rpl_flags(rom_path: &str) -> Option<Vec<u8>> {
+    fs::read(rpl_flags_path(rom_path)).ok()
+}
+
+pub fn save_rpl_flags(rom_path: &str, flags: &[u8]) -> Result<(), String> {
+    let path = rpl_flags_path(rom_path);
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    fs::write(&path, flags).map_err(|e| e.to_string())
+}
+
+/// Number of save state slots, bound to F1-F8.
+pub const SAVE_SLOTS: u8 = 8;
+
+fn state_slot_path(rom_path: &str, slot: u8) -> PathBuf {
+    let mut path = data_dir();
+    path.push("states");
+    path.push(rom_key(rom_path));
+    path.push(format!("slot{}.c8s", slot));
+    path
+}
+
+pub fn save_state_slot(rom_path: &str, slot: u8, state: &[u8]) -> Result<(), String> {
+    let path = state_slot_path(rom_path, slot);
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    fs::write(&path, state).map_err(|e| e.to_string())
+}
+
+pub fn load_state_slot(rom_path: &str, slot: u8) -> Result<Vec<u8>, String> {
+    fs::read(state_slot_path(rom_path, slot)).map_err(|e| e.to_string())
+}
+
+/// All filled slots for a ROM, as (slot number, raw state) pairs.
+pub fn list_state_slots(rom_path: &str) -> Vec<(u8, Vec<u8>)> {
+    (1..=SAVE_SLOTS)
+        .filter_map(|slot| {
+            load_state_slot(rom_path, slot)
+                .ok()
+                .map(|data| (slot, data))
+        })
+        .collect()
+}
+
+/// Default folder for screenshots.
+pub fn screenshots_dir() -> PathBuf {
+    let mut path = data_dir();
+    path.push("screenshots");
+    path
+}
+
+/// Default folder for F10 recordings.
+pub fn recordings_dir() -> PathBuf {
+    let mut path = data_dir();
+    path.push("recordings");
+    path
+}
+
+/// A new file in `dir` named after the ROM and the current time, e.g.
+/// `Pong-20250101-120000.png`.
+pub fn timestamped_path(dir: &Path, rom_path: &str, extension: &str) -> PathBuf {
+    let base = format!("{}-{}", rom_stem(rom_path), timestamp());
+    let mut path = dir.join(format!("{}.{}", base, extension));
+    // Several captures in the same second get a counter
+    let mut n = 2;
+    while path.exists() {
+        path = dir.join(format!("{}-{}.{}", base, n, extension));
+        n += 1;
+    }
+    path
+}
+
+// Current UTC time as YYYYMMDD-HHMMSS
+fn timestamp() -> String {
+    let secs = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let (days, time) = ((secs / 86_400) as i64, secs % 86_400);
+
+    // Days since 1970-01-01 to a calendar date (Howard Hinnant's civil_from_days)
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{:04}{:02}{:02}-{:02}{:02}{:02}",
+        year,
+        month,
+        day,
+        time / 3_600,
+        time / 60 % 60,
+        time % 60
+    )
+}
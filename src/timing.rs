@@ -0,0 +1,216 @@
+// src/timing.rs
+//! How fast the CPU runs: instructions per 60 Hz frame, and pacing frames
+//! against the wall clock.
+use crate::emu::Emu;
+use crate::error::Chip8Error;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+
+/// Instructions per second when nothing else is configured; the emulator
+/// used to run a fixed 10 instructions per frame.
+pub const DEFAULT_IPS: u32 = 600;
+
+/// Length of one 60 Hz frame.
+pub const FRAME_DURATION: Duration = Duration::from_nanos(1_000_000_000 / 60);
+
+// COSMAC VIP: 1.76064 MHz with 8 clocks per machine cycle gives about 3668
+// machine cycles per frame, of which the display interrupt and its DMA take
+// roughly 1070
+const VIP_FRAME_CYCLES: i64 = 3668 - 1070;
+const VIP_DISPATCH_CYCLES: i64 = 40; // Fetch and decode in the interpreter loop
+
+// In IPS mode an instruction costs this much and a frame adds `ips`, so
+// fractional instructions per frame carry over exactly
+const IPS_INSTRUCTION_COST: i64 = 60;
+
+// After a stall the clock skips ahead instead of running this many frames late
+const MAX_CATCH_UP_FRAMES: u32 = 4;
+
+/// CPU speed model.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Timing {
+    /// A fixed number of instructions per second, spread evenly over frames.
+    Ips(u32),
+    /// Each instruction costs about as many machine cycles as in the original
+    /// COSMAC VIP interpreter, and `DXYN` waits for the next vblank.
+    CosmacVip,
+}
+
+impl Default for Timing {
+    fn default() -> Self {
+        Timing::Ips(DEFAULT_IPS)
+    }
+}
+
+impl fmt::Display for Timing {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Timing::Ips(ips) => write!(f, "{} IPS", ips),
+            Timing::CosmacVip => f.write_str("COSMAC VIP cycles"),
+        }
+    }
+}
+
+impl FromStr for Timing {
+    type Err = String;
+
+    /// `vip` or a number of instructions per second.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s == "vip" {
+            return Ok(Timing::CosmacVip);
+        }
+        match s.parse() {
+            Ok(ips) if ips > 0 => Ok(Timing::Ips(ips)),
+            _ => Err(format!(
+                "invalid speed '{}' (expected instructions per second or vip)",
+                s
+            )),
+        }
+    }
+}
+
+/// Splits execution into frames according to a [`Timing`].
+///
+/// Each frame adds a budget and each instruction is charged against it;
+/// whatever is left over or overdrawn carries into the next frame.
+#[derive(Debug, Clone)]
+pub struct Scheduler {
+    timing: Timing,
+    credit: i64,
+}
+
+impl Scheduler {
+    pub fn new(timing: Timing) -> Self {
+        Self { timing, credit: 0 }
+    }
+
+    pub fn timing(&self) -> Timing {
+        self.timing
+    }
+
+    pub fn set_timing(&mut self, timing: Timing) {
+        self.timing = timing;
+        self.credit = 0;
+    }
+
+    /// Adds the budget of a new frame.
+    pub fn start_frame(&mut self) {
+        self.credit += match self.timing {
+            Timing::Ips(ips) => ips as i64,
+            Timing::CosmacVip => VIP_FRAME_CYCLES,
+        };
+    }
+
+    /// Whether the current frame's budget is used up.
+    pub fn frame_done(&self) -> bool {
+        self.credit <= 0
+    }
+
+    /// Executes one instruction and charges it to the current frame.
+    pub fn tick(&mut self, emu: &mut Emu) -> Result<(), Chip8Error> {
+        let pc = emu.pc() as usize;
+        let op = match emu.ram().get(pc..pc + 2) {
+            Some(bytes) => (bytes[0] as u16) << 8 | bytes[1] as u16,
+            None => 0,
+        };
+        let result = emu.tick();
+
+        match self.timing {
+            Timing::Ips(_) => self.credit -= IPS_INSTRUCTION_COST,
+            Timing::CosmacVip => {
+                self.credit -= VIP_DISPATCH_CYCLES + vip_cycles(op);
+                // The interpreter idles until the display interrupt after drawing
+                if op & 0xF000 == 0xD000 {
+                    self.credit = self.credit.min(0);
+                }
+            }
+        }
+        result
+    }
+
+    /// Runs one frame's worth of instructions, then ticks the timers.
+    pub fn run_frame(&mut self, emu: &mut Emu) -> Result<(), Chip8Error> {
+        self.start_frame();
+        while !self.frame_done() && !emu.has_exited() {
+            self.tick(emu)?;
+        }
+        emu.tick_timers();
+        Ok(())
+    }
+}
+
+// Approximate machine cycles the VIP interpreter spends on an instruction,
+// not counting dispatch
+fn vip_cycles(op: u16) -> i64 {
+    let x = ((op >> 8) & 0xF) as i64;
+    let n = (op & 0xF) as i64;
+    match (op >> 12, op & 0xFF) {
+        (0x0, 0xE0) => 3078,
+        (0x0, 0xEE) => 10,
+        (0x1, _) => 12,
+        (0x2, _) => 26,
+        (0x3 | 0x4 | 0x5 | 0x9, _) => 14,
+        (0x6, _) => 6,
+        (0x7, _) => 10,
+        (0x8, _) => 44,
+        (0xA, _) => 12,
+        (0xB, _) => 22,
+        (0xC, _) => 36,
+        (0xD, _) => 22 + 34 * if n == 0 { 16 } else { n },
+        (0xE, _) => 14,
+        (0xF, 0x0A) => 18,
+        (0xF, 0x1E | 0x29) => 16,
+        (0xF, 0x33) => 84,
+        (0xF, 0x55 | 0x65) => 14 + 14 * (x + 1),
+        _ => 10,
+    }
+}
+
+/// Decides when frames are due from the elapsed wall time, so emulation
+/// speed does not depend on how long rendering takes.
+#[derive(Debug, Clone)]
+pub struct FrameClock {
+    last: Instant,
+    lag: Duration, // Elapsed time not yet turned into frames
+}
+
+impl Default for FrameClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FrameClock {
+    pub fn new() -> Self {
+        Self {
+            last: Instant::now(),
+            lag: Duration::ZERO,
+        }
+    }
+
+    /// Frames due since the last call. After a long stall (e.g. the window
+    /// being dragged) at most a few are returned and the rest are dropped.
+    pub fn frames_due(&mut self) -> u32 {
+        let now = Instant::now();
+        self.lag += now - self.last;
+        self.last = now;
+
+        let mut frames = 0;
+        while self.lag >= FRAME_DURATION {
+            self.lag -= FRAME_DURATION;
+            frames += 1;
+        }
+        if frames > MAX_CATCH_UP_FRAMES {
+            self.lag = Duration::ZERO;
+            frames = MAX_CATCH_UP_FRAMES;
+        }
+        frames
+    }
+
+    /// Time left until the next frame is due.
+    pub fn until_next_frame(&self) -> Duration {
+        FRAME_DURATION.saturating_sub(self.lag + self.last.elapsed())
+    }
+}
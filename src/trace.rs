@@ -0,0 +1,202 @@
+// src/trace.rs
+//! Per-instruction execution traces, written by [`Emu::tick`](crate::Emu::tick)
+//! while a [`Tracer`] is attached.
+use crate::constants::REGISTERS_COUNT;
+use crate::error::Chip8Error;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::io::{self, Write};
+use std::ops::RangeInclusive;
+use std::str::FromStr;
+
+/// Layout of a trace line.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum TraceFormat {
+    /// Address, opcode, mnemonic and only the registers the instruction
+    /// changed: `0204  600C  LD V0, 0x0C  V0=0C`.
+    #[default]
+    Delta,
+    /// Address, opcode and every register before the instruction runs, the
+    /// usual format for diffing against other emulators:
+    /// `0208 D01F V0:0C V1:08 ... VF:00 I:022A SP:0 DT:00 ST:00`.
+    Full,
+}
+
+impl TraceFormat {
+    pub fn id(&self) -> &'static str {
+        match self {
+            TraceFormat::Delta => "delta",
+            TraceFormat::Full => "full",
+        }
+    }
+}
+
+impl fmt::Display for TraceFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.id())
+    }
+}
+
+impl FromStr for TraceFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "delta" => Ok(TraceFormat::Delta),
+            "full" => Ok(TraceFormat::Full),
+            _ => Err(format!(
+                "unknown trace format '{}' (expected delta or full)",
+                s
+            )),
+        }
+    }
+}
+
+/// Parses an inclusive address range such as `0x200-0x2FF`.
+pub fn parse_range(s: &str) -> Result<RangeInclusive<u16>, String> {
+    let hex = |part: &str| {
+        let part = part.trim();
+        let digits = part
+            .strip_prefix("0x")
+            .or_else(|| part.strip_prefix("0X"))
+            .unwrap_or(part);
+        u16::from_str_radix(digits, 16).map_err(|_| format!("invalid address '{}'", part))
+    };
+    let (start, end) = s
+        .split_once('-')
+        .ok_or_else(|| format!("invalid range '{}' (expected START-END)", s))?;
+    let (start, end) = (hex(start)?, hex(end)?);
+    if start > end {
+        return Err(format!("invalid range '{}' (start is after the end)", s));
+    }
+    Ok(start..=end)
+}
+
+/// The registers a trace line is built from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Registers {
+    pub v: [u8; REGISTERS_COUNT],
+    pub i: u16,
+    pub sp: u16,
+    pub delay_timer: u8,
+    pub sound_timer: u8,
+}
+
+/// Writes trace lines for instructions inside an address range.
+///
+/// Tracing can be switched off and on at runtime with [`Tracer::set_enabled`];
+/// a write error stops it and is kept for [`Tracer::error`].
+pub struct Tracer {
+    out: Box<dyn Write + Send>,
+    format: TraceFormat,
+    range: RangeInclusive<u16>,
+    enabled: bool,
+    error: Option<io::Error>,
+}
+
+impl Tracer {
+    /// Traces every address; wrap files in a `BufWriter`.
+    pub fn new(out: impl Write + Send + 'static, format: TraceFormat) -> Self {
+        Tracer {
+            out: Box::new(out),
+            format,
+            range: 0..=u16::MAX,
+            enabled: true,
+            error: None,
+        }
+    }
+
+    /// Only instructions whose address is in `range` are traced, e.g. to
+    /// leave out an `FX0A` wait loop.
+    pub fn with_range(mut self, range: RangeInclusive<u16>) -> Self {
+        self.range = range;
+        self
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled && self.error.is_none();
+    }
+
+    pub fn range(&self) -> &RangeInclusive<u16> {
+        &self.range
+    }
+
+    /// The write error that stopped tracing, if any.
+    pub fn error(&self) -> Option<&io::Error> {
+        self.error.as_ref()
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.out.flush()
+    }
+
+    pub(crate) fn wants(&self, pc: u16) -> bool {
+        self.enabled && self.range.contains(&pc)
+    }
+
+    pub(crate) fn record(
+        &mut self,
+        pc: u16,
+        opcode: u16,
+        mnemonic: &str,
+        before: &Registers,
+        after: &Registers,
+        error: Option<&Chip8Error>,
+    ) {
+        let mut line = match self.format {
+            TraceFormat::Delta => delta_line(pc, opcode, mnemonic, before, after),
+            TraceFormat::Full => full_line(pc, opcode, before),
+        };
+        if let Some(e) = error {
+            line.push_str(&format!("  !! {}", e));
+        }
+        if let Err(e) = writeln!(self.out, "{}", line) {
+            self.enabled = false;
+            self.error = Some(e);
+        }
+    }
+}
+
+fn delta_line(
+    pc: u16,
+    opcode: u16,
+    mnemonic: &str,
+    before: &Registers,
+    after: &Registers,
+) -> String {
+    let mut line = format!("{:04X}  {:04X}  {:<18}", pc, opcode, mnemonic);
+    for (n, (old, new)) in before.v.iter().zip(after.v.iter()).enumerate() {
+        if old != new {
+            line.push_str(&format!(" V{:X}={:02X}", n, new));
+        }
+    }
+    if before.i != after.i {
+        line.push_str(&format!(" I={:04X}", after.i));
+    }
+    if before.sp != after.sp {
+        line.push_str(&format!(" SP={:X}", after.sp));
+    }
+    if before.delay_timer != after.delay_timer {
+        line.push_str(&format!(" DT={:02X}", after.delay_timer));
+    }
+    if before.sound_timer != after.sound_timer {
+        line.push_str(&format!(" ST={:02X}", after.sound_timer));
+    }
+    line.trim_end().to_string()
+}
+
+fn full_line(pc: u16, opcode: u16, regs: &Registers) -> String {
+    let mut line = format!("{:04X} {:04X}", pc, opcode);
+    for (n, value) in regs.v.iter().enumerate() {
+        line.push_str(&format!(" V{:X}:{:02X}", n, value));
+    }
+    line.push_str(&format!(
+        " I:{:04X} SP:{:X} DT:{:02X} ST:{:02X}",
+        regs.i, regs.sp, regs.delay_timer, regs.sound_timer
+    ));
+    line
+}
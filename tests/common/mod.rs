@@ -0,0 +1,33 @@
+// tests/common/mod.rs
+//! Test programs shared by the integration tests.
+use chip8_emu::asm;
+use chip8_emu::Emu;
+
+/// Flips a pixel in the top left corner every `period` frames.
+pub fn blink(period: u8) -> String {
+    format!(
+        "
+: main
+    v2 := {}
+    i := dot
+    loop
+        sprite v0 v0 1
+        delay := v2
+        loop
+            v1 := delay
+        while v1 != 0
+        again
+    again
+: dot
+    0x80
+",
+        period
+    )
+}
+
+/// A machine with the default quirks and `source` assembled and loaded.
+pub fn load(source: &str) -> Emu {
+    let mut emu = Emu::new();
+    emu.load_rom(&asm::assemble(source).unwrap().rom).unwrap();
+    emu
+}
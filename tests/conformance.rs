@@ -0,0 +1,173 @@
+// tests/conformance.rs
+//! Conformance suite: test programs modelled on the well-known CHIP-8 test
+//! ROMs (corax+ opcodes, flags, quirks, keypad), assembled from
+//! `tests/roms/*.8o` and run headless under every quirks profile. Each check
+//! draws a tick or a cross, and the harness reads those glyphs back from the
+//! framebuffer.
+use chip8_emu::asm;
+use chip8_emu::headless::{self, InputScript, RunConfig, Until};
+use chip8_emu::quirks::IndexIncrement;
+use chip8_emu::timing::Timing;
+use chip8_emu::{Emu, QuirksProfile};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const TIMING: Timing = Timing::Ips(600);
+const MAX_FRAMES: u64 = 600;
+const FAILURES_REG: usize = 0xC; // vC in prelude.8o
+
+fn test_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests")
+}
+
+fn assemble(name: &str) -> Vec<u8> {
+    let roms = test_dir().join("roms");
+    let prelude = fs::read_to_string(roms.join("prelude.8o")).unwrap();
+    let source = fs::read_to_string(roms.join(format!("{}.8o", name))).unwrap();
+    match asm::assemble(&format!("{}\n{}", prelude, source)) {
+        Ok(program) => program.rom,
+        Err(e) => panic!("{}.8o (after the prelude): {}", name, e),
+    }
+}
+
+fn input(name: &str) -> InputScript {
+    let path = test_dir().join("roms").join(format!("{}.keys", name));
+    match fs::read_to_string(path) {
+        Ok(text) => text.parse().unwrap(),
+        Err(_) => InputScript::default(),
+    }
+}
+
+// Runs the ROM until it halts on a jump to itself
+fn run(rom: &[u8], profile: QuirksProfile, input: InputScript) -> Emu {
+    let mut emu = Emu::with_quirks(profile.quirks());
+    emu.load_rom(rom).unwrap();
+    let config = RunConfig {
+        max_frames: MAX_FRAMES,
+        timing: TIMING,
+        until: Some(Until::Halt),
+        input,
+    };
+    let outcome = headless::run(&mut emu, &config)
+        .unwrap_or_else(|e| panic!("{} profile: {}", profile.id(), e));
+    assert!(
+        outcome.condition_met,
+        "{} profile: did not halt within {} frames",
+        profile.id(),
+        MAX_FRAMES
+    );
+    emu
+}
+
+// Tick and cross glyphs of prelude.8o, one byte per row
+const TICK: [u8; 5] = [0x00, 0x04, 0x08, 0x90, 0x60];
+const CROSS: [u8; 5] = [0x88, 0x50, 0x20, 0x50, 0x88];
+const CHECK_COUNT_REG: usize = 0xD; // vD in prelude.8o
+
+// Reads the tick (true) or cross (false) of every check from the screen, in
+// the layout of `report` in prelude.8o
+fn results(emu: &Emu, profile: QuirksProfile) -> Vec<bool> {
+    let width = emu.width();
+    let display = emu.get_display();
+    (0..emu.v_regs()[CHECK_COUNT_REG] as usize)
+        .map(|check| {
+            let x = 1 + 12 * (check % 5) + 5;
+            let y = 1 + 6 * (check / 5);
+            let glyph: Vec<u8> = (y..y + 5)
+                .map(|row| {
+                    (0..7).fold(0, |byte, col| {
+                        let lit = display[row * width + x + col] != 0;
+                        byte | (lit as u8) << (7 - col)
+                    })
+                })
+                .collect();
+            if glyph == TICK {
+                true
+            } else if glyph == CROSS {
+                false
+            } else {
+                panic!(
+                    "{} profile: no tick or cross for check {}\n{}",
+                    profile.id(),
+                    check,
+                    headless::display_text(emu)
+                )
+            }
+        })
+        .collect()
+}
+
+fn assert_results(name: &str, profile: QuirksProfile, emu: &Emu, expected: &[bool]) {
+    let actual = results(emu, profile);
+    let failed: Vec<usize> = (0..expected.len().max(actual.len()))
+        .filter(|&n| actual.get(n) != expected.get(n))
+        .collect();
+    assert!(
+        failed.is_empty(),
+        "{} profile: checks {:?} of {} show the wrong glyph\n{}",
+        profile.id(),
+        failed,
+        name,
+        headless::display_text(emu)
+    );
+}
+
+// Tests whose checks must all pass under any profile
+fn assert_passes_everywhere(name: &str, checks: usize) {
+    let rom = assemble(name);
+    for profile in QuirksProfile::all() {
+        let emu = run(&rom, profile, input(name));
+        assert_results(name, profile, &emu, &vec![true; checks]);
+        assert_eq!(emu.v_regs()[FAILURES_REG], 0, "{} profile", profile.id());
+    }
+}
+
+#[test]
+fn opcodes() {
+    assert_passes_everywhere("opcodes", 22);
+}
+
+#[test]
+fn flags() {
+    assert_passes_everywhere("flags", 14);
+}
+
+#[test]
+fn keypad() {
+    assert_passes_everywhere("keypad", 3);
+}
+
+#[test]
+fn quirks_match_each_profile() {
+    let rom = assemble("quirks");
+    for profile in QuirksProfile::all() {
+        let quirks = profile.quirks();
+        // In the order of the checks in quirks.8o
+        let expected = [
+            quirks.vf_reset,
+            quirks.shift_uses_vy,
+            quirks.load_store != IndexIncrement::Unchanged,
+            quirks.load_store == IndexIncrement::ByXPlusOne,
+            quirks.jump_uses_vx,
+            quirks.wrap_sprites,
+            quirks.display_wait,
+        ];
+        let emu = run(&rom, profile, InputScript::default());
+        assert_results("quirks", profile, &emu, &expected);
+    }
+}
+
+#[test]
+fn ibm_logo() {
+    let rom = fs::read(Path::new(env!("CARGO_MANIFEST_DIR")).join("assets/ibm.ch8")).unwrap();
+    let expected = fs::read_to_string(test_dir().join("roms/ibm.txt")).unwrap();
+    for profile in QuirksProfile::all() {
+        let emu = run(&rom, profile, InputScript::default());
+        assert_eq!(
+            headless::display_text(&emu),
+            expected,
+            "{} profile",
+            profile.id()
+        );
+    }
+}
@@ -0,0 +1,127 @@
+// tests/debug.rs
+//! Watchpoints and conditional breakpoints of `chip8_emu::debug`.
+use chip8_emu::asm;
+use chip8_emu::condition::Condition;
+use chip8_emu::debug::{Debugger, StopReason, WatchKind, Watchpoint};
+use chip8_emu::timing::Timing;
+use chip8_emu::Emu;
+
+fn load(source: &str) -> Emu {
+    let mut emu = Emu::new();
+    emu.load_rom(&asm::assemble(source).unwrap().rom).unwrap();
+    emu
+}
+
+// Runs frames until the debugger stops
+fn run(debugger: &mut Debugger, emu: &mut Emu) -> StopReason {
+    for _ in 0..60 {
+        if let Some(reason) = debugger.run_frame(emu) {
+            return reason;
+        }
+    }
+    panic!("the debugger did not stop");
+}
+
+const STORE: &str = "
+: main
+    v0 := 1
+    v1 := 2
+    i := buffer
+    save v1
+    i := other
+    load v0
+    loop again
+: buffer
+    0 0
+: other
+    0
+";
+
+#[test]
+fn write_watchpoint_stops_after_the_store() {
+    let mut emu = load(STORE);
+    let buffer = asm::assemble(STORE).unwrap().symbols.labels["buffer"];
+    let mut debugger = Debugger::new(Timing::default());
+    debugger.add_watchpoint(
+        &mut emu,
+        Watchpoint {
+            range: buffer + 1..=buffer + 1,
+            kind: WatchKind::Write,
+        },
+    );
+
+    match run(&mut debugger, &mut emu) {
+        StopReason::Watchpoint { addr, pc, .. } => {
+            assert_eq!(addr, buffer + 1);
+            assert_eq!(pc, 0x206); // save v1
+        }
+        other => panic!("unexpected stop: {:?}", other),
+    }
+    assert_eq!(emu.ram()[buffer as usize + 1], 2);
+}
+
+#[test]
+fn read_and_index_watchpoints() {
+    let mut emu = load(STORE);
+    let other = asm::assemble(STORE).unwrap().symbols.labels["other"];
+    let mut debugger = Debugger::new(Timing::default());
+    for kind in [WatchKind::Index, WatchKind::Read] {
+        debugger.add_watchpoint(
+            &mut emu,
+            Watchpoint {
+                range: other..=other,
+                kind,
+            },
+        );
+    }
+
+    // i := other comes first, then load v0 reads it
+    for expected in [WatchKind::Index, WatchKind::Read] {
+        debugger.resume();
+        match run(&mut debugger, &mut emu) {
+            StopReason::Watchpoint { watchpoint, .. } => assert_eq!(watchpoint.kind, expected),
+            other => panic!("unexpected stop: {:?}", other),
+        }
+    }
+}
+
+#[test]
+fn condition_stops_when_it_becomes_true() {
+    let mut emu = load(
+        "
+: main
+    v3 := 0
+    loop
+        v3 += 4
+    again
+",
+    );
+    let mut debugger = Debugger::new(Timing::default());
+    debugger.add_condition(&emu, "V3 == 0x10 && DT == 0".parse().unwrap());
+
+    assert!(matches!(
+        run(&mut debugger, &mut emu),
+        StopReason::Condition { .. }
+    ));
+    assert_eq!(emu.v_regs()[3], 0x10);
+
+    // Still true right after the stop, so continuing does not stop again at once
+    debugger.resume();
+    debugger.run_frame(&mut emu);
+    assert!(!debugger.is_paused());
+}
+
+#[test]
+fn condition_syntax() {
+    let emu = Emu::new();
+    let holds = |s: &str| s.parse::<Condition>().unwrap().holds(&emu);
+    assert!(holds("pc == 0x200"));
+    assert!(holds("[0] == 0xF0 && [I + 1] == 0x90")); // Font data of 0
+    assert!(holds("!(V0 != 0) || ST"));
+    assert!(holds("1 + 2 & 0b11 == 3"));
+    assert!(!holds("SP > 0"));
+
+    for bad in ["V3 ==", "VG == 1", "(V0", "V0 = 1", "[PC"] {
+        assert!(bad.parse::<Condition>().is_err(), "{} parsed", bad);
+    }
+}
@@ -0,0 +1,161 @@
+// tests/deflicker.rs
+//! Flicker reduction of `chip8_emu::deflicker`.
+mod common;
+
+use chip8_emu::asm;
+use chip8_emu::deflicker::Deflicker;
+use chip8_emu::timing::{Scheduler, Timing};
+use chip8_emu::{Emu, QuirksProfile};
+
+// Moves a dot one pixel right every frame, erasing and drawing it together
+const MOVING: &str = "
+: main
+    i := dot
+    v2 := 1
+    sprite v0 v3 1
+    loop
+        delay := v2
+        loop
+            v1 := delay
+        while v1 != 0
+        again
+        sprite v0 v3 1
+        v0 += 1
+        sprite v0 v3 1
+    again
+: dot
+    0x80
+";
+
+// A dot on plane 2 that stays, under a dot on plane 1 that blinks
+const PLANES: &str = "
+: main
+    i := dot
+    plane 2
+    sprite v0 v0 1
+    plane 1
+    v2 := 1
+    loop
+        sprite v0 v0 1
+        delay := v2
+        loop
+            v1 := delay
+        while v1 != 0
+        again
+    again
+: dot
+    0x80
+";
+
+// A dot on plane 1 for a few frames, then on plane 2 for good
+const SWITCH: &str = "
+: main
+    i := dot
+    sprite v0 v0 1
+    v2 := 3
+    delay := v2
+    loop
+        v1 := delay
+    while v1 != 0
+    again
+    sprite v0 v0 1
+    plane 2
+    sprite v0 v0 1
+    loop again
+: dot
+    0x80
+";
+
+fn xo_chip(source: &str) -> Emu {
+    let mut emu = Emu::with_quirks(QuirksProfile::XoChip.quirks());
+    emu.load_rom(&asm::assemble(source).unwrap().rom).unwrap();
+    emu
+}
+
+// Runs `frames` frames, passing the raw and deflickered screens of each
+fn run(emu: &mut Emu, frames: usize, mut check: impl FnMut(usize, &[u8], &[u8])) {
+    let mut scheduler = Scheduler::new(Timing::default());
+    let mut deflicker = Deflicker::new();
+    for frame in 0..frames {
+        scheduler.run_frame(emu).unwrap();
+        deflicker.push(emu);
+        check(frame, emu.get_display(), &deflicker.display(emu));
+    }
+}
+
+#[test]
+fn blinking_pixel_stays_lit() {
+    let mut raw_off = 0;
+    run(
+        &mut common::load(&common::blink(1)),
+        20,
+        |frame, raw, shown| {
+            if raw[0] == 0 {
+                raw_off += 1;
+            }
+            // Once the pattern has been seen
+            if frame >= 3 {
+                assert_eq!(shown[0], 1, "frame {}", frame);
+            }
+        },
+    );
+    assert!(raw_off > 5, "the pixel did not blink");
+}
+
+#[test]
+fn slow_blinking_is_left_alone() {
+    run(
+        &mut common::load(&common::blink(3)),
+        30,
+        |frame, raw, shown| {
+            assert_eq!(shown, raw, "frame {}", frame);
+        },
+    );
+}
+
+#[test]
+fn moving_sprites_leave_no_trail() {
+    run(&mut common::load(MOVING), 20, |frame, raw, shown| {
+        assert_eq!(raw.iter().filter(|&&px| px != 0).count(), 1);
+        assert_eq!(shown, raw, "frame {}", frame);
+    });
+}
+
+#[test]
+fn planes_are_filled_in_separately() {
+    let mut raw_colors = Vec::new();
+    run(&mut xo_chip(PLANES), 20, |frame, raw, shown| {
+        raw_colors.push(raw[0]);
+        if frame >= 3 {
+            assert_eq!(shown[0], 3, "frame {}", frame);
+        }
+    });
+    assert!(raw_colors.contains(&2) && raw_colors.contains(&3));
+
+    // A pixel that moves from plane 1 to plane 2 does not turn into colour 3
+    run(&mut xo_chip(SWITCH), 10, |frame, raw, shown| {
+        assert_eq!(shown, raw, "frame {}", frame);
+    });
+}
+
+#[test]
+fn reset_shows_the_plain_screen() {
+    let mut emu = common::load(&common::blink(1));
+    let mut scheduler = Scheduler::new(Timing::default());
+    let mut deflicker = Deflicker::new();
+
+    // Run until the blinking pixel is filled in
+    for _ in 0..10 {
+        scheduler.run_frame(&mut emu).unwrap();
+        deflicker.push(&emu);
+    }
+    if emu.get_display()[0] != 0 {
+        scheduler.run_frame(&mut emu).unwrap();
+        deflicker.push(&emu);
+    }
+    assert_eq!(emu.get_display()[0], 0);
+    assert_eq!(deflicker.display(&emu)[0], 1);
+
+    deflicker.reset();
+    assert_eq!(deflicker.display(&emu), emu.get_display());
+}
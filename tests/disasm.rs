@@ -0,0 +1,155 @@
+// tests/disasm.rs
+//! Mnemonics and code/data separation of `chip8_emu::disasm`.
+use chip8_emu::disasm::{decode, disassemble, Flow, Line};
+use chip8_emu::{Quirks, QuirksProfile};
+
+fn mnemonic(words: &[u16], quirks: &Quirks) -> String {
+    let memory: Vec<u8> = words.iter().flat_map(|w| w.to_be_bytes()).collect();
+    decode(&memory, 0, quirks).unwrap().mnemonic
+}
+
+#[test]
+fn mnemonics_of_every_opcode_group() {
+    let chip8 = QuirksProfile::CosmacVip.quirks();
+    let cases: &[(u16, &str)] = &[
+        (0x0000, "NOP"),
+        (0x00E0, "CLS"),
+        (0x00EE, "RET"),
+        (0x1234, "JP 0x234"),
+        (0x2456, "CALL 0x456"),
+        (0x3A1F, "SE VA, 0x1F"),
+        (0x4B20, "SNE VB, 0x20"),
+        (0x5120, "SE V1, V2"),
+        (0x6C7F, "LD VC, 0x7F"),
+        (0x7D01, "ADD VD, 0x01"),
+        (0x8120, "LD V1, V2"),
+        (0x8121, "OR V1, V2"),
+        (0x8122, "AND V1, V2"),
+        (0x8123, "XOR V1, V2"),
+        (0x8124, "ADD V1, V2"),
+        (0x8125, "SUB V1, V2"),
+        (0x8126, "SHR V1, V2"),
+        (0x8127, "SUBN V1, V2"),
+        (0x812E, "SHL V1, V2"),
+        (0x9340, "SNE V3, V4"),
+        (0xA2F0, "LD I, 0x2F0"),
+        (0xB300, "JP V0, 0x300"),
+        (0xC50F, "RND V5, 0x0F"),
+        (0xD015, "DRW V0, V1, 5"),
+        (0xE79E, "SKP V7"),
+        (0xE8A1, "SKNP V8"),
+        (0xF107, "LD V1, DT"),
+        (0xF20A, "LD V2, K"),
+        (0xF315, "LD DT, V3"),
+        (0xF418, "LD ST, V4"),
+        (0xF51E, "ADD I, V5"),
+        (0xF629, "LD F, V6"),
+        (0xF733, "LD B, V7"),
+        (0xF855, "LD [I], V8"),
+        (0xF965, "LD V9, [I]"),
+    ];
+    for &(op, expected) in cases {
+        assert_eq!(mnemonic(&[op], &chip8), expected, "{:04X}", op);
+    }
+}
+
+#[test]
+fn schip_and_xo_chip_mnemonics() {
+    let schip = QuirksProfile::SuperChip.quirks();
+    let xo = QuirksProfile::XoChip.quirks();
+    let schip_cases: &[(u16, &str)] = &[
+        (0x00C4, "SCD 4"),
+        (0x00FB, "SCR"),
+        (0x00FC, "SCL"),
+        (0x00FD, "EXIT"),
+        (0x00FE, "LOW"),
+        (0x00FF, "HIGH"),
+        (0x8106, "SHR V1"),
+        (0x810E, "SHL V1"),
+        (0xB312, "JP V3, 0x312"),
+        (0xF130, "LD HF, V1"),
+        (0xF375, "LD R, V3"),
+        (0xF385, "LD V3, R"),
+    ];
+    for &(op, expected) in schip_cases {
+        assert_eq!(mnemonic(&[op], &schip), expected, "{:04X}", op);
+    }
+
+    let xo_cases: &[(u16, &str)] = &[
+        (0x00D3, "SCU 3"),
+        (0x5232, "SAVE V2-V3"),
+        (0x5233, "LOAD V2-V3"),
+        (0xF201, "PLANE 2"),
+        (0xF002, "AUDIO"),
+        (0xF43A, "PITCH V4"),
+    ];
+    for &(op, expected) in xo_cases {
+        assert_eq!(mnemonic(&[op], &xo), expected, "{:04X}", op);
+    }
+
+    // F000 NNNN is one 4-byte instruction
+    let memory = [0xF0, 0x00, 0x12, 0x34];
+    let long = decode(&memory, 0, &xo).unwrap();
+    assert_eq!((long.mnemonic.as_str(), long.len), ("LD I, 0x1234", 4));
+    assert_eq!(decode(&memory[..2], 0, &xo), None);
+
+    // Outside XO-CHIP these are unknown
+    for op in [0x00D3u16, 0x5232, 0x5233, 0xF000, 0xF201, 0xF002, 0xF43A] {
+        assert!(!decode(&op.to_be_bytes(), 0, &schip).unwrap().valid);
+    }
+}
+
+#[test]
+fn unknown_words_become_data() {
+    let quirks = QuirksProfile::CosmacVip.quirks();
+    let unknown = decode(&[0x5A, 0xB1], 0, &quirks).unwrap();
+    assert!(!unknown.valid);
+    assert_eq!(unknown.flow, Flow::Stop);
+    assert_eq!(unknown.mnemonic, "DW 0x5AB1");
+
+    // Reached as code, but not executable: listed as bytes
+    let listing = disassemble(&[0x5A, 0xB1], &quirks);
+    assert_eq!(
+        listing.lines,
+        vec![Line::Data {
+            addr: 0x200,
+            bytes: vec![0x5A, 0xB1],
+        }]
+    );
+    assert!(listing.to_string().contains("DB 0x5A, 0xB1"));
+}
+
+#[test]
+fn reachability_stops_at_data() {
+    let quirks = QuirksProfile::CosmacVip.quirks();
+    let rom = [
+        0xA2, 0x08, // 200: LD I, 0x208
+        0x22, 0x0C, // 202: CALL 0x20C
+        0x12, 0x06, // 204: JP 0x206
+        0x12, 0x06, // 206: JP 0x206 (loops forever)
+        0x60, 0x01, // 208: sprite bytes that decode as LD V0, 0x01
+        0xFF, 0xFF, // 20A: more data
+        0x00, 0xEE, // 20C: RET
+    ];
+    let listing = disassemble(&rom, &quirks);
+
+    let code: Vec<u16> = listing
+        .lines
+        .iter()
+        .filter(|line| matches!(line, Line::Code { .. }))
+        .map(Line::addr)
+        .collect();
+    assert_eq!(code, [0x200, 0x202, 0x204, 0x206, 0x20C]);
+    assert!(listing.lines.iter().any(|line| *line
+        == Line::Data {
+            addr: 0x208,
+            bytes: vec![0x60, 0x01, 0xFF, 0xFF],
+        }));
+
+    // Jump and call targets get labels
+    assert_eq!(listing.labels.get(&0x20C).map(String::as_str), Some("L20C"));
+    assert_eq!(listing.labels.get(&0x206).map(String::as_str), Some("L206"));
+    let text = listing.to_string();
+    assert!(text.contains("CALL L20C"));
+    assert!(text.contains("L206:\n"));
+}
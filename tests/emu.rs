@@ -0,0 +1,131 @@
+// tests/emu.rs
+//! Errors and edge cases of `chip8_emu::Emu` at the limits of the stack,
+//! memory and the keypad.
+use chip8_emu::constants::{RAM_SIZE, STACK_SIZE, START_ADDR};
+use chip8_emu::{Chip8Error, Emu, QuirksProfile};
+
+fn load(words: &[u16]) -> Emu {
+    let rom: Vec<u8> = words.iter().flat_map(|w| w.to_be_bytes()).collect();
+    let mut emu = Emu::new();
+    emu.load_rom(&rom).unwrap();
+    emu
+}
+
+// Ticks until an instruction fails
+fn first_error(emu: &mut Emu) -> Chip8Error {
+    for _ in 0..100 {
+        if let Err(e) = emu.tick() {
+            return e;
+        }
+    }
+    panic!("no instruction failed");
+}
+
+#[test]
+fn return_with_an_empty_stack_underflows() {
+    let mut emu = load(&[0x6001, 0x00EE]);
+    assert_eq!(
+        first_error(&mut emu),
+        Chip8Error::StackUnderflow { pc: 0x202 }
+    );
+    assert_eq!(emu.sp(), 0);
+}
+
+#[test]
+fn seventeen_nested_calls_overflow_the_stack() {
+    // 200: call 202, 202: call 202, ...
+    let mut emu = load(&[0x2202, 0x2202]);
+    assert_eq!(
+        first_error(&mut emu),
+        Chip8Error::StackOverflow { pc: 0x202 }
+    );
+    assert_eq!(emu.sp() as usize, STACK_SIZE);
+}
+
+#[test]
+fn unknown_opcode_reports_its_address() {
+    // 5XY1 is not an instruction outside XO-CHIP
+    let mut emu = load(&[0x6001, 0x6102, 0x5121]);
+    assert_eq!(
+        first_error(&mut emu),
+        Chip8Error::UnknownOpcode {
+            opcode: 0x5121,
+            pc: 0x204
+        }
+    );
+    assert_eq!(emu.op_pc(), 0x204);
+}
+
+#[test]
+fn oversized_rom_is_rejected() {
+    let max = RAM_SIZE - START_ADDR as usize;
+    let mut emu = Emu::new();
+    assert!(emu.load_rom(&vec![0; max]).is_ok());
+    assert_eq!(
+        emu.load_rom(&vec![0; max + 1]),
+        Err(Chip8Error::RomTooLarge { size: max + 1, max })
+    );
+}
+
+#[test]
+fn memory_access_past_the_end_of_ram_is_an_error() {
+    let cases: &[(&[u16], usize)] = &[
+        // I := FFE, BCD of V0 writes FFE, FFF and 1000
+        (&[0xAFFE, 0xF033], 0x1000),
+        // I := FFF, save V0-V1
+        (&[0xAFFF, 0xF155], 0x1000),
+        // I := FFD, load V0-V3
+        (&[0xAFFD, 0xF365], 0x1000),
+    ];
+    for &(program, addr) in cases {
+        let mut emu = load(program);
+        assert_eq!(
+            first_error(&mut emu),
+            Chip8Error::MemoryOutOfBounds { addr, pc: 0x202 },
+            "{:04X?}",
+            program
+        );
+    }
+}
+
+// XO-CHIP machine with `op` in the last word of its 64K memory
+fn at_end_of_memory(op: [u8; 2]) -> Emu {
+    let mut emu = Emu::with_quirks(QuirksProfile::XoChip.quirks());
+    emu.load_rom(&[0x12, 0x00]).unwrap();
+    let mut snapshot = emu.snapshot();
+    let end = snapshot.ram.len() - 2;
+    snapshot.ram[end..].copy_from_slice(&op);
+    snapshot.pc = end as u16;
+    emu.restore(snapshot).unwrap();
+    emu
+}
+
+#[test]
+fn key_wait_at_end_of_memory_repeats_without_overflow() {
+    // FX0A with no key held: the PC wraps to 0 on fetch and back to FFFE
+    let mut emu = at_end_of_memory([0xF0, 0x0A]);
+    emu.tick().unwrap();
+    assert_eq!(emu.pc(), 0xFFFE);
+    emu.tick().unwrap();
+    assert_eq!(emu.pc(), 0xFFFE);
+}
+
+#[test]
+fn display_wait_at_end_of_memory_repeats_without_overflow() {
+    let mut quirks = QuirksProfile::XoChip.quirks();
+    quirks.display_wait = true;
+    let mut emu = at_end_of_memory([0xD0, 0x01]);
+    emu.set_quirks(quirks);
+    emu.tick().unwrap();
+    assert_eq!(emu.pc(), 0xFFFE);
+}
+
+#[test]
+fn keys_out_of_range_are_ignored() {
+    let mut emu = Emu::new();
+    emu.keypress(0x10, true);
+    emu.keypress(usize::MAX, true);
+    emu.keypress(0xF, true);
+    assert_eq!(emu.keys().iter().filter(|&&held| held).count(), 1);
+    assert!(emu.keys()[0xF]);
+}
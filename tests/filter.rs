@@ -0,0 +1,174 @@
+// tests/filter.rs
+//! Phosphor persistence and scanlines of `chip8_emu::filter`.
+use chip8_emu::asm;
+use chip8_emu::filter::{Filter, FilterSettings};
+use chip8_emu::headless::DEFAULT_PALETTE;
+use chip8_emu::timing::{Scheduler, Timing};
+use chip8_emu::Emu;
+
+// One lit pixel in the top left corner
+const DOT: &str = "
+: main
+    i := dot
+    sprite v0 v0 1
+    loop again
+: dot
+    0x80
+";
+
+fn dot() -> Emu {
+    let mut emu = Emu::new();
+    emu.load_rom(&asm::assemble(DOT).unwrap().rom).unwrap();
+    Scheduler::new(Timing::default())
+        .run_frame(&mut emu)
+        .unwrap();
+    emu
+}
+
+// Colour of the top left output pixel
+fn corner(filter: &Filter) -> [u8; 3] {
+    let rgb = filter.render(1);
+    [rgb[0], rgb[1], rgb[2]]
+}
+
+#[test]
+fn no_filter_is_a_plain_upscale() {
+    let mut filter = Filter::new(FilterSettings::default());
+    filter.update(&dot(), &DEFAULT_PALETTE, 1);
+
+    let rgb = filter.render(2);
+    let width = 64 * 2;
+    for (x, y) in [(0, 0), (1, 0), (0, 1), (1, 1)] {
+        assert_eq!(rgb[(y * width + x) * 3..][..3], DEFAULT_PALETTE[1]);
+    }
+    assert_eq!(rgb[2 * 3..][..3], DEFAULT_PALETTE[0]);
+
+    // Turned off at once
+    filter.update(&Emu::new(), &DEFAULT_PALETTE, 1);
+    assert_eq!(corner(&filter), DEFAULT_PALETTE[0]);
+}
+
+#[test]
+fn persistence_fades_out_over_the_set_frames() {
+    let settings = FilterSettings {
+        persistence: 4,
+        ..Default::default()
+    };
+    let mut filter = Filter::new(settings);
+    let (lit, blank) = (dot(), Emu::new());
+    filter.update(&lit, &DEFAULT_PALETTE, 1);
+    assert_eq!(corner(&filter), DEFAULT_PALETTE[1]);
+
+    let mut last = DEFAULT_PALETTE[1];
+    for _ in 0..4 {
+        filter.update(&blank, &DEFAULT_PALETTE, 1);
+        let now = corner(&filter);
+        assert!(now[0] < last[0] && now[0] > DEFAULT_PALETTE[0][0]);
+        last = now;
+    }
+    // At most 5% left after the persistence time
+    let range = (DEFAULT_PALETTE[1][0] - DEFAULT_PALETTE[0][0]) as f32;
+    assert!((last[0] - DEFAULT_PALETTE[0][0]) as f32 <= range * 0.05 + 1.0);
+
+    // Paused: nothing fades
+    filter.update(&blank, &DEFAULT_PALETTE, 0);
+    assert_eq!(corner(&filter), last);
+
+    // Lighting up again is instant
+    filter.update(&lit, &DEFAULT_PALETTE, 1);
+    assert_eq!(corner(&filter), DEFAULT_PALETTE[1]);
+}
+
+#[test]
+fn scanlines_darken_the_bottom_of_each_row() {
+    let settings = FilterSettings {
+        scanlines: true,
+        ..Default::default()
+    };
+    let mut filter = Filter::new(settings);
+    filter.update(&dot(), &DEFAULT_PALETTE, 1);
+
+    let scale = 3;
+    let rgb = filter.render(scale);
+    let width = 64 * scale;
+    let row = |y: usize| rgb[y * width * 3];
+    assert_eq!(row(0), DEFAULT_PALETTE[1][0]);
+    assert_eq!(row(1), DEFAULT_PALETTE[1][0]);
+    assert!(row(2) < DEFAULT_PALETTE[1][0]);
+}
+
+#[test]
+fn grid_darkens_the_last_row_and_column_of_each_pixel() {
+    let settings = FilterSettings {
+        grid: true,
+        ..Default::default()
+    };
+    let mut filter = Filter::new(settings);
+    // A grey background, so the grid shows on unlit pixels too
+    let palette = [[100, 100, 100], [255, 255, 255], [0, 0, 0], [0, 0, 0]];
+    filter.update(&dot(), &palette, 1);
+
+    let scale = 4;
+    let rgb = filter.render(scale);
+    let width = 64 * scale;
+    let pixel = |x: usize, y: usize| rgb[(y * width + x) * 3];
+    for y in 0..32 * scale {
+        for x in 0..width {
+            let lit = x < scale && y < scale;
+            let base = if lit { 255 } else { 100 };
+            let on_column = x % scale == scale - 1;
+            let on_row = y % scale == scale - 1;
+            let value = pixel(x, y);
+            match (on_column, on_row) {
+                (false, false) => assert_eq!(value, base, "x {} y {}", x, y),
+                (true, true) => assert!(value < pixel(x - 1, y), "x {} y {}", x, y),
+                _ => assert!(value < base, "x {} y {}", x, y),
+            }
+        }
+    }
+    assert_eq!(pixel(3, 0), pixel(0, 3));
+    assert_eq!(pixel(7, 0), pixel(4, 3));
+
+    // Too small for lines
+    let rgb = filter.render(2);
+    assert!(rgb.chunks(3).skip(2).take(62).all(|px| px == [100; 3]));
+}
+
+#[test]
+fn bloom_never_darkens_below_the_background() {
+    let settings = FilterSettings {
+        bloom: true,
+        ..Default::default()
+    };
+    let plain = Filter::new(FilterSettings::default());
+    // Lit pixels darker than the background, as on the Game Boy
+    let gameboy = [[155, 188, 15], [15, 56, 15], [139, 172, 15], [48, 98, 48]];
+    for palette in [DEFAULT_PALETTE, gameboy] {
+        let mut bloom = Filter::new(settings);
+        let mut plain = plain.clone();
+        bloom.update(&dot(), &palette, 1);
+        plain.update(&dot(), &palette, 1);
+
+        let (glowing, flat) = (bloom.render(1), plain.render(1));
+        for (n, (px, flat)) in glowing.chunks(3).zip(flat.chunks(3)).enumerate() {
+            for c in 0..3 {
+                assert!(px[c] >= flat[c], "pixel {} channel {}", n, c);
+                if n != 0 {
+                    assert!(px[c] >= palette[0][c], "pixel {} channel {}", n, c);
+                }
+            }
+        }
+        // Far from the dot nothing changes
+        assert_eq!(glowing[10 * 3..][..3], palette[0]);
+    }
+
+    // Neighbours of a lit pixel glow
+    let mut bloom = Filter::new(settings);
+    bloom.update(&dot(), &DEFAULT_PALETTE, 1);
+    let rgb = bloom.render(1);
+    let right = &rgb[3..6];
+    let below = &rgb[64 * 3..][..3];
+    let diagonal = &rgb[65 * 3..][..3];
+    assert!(right[0] > 0 && right == below);
+    assert!(diagonal[0] > 0 && diagonal[0] < right[0]);
+}
@@ -0,0 +1,164 @@
+// tests/movie.rs
+//! Recording and replaying input movies with `chip8_emu::movie`.
+use chip8_emu::asm;
+use chip8_emu::headless::{self, RunConfig};
+use chip8_emu::movie::{self, Movie};
+use chip8_emu::rng::{Rng, RngMode};
+use chip8_emu::timing::{Scheduler, Timing};
+use chip8_emu::{Emu, QuirksProfile};
+
+// Waits for a key, then draws it at a random place
+const GAME: &str = "
+: main
+    loop
+        v0 := key
+        i := hex v0
+        v1 := random 0x3F
+        v2 := random 0x1F
+        sprite v1 v2 5
+    again
+";
+
+fn power_on(rom: &[u8], movie: &Movie) -> Emu {
+    let mut emu = Emu::with_quirks(movie.quirks.quirks());
+    emu.load_rom(rom).unwrap();
+    emu.set_rng(Rng::new(movie.rng_mode, movie.seed));
+    emu
+}
+
+// Plays a game with a key going down and up every 20 frames
+fn record(rom: &[u8], seed: u64) -> Movie {
+    let mut movie = Movie::new(
+        rom,
+        QuirksProfile::Modern,
+        RngMode::Xorshift,
+        seed,
+        Timing::default(),
+    );
+    let mut emu = power_on(rom, &movie);
+    let mut scheduler = Scheduler::new(movie.timing);
+    for frame in 0..200 {
+        let key = frame / 20 % 16;
+        emu.keypress(key, frame % 20 < 10);
+        movie.record_frame(&emu);
+        scheduler.run_frame(&mut emu).unwrap();
+    }
+    movie.finish(&emu);
+    movie
+}
+
+fn replay(rom: &[u8], movie: &Movie) -> Emu {
+    let mut emu = power_on(rom, movie);
+    let config = RunConfig {
+        max_frames: movie.len() as u64,
+        timing: movie.timing,
+        until: None,
+        input: movie.input_script(),
+    };
+    headless::run(&mut emu, &config).unwrap();
+    emu
+}
+
+#[test]
+fn replay_ends_on_the_recorded_screen() {
+    let rom = asm::assemble(GAME).unwrap().rom;
+    let movie = record(&rom, 42);
+    assert_eq!(movie.len(), 200);
+
+    let emu = replay(&rom, &movie);
+    assert_eq!(Some(movie::display_hash(&emu)), movie.final_hash);
+
+    // The same keys with another seed draw elsewhere
+    let mut other = movie.clone();
+    other.seed = 7;
+    let emu = replay(&rom, &other);
+    assert_ne!(Some(movie::display_hash(&emu)), movie.final_hash);
+}
+
+#[test]
+fn movie_survives_a_round_trip_through_text() {
+    let rom = asm::assemble(GAME).unwrap().rom;
+    let movie = record(&rom, 42);
+    let text = movie.to_string();
+    assert!(text.lines().any(|line| line == "0..............."));
+    assert_eq!(text.parse::<Movie>().unwrap(), movie);
+}
+
+#[test]
+fn movie_checks_rom_and_input() {
+    let rom = asm::assemble(GAME).unwrap().rom;
+    let movie = record(&rom, 42);
+    assert!(movie.check_rom(&rom).is_ok());
+    assert!(movie.check_rom(&[0x12, 0x00]).is_err());
+
+    let text = movie
+        .to_string()
+        .replace("0...............", ".0..............");
+    let err = text.parse::<Movie>().unwrap_err();
+    assert!(err.contains("key 1"), "{}", err);
+}
+
+#[test]
+fn malformed_movies_are_rejected() {
+    let header = "c8m 1\nrom 00000000000000ab\n";
+    let cases = [
+        ("", "not a c8m movie"),
+        ("c8m 2\ninput\n", "not a c8m movie"),
+        ("c8m 1\ninput\n", "no rom hash"),
+        (header, "no 'input' line"),
+        (
+            "c8m 1\nrom 00000000000000ab\nquirks modern\n",
+            "no 'input' line",
+        ),
+        (
+            "c8m 1\nrom 00000000000000ab\nspeed 2\ninput\n",
+            "line 3: unknown header 'speed'",
+        ),
+        (
+            "c8m 1\nrom 00000000000000ab\nseed\ninput\n",
+            "line 3: expected '<name> <value>'",
+        ),
+        ("c8m 1\nrom xyz\ninput\n", "line 2: invalid hash 'xyz'"),
+        (
+            "c8m 1\nrom 00000000000000ab\nseed -1\ninput\n",
+            "line 3: invalid seed '-1'",
+        ),
+    ];
+    for (text, expected) in cases {
+        let err = text.parse::<Movie>().unwrap_err();
+        assert!(err.contains(expected), "{:?}: {}", text, err);
+    }
+    assert!(format!("{}input\n", header).parse::<Movie>().is_ok());
+}
+
+#[test]
+fn bad_key_columns_are_rejected() {
+    let movie = |frames: &str| format!("c8m 1\nrom 00000000000000ab\ninput\n{}", frames);
+    let cases = [
+        (
+            "................\n..3.............\n",
+            "line 5: '3' in the column of key 2",
+        ),
+        ("...2............\n", "line 4: '2' in the column of key 3"),
+        ("1...............\n", "line 4: '1' in the column of key 0"),
+        ("...............X\n", "line 4: 'X' in the column of key F"),
+        ("...............\n", "line 4: expected 16 keys"),
+        (".................\n", "line 4: expected 16 keys"),
+        ("\n", "line 4: expected 16 keys"),
+    ];
+    for (frames, expected) in cases {
+        let err = movie(frames).parse::<Movie>().unwrap_err();
+        assert!(err.contains(expected), "{:?}: {}", frames, err);
+    }
+
+    let ok = movie("0123456789ABCDEF\n.1.............F\n")
+        .parse::<Movie>()
+        .unwrap();
+    assert_eq!(ok.len(), 2);
+    let mut emu = Emu::new();
+    assert!(ok.apply_frame(0, &mut emu));
+    assert!(emu.keys().iter().all(|&held| held));
+    assert!(ok.apply_frame(1, &mut emu));
+    let held: Vec<usize> = (0..16).filter(|&key| emu.keys()[key]).collect();
+    assert_eq!(held, [0x1, 0xF]);
+}
@@ -0,0 +1,102 @@
+// tests/pad.rs
+//! Controller input on the keypad, driven by synthetic controller events.
+use chip8_emu::pad::{PadEvent, PadMap, PadState, AXIS_THRESHOLD};
+use chip8_emu::Emu;
+
+fn button(pad: u32, button: &str, pressed: bool) -> PadEvent {
+    PadEvent::Button {
+        pad,
+        button: button.to_string(),
+        pressed,
+    }
+}
+
+fn axis(pad: u32, axis: &str, value: i16) -> PadEvent {
+    PadEvent::Axis {
+        pad,
+        axis: axis.to_string(),
+        value,
+    }
+}
+
+fn held(emu: &Emu) -> Vec<usize> {
+    (0..16).filter(|&k| emu.keys()[k]).collect()
+}
+
+#[test]
+fn dpad_presses_default_keys() {
+    let map = PadMap::default();
+    let mut state = PadState::default();
+    let mut emu = Emu::new();
+
+    state.apply(&map, &button(0, "dpup", true), &mut emu);
+    state.apply(&map, &button(0, "a", true), &mut emu);
+    assert_eq!(held(&emu), vec![0x5, 0x6]);
+
+    state.apply(&map, &button(0, "dpup", false), &mut emu);
+    assert_eq!(held(&emu), vec![0x6]);
+}
+
+#[test]
+fn key_stays_down_while_any_controller_holds_it() {
+    let map = PadMap::default();
+    let mut state = PadState::default();
+    let mut emu = Emu::new();
+
+    state.apply(&map, &button(0, "dpleft", true), &mut emu);
+    state.apply(&map, &axis(1, "leftx", -AXIS_THRESHOLD), &mut emu);
+    state.apply(&map, &button(0, "dpleft", false), &mut emu);
+    assert_eq!(held(&emu), vec![0x7]);
+
+    // A small stick movement is within the dead zone
+    state.apply(&map, &axis(1, "leftx", -AXIS_THRESHOLD + 1), &mut emu);
+    assert!(held(&emu).is_empty());
+
+    state.apply(&map, &axis(1, "leftx", i16::MAX), &mut emu);
+    assert_eq!(held(&emu), vec![0x9]);
+}
+
+#[test]
+fn unplugging_releases_held_keys() {
+    let map = PadMap::default();
+    let mut state = PadState::default();
+    let mut emu = Emu::new();
+
+    state.apply(&map, &button(0, "b", true), &mut emu);
+    state.apply(&map, &button(1, "dpdown", true), &mut emu);
+    state.apply(&map, &PadEvent::Removed { pad: 1 }, &mut emu);
+    assert_eq!(held(&emu), vec![0x4]);
+}
+
+#[test]
+fn custom_bindings_replace_defaults() {
+    let mut map = PadMap::empty();
+    map.bind("start", 0xF);
+    map.bind("righttrigger+", 0x0);
+    let mut state = PadState::default();
+    let mut emu = Emu::new();
+
+    state.apply(&map, &button(0, "dpup", true), &mut emu);
+    state.apply(&map, &button(0, "start", true), &mut emu);
+    state.apply(&map, &axis(0, "righttrigger", i16::MAX), &mut emu);
+    assert_eq!(held(&emu), vec![0x0, 0xF]);
+}
+
+#[test]
+fn bindings_outside_the_keypad_are_rejected() {
+    let map: PadMap = serde_json::from_str(r#"{"start": 15, "a": 0}"#).unwrap();
+    assert_eq!(map.key("start"), Some(0xF));
+    assert_eq!(map.key("a"), Some(0x0));
+    assert_eq!(
+        serde_json::to_string(&map).unwrap(),
+        r#"{"a":0,"start":15}"#
+    );
+
+    let err = serde_json::from_str::<PadMap>(r#"{"start": 16}"#).unwrap_err();
+    assert!(
+        err.to_string().contains("'start' is bound to key 16"),
+        "{}",
+        err
+    );
+    assert!(serde_json::from_str::<PadMap>(r#"{"a": 99999}"#).is_err());
+}
@@ -0,0 +1,98 @@
+// tests/record.rs
+//! GIF, Y4M and WAV output of `chip8_emu::record`.
+mod common;
+
+use chip8_emu::headless::DEFAULT_PALETTE;
+use chip8_emu::record::{AudioRecorder, VideoFormat, VideoRecorder, SAMPLE_RATE};
+use chip8_emu::timing::{Scheduler, Timing};
+use chip8_emu::Emu;
+use common::{blink, load};
+use std::io::Cursor;
+
+// Beeps for half a second, then waits
+const BEEP: &str = "
+: main
+    v0 := 30
+    buzzer := v0
+    loop again
+";
+
+fn record_video(format: VideoFormat, emu: &mut Emu, frames: u32) -> Vec<u8> {
+    let mut scheduler = Scheduler::new(Timing::default());
+    let mut recorder = VideoRecorder::new(
+        Vec::new(),
+        format,
+        (emu.width(), emu.height()),
+        2,
+        &DEFAULT_PALETTE,
+    )
+    .unwrap();
+    for _ in 0..frames {
+        scheduler.run_frame(emu).unwrap();
+        recorder.add_frame(emu).unwrap();
+    }
+    recorder.finish().unwrap()
+}
+
+// Delays of every frame in a GIF, in 1/100 s
+fn gif_delays(data: &[u8]) -> Vec<u16> {
+    let mut decoder = gif::DecodeOptions::new().read_info(data).unwrap();
+    assert_eq!((decoder.width(), decoder.height()), (128, 64));
+    let mut delays = Vec::new();
+    while let Some(frame) = decoder.read_next_frame().unwrap() {
+        delays.push(frame.delay);
+    }
+    delays
+}
+
+#[test]
+fn gif_keeps_one_second_per_sixty_frames() {
+    let delays = gif_delays(&record_video(VideoFormat::Gif, &mut load(&blink(3)), 60));
+    assert!(delays.len() >= 15, "{:?}", delays);
+    assert_eq!(delays.iter().map(|&d| d as u32).sum::<u32>(), 100);
+}
+
+#[test]
+fn gif_drops_frames_too_short_for_viewers() {
+    // A new picture every frame is faster than GIF delays can show
+    let delays = gif_delays(&record_video(VideoFormat::Gif, &mut load(&blink(1)), 60));
+    assert!(delays.iter().all(|&d| d >= 2), "{:?}", delays);
+    // The last frame may be stretched to the minimum
+    let total: u32 = delays.iter().map(|&d| d as u32).sum();
+    assert!((100..=101).contains(&total), "{:?}", delays);
+}
+
+#[test]
+fn y4m_writes_every_frame() {
+    let data = record_video(VideoFormat::Y4m, &mut load(&blink(3)), 10);
+    let header = b"YUV4MPEG2 W128 H64 F60:1 Ip A1:1 C444\n";
+    assert!(data.starts_with(header));
+    assert_eq!(data.len(), header.len() + 10 * (6 + 128 * 64 * 3));
+}
+
+#[test]
+fn wav_follows_the_sound_timer() {
+    let mut emu = load(BEEP);
+    let mut scheduler = Scheduler::new(Timing::default());
+    let mut recorder = AudioRecorder::new(Cursor::new(Vec::new())).unwrap();
+    for _ in 0..60 {
+        scheduler.run_frame(&mut emu).unwrap();
+        recorder.add_frame(&emu).unwrap();
+    }
+    let data = recorder.finish().unwrap().into_inner();
+
+    let samples_per_frame = (SAMPLE_RATE / 60) as usize;
+    let data_size = 60 * samples_per_frame * 2;
+    assert_eq!(&data[..4], b"RIFF");
+    assert_eq!(&data[4..8], &(36 + data_size as u32).to_le_bytes());
+    assert_eq!(&data[40..44], &(data_size as u32).to_le_bytes());
+    assert_eq!(data.len(), 44 + data_size);
+
+    let samples: Vec<i16> = data[44..]
+        .chunks(2)
+        .map(|s| i16::from_le_bytes([s[0], s[1]]))
+        .collect();
+    let frame = |n: usize| &samples[n * samples_per_frame..][..samples_per_frame];
+    assert!(frame(10).iter().any(|&s| s != 0));
+    assert!(frame(50).iter().all(|&s| s == 0));
+}
@@ -0,0 +1,77 @@
+// tests/rewind.rs
+//! Delta history of `chip8_emu::rewind`.
+use chip8_emu::rewind::RewindBuffer;
+
+// A 600-byte state that changes in scattered places from frame to frame, with
+// zero and literal runs both shorter and longer than one varint byte (127)
+fn state(frame: usize) -> Vec<u8> {
+    let mut state = vec![0u8; 600];
+    state[frame % 7] = frame as u8 + 1;
+    for byte in &mut state[200..200 + 150 + frame] {
+        *byte = (frame % 251) as u8 + 1;
+    }
+    state[599] = frame as u8;
+    state
+}
+
+#[test]
+fn rewinds_every_frame_byte_for_byte() {
+    let mut rewind = RewindBuffer::new(100);
+    let states: Vec<Vec<u8>> = (0..40).map(state).collect();
+    for s in &states {
+        rewind.push(s.clone());
+    }
+    assert_eq!(rewind.len(), 39);
+
+    for expected in states.iter().rev().skip(1) {
+        assert_eq!(rewind.rewind(), Some(expected.as_slice()));
+    }
+    assert_eq!(rewind.rewind(), None);
+    assert!(rewind.is_empty());
+}
+
+#[test]
+fn keeps_states_across_size_changes() {
+    // A lo-res/hi-res switch changes the size of the state
+    let states = [
+        vec![1; 10],
+        vec![2; 30],
+        vec![2; 30],
+        vec![0; 10],
+        vec![5; 10],
+    ];
+    let mut rewind = RewindBuffer::new(10);
+    for s in &states {
+        rewind.push(s.clone());
+    }
+    for expected in states.iter().rev().skip(1) {
+        assert_eq!(rewind.rewind(), Some(expected.as_slice()));
+    }
+}
+
+#[test]
+fn oldest_frames_are_dropped_at_capacity() {
+    let mut rewind = RewindBuffer::new(5);
+    for frame in 0..10 {
+        rewind.push(state(frame));
+    }
+    assert_eq!(rewind.len(), 5);
+
+    for frame in (4..9).rev() {
+        assert_eq!(rewind.rewind(), Some(state(frame).as_slice()));
+    }
+    assert_eq!(rewind.rewind(), None);
+}
+
+#[test]
+fn deltas_are_smaller_than_full_states() {
+    let mut rewind = RewindBuffer::new(100);
+    for frame in 0..50 {
+        rewind.push(state(frame));
+    }
+    assert!(rewind.memory_usage() < 50 * 600 / 2);
+
+    rewind.clear();
+    assert!(rewind.is_empty());
+    assert_eq!(rewind.memory_usage(), 0);
+}
@@ -0,0 +1,70 @@
+// tests/rng.rs
+//! Seeded random numbers of `chip8_emu::rng` and `CXNN`.
+use chip8_emu::asm;
+use chip8_emu::rng::{Rng, RngMode};
+use chip8_emu::Emu;
+
+const MODES: [RngMode; 1] = [RngMode::Xorshift];
+
+fn bytes(rng: &mut Rng, count: usize) -> Vec<u8> {
+    (0..count).map(|_| rng.next_byte()).collect()
+}
+
+#[test]
+fn same_seed_gives_the_same_sequence() {
+    for mode in MODES {
+        let first = bytes(&mut Rng::new(mode, 1234), 100);
+        assert_eq!(bytes(&mut Rng::new(mode, 1234), 100), first, "{}", mode);
+        assert_ne!(bytes(&mut Rng::new(mode, 4321), 100), first, "{}", mode);
+
+        let mut rng = Rng::new(mode, 99);
+        bytes(&mut rng, 10);
+        rng.reseed(1234);
+        assert_eq!(bytes(&mut rng, 100), first, "{} after reseed", mode);
+        assert_eq!(rng.seed(), 1234);
+    }
+}
+
+#[test]
+fn cxnn_masks_the_random_byte() {
+    // vN := random NN for a few masks, then stop on a jump to itself
+    let source = "
+: main
+    v0 := random 0x0F
+    v1 := random 0xF0
+    v2 := random 0x00
+    v3 := random 0xA5
+    v4 := random 0xFF
+    loop again
+";
+    let rom = asm::assemble(source).unwrap().rom;
+    for mode in MODES {
+        for seed in 0..20 {
+            let mut emu = Emu::new();
+            emu.load_rom(&rom).unwrap();
+            emu.set_rng(Rng::new(mode, seed));
+            for _ in 0..5 {
+                emu.tick().unwrap();
+            }
+
+            let mut rng = Rng::new(mode, seed);
+            let v = emu.v_regs();
+            for (reg, mask) in [0x0F, 0xF0, 0x00, 0xA5, 0xFF].into_iter().enumerate() {
+                assert_eq!(v[reg] & !mask, 0, "v{} with mask {:02X}", reg, mask);
+                assert_eq!(v[reg], rng.next_byte() & mask);
+            }
+        }
+    }
+}
+
+#[test]
+fn mode_names_round_trip() {
+    for mode in MODES {
+        assert_eq!(mode.to_string().parse::<RngMode>(), Ok(mode));
+        assert_eq!(mode.id().to_uppercase().parse::<RngMode>(), Ok(mode));
+    }
+    assert_eq!(RngMode::default(), RngMode::Xorshift);
+    assert!("vip".parse::<RngMode>().is_err());
+    assert!("vblank".parse::<RngMode>().is_err());
+    assert!("".parse::<RngMode>().is_err());
+}
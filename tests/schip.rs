@@ -0,0 +1,193 @@
+// tests/schip.rs
+//! SUPER-CHIP opcodes of `chip8_emu::Emu`: resolutions, scrolling, big
+//! sprites and fonts, exit and the RPL flags.
+use chip8_emu::asm;
+use chip8_emu::constants::{BIG_FONTSET, BIG_FONT_ADDR};
+use chip8_emu::{Emu, QuirksProfile};
+use std::fs;
+
+fn load(source: &str, profile: QuirksProfile) -> Emu {
+    let mut emu = Emu::with_quirks(profile.quirks());
+    emu.load_rom(&asm::assemble(source).unwrap().rom).unwrap();
+    emu
+}
+
+fn run(emu: &mut Emu, instructions: usize) {
+    for _ in 0..instructions {
+        emu.tick().unwrap();
+    }
+}
+
+// Coordinates of the lit pixels, row by row
+fn lit(emu: &Emu) -> Vec<(usize, usize)> {
+    let width = emu.width();
+    (0..emu.get_display().len())
+        .filter(|&n| emu.get_display()[n] != 0)
+        .map(|n| (n % width, n / width))
+        .collect()
+}
+
+#[test]
+fn resolution_switches_between_64x32_and_128x64() {
+    let mut emu = load(
+        ": main hires v0 := 100 i := dot sprite v0 v0 1 lores loop again : dot 0x80",
+        QuirksProfile::SuperChip,
+    );
+    assert_eq!((emu.width(), emu.height()), (64, 32));
+    run(&mut emu, 1);
+    assert!(emu.is_hires());
+    assert_eq!((emu.width(), emu.height()), (128, 64));
+    assert_eq!(emu.get_display().len(), 128 * 64);
+
+    // Drawn past the lo-res edge
+    run(&mut emu, 3);
+    assert_eq!(lit(&emu), [(100, 36)]);
+
+    // Switching clears the screen
+    run(&mut emu, 1);
+    assert!(!emu.is_hires());
+    assert_eq!((emu.width(), emu.height()), (64, 32));
+    assert_eq!(emu.get_display(), [0; 64 * 32]);
+}
+
+// A dot at (10, 10), then scroll-down 4, scroll-right and scroll-left
+fn scrolled(resolution: &str, profile: QuirksProfile) -> Vec<Vec<(usize, usize)>> {
+    let source = format!(
+        ": main {} v0 := 10 i := dot sprite v0 v0 1
+           scroll-down 4 scroll-right scroll-right scroll-left
+           loop again
+         : dot 0x80",
+        resolution
+    );
+    let mut emu = load(&source, profile);
+    run(&mut emu, 4);
+    (0..4)
+        .map(|_| {
+            run(&mut emu, 1);
+            lit(&emu)
+        })
+        .collect()
+}
+
+#[test]
+fn hires_scrolls_whole_pixels() {
+    assert_eq!(
+        scrolled("hires", QuirksProfile::SuperChip),
+        [
+            vec![(10, 14)],
+            vec![(14, 14)],
+            vec![(18, 14)],
+            vec![(14, 14)]
+        ]
+    );
+}
+
+#[test]
+fn lores_scrolls_half_as_far() {
+    assert_eq!(
+        scrolled("lores", QuirksProfile::SuperChip),
+        [
+            vec![(10, 12)],
+            vec![(12, 12)],
+            vec![(14, 12)],
+            vec![(12, 12)]
+        ]
+    );
+    // XO-CHIP scrolls lo-res by whole pixels
+    assert_eq!(
+        scrolled("lores", QuirksProfile::XoChip),
+        [
+            vec![(10, 14)],
+            vec![(14, 14)],
+            vec![(18, 14)],
+            vec![(14, 14)]
+        ]
+    );
+}
+
+#[test]
+fn scrolling_drops_pixels_off_the_edge() {
+    let mut emu = load(
+        ": main hires v0 := 126 v1 := 63 i := dot sprite v0 v1 1
+           scroll-right scroll-down 1 loop again
+         : dot 0x80",
+        QuirksProfile::SuperChip,
+    );
+    run(&mut emu, 5);
+    assert_eq!(lit(&emu), [(126, 63)]);
+    run(&mut emu, 2);
+    assert!(lit(&emu).is_empty());
+}
+
+#[test]
+fn dxy0_draws_16x16_and_reports_collisions() {
+    let mut emu = load(
+        ": main hires i := block
+           sprite v0 v0 0
+           v1 := 8
+           sprite v1 v1 0
+           loop again
+         : block
+           0xFF 0xFF 0xFF 0xFF 0xFF 0xFF 0xFF 0xFF 0xFF 0xFF 0xFF 0xFF 0xFF 0xFF 0xFF 0xFF
+           0xFF 0xFF 0xFF 0xFF 0xFF 0xFF 0xFF 0xFF 0xFF 0xFF 0xFF 0xFF 0xFF 0xFF 0xFF 0xFF",
+        QuirksProfile::SuperChip,
+    );
+    run(&mut emu, 3);
+    let pixels = lit(&emu);
+    assert_eq!(pixels.len(), 256);
+    assert!(pixels.iter().all(|&(x, y)| x < 16 && y < 16));
+    assert_eq!(emu.v_regs()[0xF], 0);
+
+    // Overlapping by 8x8: those pixels go out
+    run(&mut emu, 2);
+    assert_eq!(emu.v_regs()[0xF], 1);
+    let pixels = lit(&emu);
+    assert_eq!(pixels.len(), 256 + 256 - 2 * 64);
+    assert!(!pixels.contains(&(8, 8)) && !pixels.contains(&(15, 15)));
+    assert!(pixels.contains(&(23, 23)) && pixels.contains(&(0, 0)));
+}
+
+#[test]
+fn fx30_points_at_the_big_font() {
+    for digit in 0..10u8 {
+        let source = format!(": main v3 := {} i := bighex v3 loop again", digit);
+        let mut emu = load(&source, QuirksProfile::SuperChip);
+        run(&mut emu, 2);
+        let addr = BIG_FONT_ADDR + digit as usize * 10;
+        assert_eq!(emu.i_reg() as usize, addr);
+        assert_eq!(
+            emu.ram()[addr..addr + 10],
+            BIG_FONTSET[digit as usize * 10..][..10]
+        );
+    }
+}
+
+#[test]
+fn exit_halts_the_machine() {
+    let mut emu = load(": main exit v0 := 1", QuirksProfile::SuperChip);
+    run(&mut emu, 1);
+    assert!(emu.has_exited());
+    run(&mut emu, 5);
+    assert_eq!(emu.pc(), 0x202);
+    assert_eq!(emu.v_regs()[0], 0);
+}
+
+#[test]
+fn rpl_flags_survive_a_round_trip_through_a_file() {
+    let mut first = load(
+        ": main v0 := 11 v1 := 22 v2 := 33 v3 := 44 saveflags v3 loop again",
+        QuirksProfile::SuperChip,
+    );
+    run(&mut first, 5);
+    assert_eq!(first.rpl_flags()[..5], [11, 22, 33, 44, 0]);
+
+    let path = std::env::temp_dir().join(format!("chip8-rpl-{}.flags", std::process::id()));
+    fs::write(&path, first.rpl_flags()).unwrap();
+    let flags = fs::read(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+
+    let mut second = load(": main loadflags v2 loop again", QuirksProfile::SuperChip);
+    second.set_rpl_flags(&flags);
+    run(&mut second, 1);
+    assert_eq!(second.v_regs()[..4], [11, 22, 33, 0]);
+}
@@ -0,0 +1,140 @@
+// tests/state.rs
+//! The versioned save state format of `chip8_emu::state`.
+use chip8_emu::asm;
+use chip8_emu::constants::STACK_SIZE;
+use chip8_emu::state::{Snapshot, StateError, STATE_VERSION};
+use chip8_emu::timing::{Scheduler, Timing};
+use chip8_emu::{Emu, QuirksProfile};
+
+// Hires, both planes, an audio pattern, pitch, flags and random sprites
+const XO: &str = "
+: main
+    hires
+    plane 3
+    i := pattern
+    audio
+    v0 := 200
+    pitch := v0
+    v0 := 30
+    buzzer := v0
+    v0 := 7
+    saveflags v0
+    loop
+        v1 := random 0x7F
+        v2 := random 0x3F
+        i := box
+        sprite v1 v2 4
+    again
+: pattern
+    0xF0 0xF0 0x0F 0x0F 0xAA 0x55 0xAA 0x55
+    0xF0 0xF0 0x0F 0x0F 0xAA 0x55 0xAA 0x55
+: box
+    0xF0 0x90 0x90 0xF0 0xFF 0x81 0x81 0xFF
+";
+
+const SP_OFFSET: usize = 18; // Magic, version, ROM hash, PC, I
+
+fn load(source: &str) -> Emu {
+    let mut emu = Emu::with_quirks(QuirksProfile::XoChip.quirks());
+    emu.load_rom(&asm::assemble(source).unwrap().rom).unwrap();
+    emu
+}
+
+fn run(emu: &mut Emu, frames: u32) {
+    let mut scheduler = Scheduler::new(Timing::default());
+    for _ in 0..frames {
+        scheduler.run_frame(emu).unwrap();
+    }
+}
+
+#[test]
+fn round_trip_keeps_hires_and_xo_state() {
+    let mut emu = load(XO);
+    run(&mut emu, 10);
+    let state = emu.save_state();
+
+    let mut copy = load(XO);
+    copy.load_state(&state).unwrap();
+    assert_eq!(copy.snapshot(), emu.snapshot());
+    assert!(copy.is_hires());
+    assert_eq!(copy.planes(), 3);
+    assert_eq!(copy.rpl_flags()[0], 7);
+    assert_eq!(copy.audio_pattern(), emu.audio_pattern());
+    assert!(copy.get_sound_timer() > 0);
+
+    // Random numbers continue where they left off
+    run(&mut emu, 10);
+    run(&mut copy, 10);
+    assert_eq!(copy.get_display(), emu.get_display());
+}
+
+// Loads `state` into a fresh machine, which must stay untouched
+fn rejection(state: &[u8]) -> StateError {
+    let mut emu = load(XO);
+    let before = emu.snapshot();
+    let err = emu.load_state(state).unwrap_err();
+    assert_eq!(emu.snapshot(), before);
+    err
+}
+
+#[test]
+fn wrong_magic_is_rejected() {
+    let mut state = load(XO).save_state();
+    state[0] = b'X';
+    assert_eq!(rejection(&state), StateError::NotAState);
+}
+
+#[test]
+fn other_version_is_rejected() {
+    let mut state = load(XO).save_state();
+    state[4..6].copy_from_slice(&(STATE_VERSION + 1).to_le_bytes());
+    assert_eq!(
+        rejection(&state),
+        StateError::UnsupportedVersion(STATE_VERSION + 1)
+    );
+}
+
+#[test]
+fn state_of_another_rom_is_rejected() {
+    let other = load(": main loop again");
+    let state = other.save_state();
+    assert_eq!(
+        rejection(&state),
+        StateError::RomMismatch {
+            expected: load(XO).rom_hash(),
+            found: other.rom_hash(),
+        }
+    );
+}
+
+#[test]
+fn truncated_state_is_rejected() {
+    let state = load(XO).save_state();
+    for len in [3, 20, state.len() / 2, state.len() - 1] {
+        assert_eq!(
+            rejection(&state[..len]),
+            StateError::Corrupt("unexpected end of data"),
+            "cut at {} bytes",
+            len
+        );
+    }
+}
+
+#[test]
+fn stack_pointer_out_of_range_is_rejected() {
+    let mut state = load(XO).save_state();
+    let sp = STACK_SIZE as u16 + 1;
+    state[SP_OFFSET..SP_OFFSET + 2].copy_from_slice(&sp.to_le_bytes());
+    assert_eq!(
+        rejection(&state),
+        StateError::Corrupt("stack pointer out of range")
+    );
+}
+
+#[test]
+fn trailing_bytes_are_rejected() {
+    let mut state = load(XO).save_state();
+    state.push(0);
+    assert_eq!(rejection(&state), StateError::Corrupt("trailing data"));
+    assert!(Snapshot::decode(&state).is_err());
+}
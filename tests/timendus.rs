@@ -0,0 +1,91 @@
+// tests/timendus.rs
+//! Timendus' chip8-test-suite ROMs, run headless under every quirks profile
+//! and compared against golden screens.
+//!
+//! The ROMs go in `tests/roms/timendus/` (`*.ch8`), with a golden screen per
+//! ROM and profile in `tests/roms/timendus/golden/<rom>.<profile>.txt` in the
+//! text form of [`headless::display_text`]. ROMs with a menu get a key script
+//! (see [`InputScript`]) named `<rom>.keys`, or `<rom>.<profile>.keys` for one
+//! profile. Run with `BLESS=1` to write the goldens of the current build.
+use chip8_emu::headless::{self, InputScript, RunConfig};
+use chip8_emu::timing::Timing;
+use chip8_emu::{Emu, QuirksProfile};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const TIMING: Timing = Timing::Ips(1000);
+const FRAMES: u64 = 600;
+
+fn suite_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/roms/timendus")
+}
+
+fn roms() -> Vec<PathBuf> {
+    let mut roms: Vec<PathBuf> = fs::read_dir(suite_dir())
+        .map(|dir| dir.filter_map(|entry| Some(entry.ok()?.path())).collect())
+        .unwrap_or_default();
+    roms.retain(|path| path.extension().is_some_and(|ext| ext == "ch8"));
+    roms.sort();
+    roms
+}
+
+fn input(stem: &str, profile: QuirksProfile) -> InputScript {
+    [
+        format!("{}.{}.keys", stem, profile.id()),
+        format!("{}.keys", stem),
+    ]
+    .iter()
+    .find_map(|name| fs::read_to_string(suite_dir().join(name)).ok())
+    .map(|text| text.parse().unwrap())
+    .unwrap_or_default()
+}
+
+// The screen after a fixed number of frames; the ROMs end waiting for a key
+// or in a loop, so there is no single halt condition
+fn screen(rom: &[u8], stem: &str, profile: QuirksProfile) -> String {
+    let mut emu = Emu::with_quirks(profile.quirks());
+    emu.load_rom(rom).unwrap();
+    let config = RunConfig {
+        max_frames: FRAMES,
+        timing: TIMING,
+        until: None,
+        input: input(stem, profile),
+    };
+    headless::run(&mut emu, &config)
+        .unwrap_or_else(|e| panic!("{} under {}: {}", stem, profile.id(), e));
+    headless::display_text(&emu)
+}
+
+#[test]
+#[ignore = "needs the chip8-test-suite ROMs in tests/roms/timendus"]
+fn screens_match_goldens() {
+    let roms = roms();
+    assert!(
+        !roms.is_empty(),
+        "no ROMs in {}; see the Tests section of the README",
+        suite_dir().display()
+    );
+    let bless = std::env::var_os("BLESS").is_some();
+    let mut mismatches = Vec::new();
+    for path in roms {
+        let stem = path.file_stem().unwrap().to_string_lossy().into_owned();
+        let rom = fs::read(&path).unwrap();
+        for profile in QuirksProfile::all() {
+            let actual = screen(&rom, &stem, profile);
+            let golden = suite_dir()
+                .join("golden")
+                .join(format!("{}.{}.txt", stem, profile.id()));
+            if bless {
+                fs::create_dir_all(golden.parent().unwrap()).unwrap();
+                fs::write(&golden, &actual).unwrap();
+                continue;
+            }
+            match fs::read_to_string(&golden) {
+                Ok(expected) if expected == actual => {}
+                Ok(_) => mismatches.push(format!("{} under {}:\n{}", stem, profile.id(), actual)),
+                Err(_) => mismatches.push(format!("{} has no golden", golden.display())),
+            }
+        }
+    }
+    assert!(mismatches.is_empty(), "{}", mismatches.join("\n"));
+}
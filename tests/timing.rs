@@ -0,0 +1,50 @@
+// tests/timing.rs
+//! Instruction budgets of `chip8_emu::timing::Scheduler`.
+use chip8_emu::asm;
+use chip8_emu::timing::{Scheduler, Timing};
+use chip8_emu::Emu;
+
+// Counts instructions in v0/v1 forever
+const COUNTER: &str = "
+: main
+    loop
+        v0 += 1
+        if v0 == 0 then v1 += 1
+    again
+";
+
+fn instructions_in_one_second(timing: Timing, source: &str) -> Emu {
+    let mut emu = Emu::new();
+    emu.load_rom(&asm::assemble(source).unwrap().rom).unwrap();
+    let mut scheduler = Scheduler::new(timing);
+    for _ in 0..60 {
+        scheduler.run_frame(&mut emu).unwrap();
+    }
+    emu
+}
+
+#[test]
+fn ips_carries_fractions_between_frames() {
+    // Three instructions per loop: add, skip, jump (the v1 add is skipped)
+    let emu = instructions_in_one_second(Timing::Ips(699), COUNTER);
+    assert_eq!(emu.v_regs()[0], 233);
+}
+
+#[test]
+fn vip_timing_draws_one_sprite_per_frame() {
+    let emu = instructions_in_one_second(
+        Timing::CosmacVip,
+        "
+: main
+    i := dot
+    loop
+        sprite v0 v0 1
+        v1 += 1
+    again
+: dot
+    0x80
+",
+    );
+    // The frame ends after each sprite; the add runs at the start of the next
+    assert_eq!(emu.v_regs()[1], 59);
+}
@@ -0,0 +1,92 @@
+// tests/trace.rs
+//! Trace lines and address ranges of `chip8_emu::trace`.
+use chip8_emu::asm;
+use chip8_emu::trace::{parse_range, TraceFormat, Tracer};
+use chip8_emu::Emu;
+use std::io::{self, Write};
+use std::ops::RangeInclusive;
+use std::sync::{Arc, Mutex};
+
+const PROGRAM: &str = "
+: main
+    v0 := 0x0C
+    v1 := 8
+    i := data
+    loop again
+: data
+    0
+";
+
+// Trace output that stays readable after the tracer is attached
+#[derive(Clone, Default)]
+struct Output(Arc<Mutex<Vec<u8>>>);
+
+impl Write for Output {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.lock().unwrap().write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+fn trace(format: TraceFormat, range: RangeInclusive<u16>, ticks: usize) -> Vec<String> {
+    let mut emu = Emu::new();
+    emu.load_rom(&asm::assemble(PROGRAM).unwrap().rom).unwrap();
+    let output = Output::default();
+    emu.set_tracer(Some(Tracer::new(output.clone(), format).with_range(range)));
+    for _ in 0..ticks {
+        emu.tick().unwrap();
+    }
+    let text = String::from_utf8(output.0.lock().unwrap().clone()).unwrap();
+    text.lines().map(str::to_string).collect()
+}
+
+#[test]
+fn delta_lines_show_changed_registers() {
+    assert_eq!(
+        trace(TraceFormat::Delta, 0..=u16::MAX, 5),
+        [
+            "0200  600C  LD V0, 0x0C        V0=0C",
+            "0202  6108  LD V1, 0x08        V1=08",
+            "0204  A208  LD I, 0x208        I=0208",
+            "0206  1206  JP 0x206",
+            "0206  1206  JP 0x206",
+        ]
+    );
+}
+
+#[test]
+fn full_lines_show_every_register_before_the_instruction() {
+    let lines = trace(TraceFormat::Full, 0..=u16::MAX, 3);
+    let zeros =
+        " V2:00 V3:00 V4:00 V5:00 V6:00 V7:00 V8:00 V9:00 VA:00 VB:00 VC:00 VD:00 VE:00 VF:00";
+    assert_eq!(
+        lines,
+        [
+            format!("0200 600C V0:00 V1:00{} I:0000 SP:0 DT:00 ST:00", zeros),
+            format!("0202 6108 V0:0C V1:00{} I:0000 SP:0 DT:00 ST:00", zeros),
+            format!("0204 A208 V0:0C V1:08{} I:0000 SP:0 DT:00 ST:00", zeros),
+        ]
+    );
+}
+
+#[test]
+fn only_addresses_in_range_are_traced() {
+    let lines = trace(TraceFormat::Delta, 0x202..=0x204, 5);
+    assert_eq!(lines.len(), 2);
+    assert!(lines[0].starts_with("0202"));
+    assert!(lines[1].starts_with("0204"));
+}
+
+#[test]
+fn ranges_are_parsed_and_checked() {
+    assert_eq!(parse_range("0x200-0x2FF"), Ok(0x200..=0x2FF));
+    assert_eq!(parse_range("200 - 2ff"), Ok(0x200..=0x2FF));
+    assert_eq!(parse_range("0x300-0x300"), Ok(0x300..=0x300));
+    assert!(parse_range("0x300-0x200").is_err());
+    assert!(parse_range("0x200").is_err());
+    assert!(parse_range("0x200-0xG00").is_err());
+    assert!(parse_range("0x200-0x10000").is_err());
+}
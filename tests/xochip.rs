@@ -0,0 +1,184 @@
+// tests/xochip.rs
+//! XO-CHIP opcodes of `chip8_emu::Emu`: the 64K address space, register
+//! ranges, bitplanes and the audio pattern.
+use chip8_emu::constants::{DEFAULT_AUDIO_PATTERN, XO_RAM_SIZE};
+use chip8_emu::{Chip8Error, Emu, QuirksProfile};
+
+// An XO-CHIP machine with `words` at 0x200, then `data` right after them
+fn load(words: &[u16], data: &[u8]) -> Emu {
+    let mut rom: Vec<u8> = words.iter().flat_map(|w| w.to_be_bytes()).collect();
+    rom.extend_from_slice(data);
+    let mut emu = Emu::with_quirks(QuirksProfile::XoChip.quirks());
+    emu.load_rom(&rom).unwrap();
+    emu
+}
+
+fn run(emu: &mut Emu, instructions: usize) {
+    for _ in 0..instructions {
+        emu.tick().unwrap();
+    }
+}
+
+#[test]
+fn f000_loads_a_16_bit_index() {
+    let mut emu = load(&[0xF000, 0xBEEF, 0x6005], &[]);
+    run(&mut emu, 1);
+    assert_eq!(emu.i_reg(), 0xBEEF);
+    assert_eq!(emu.pc(), 0x204);
+    run(&mut emu, 1);
+    assert_eq!(emu.v_regs()[0], 5);
+
+    // Outside XO-CHIP it is not an instruction
+    let mut emu = Emu::with_quirks(QuirksProfile::SuperChip.quirks());
+    emu.load_rom(&[0xF0, 0x00, 0xBE, 0xEF]).unwrap();
+    assert_eq!(
+        emu.tick(),
+        Err(Chip8Error::UnknownOpcode {
+            opcode: 0xF000,
+            pc: 0x200
+        })
+    );
+}
+
+#[test]
+fn skips_step_over_all_four_bytes_of_f000() {
+    // 3XNN, 4XNN, 5XY0, 9XY0, EX9E and EXA1, each taken
+    let skips = [0x3000, 0x4001, 0x5010, 0x9020, 0xE39E, 0xE4A1];
+    for skip in skips {
+        let mut emu = load(&[0x6201, 0x6300, 0x6405, skip, 0xF000, 0x1234, 0x6507], &[]);
+        emu.keypress(0, true);
+        run(&mut emu, 4);
+        assert_eq!(emu.pc(), 0x20C, "{:04X}", skip);
+        run(&mut emu, 1);
+        assert_eq!(emu.v_regs()[5], 7, "{:04X}", skip);
+        assert_eq!(emu.i_reg(), 0, "{:04X}", skip);
+    }
+}
+
+#[test]
+fn register_ranges_save_and_load_in_either_order() {
+    // v1..v3 := 1, 2, 3; I := data
+    let setup = [0x6101, 0x6202, 0x6303, 0xA220];
+    let data = 0x220;
+
+    // 5132: save v1-v3
+    let mut emu = load(&[setup[0], setup[1], setup[2], setup[3], 0x5132], &[]);
+    run(&mut emu, 5);
+    assert_eq!(emu.ram()[data..data + 4], [1, 2, 3, 0]);
+    assert_eq!(emu.i_reg(), data as u16);
+
+    // 5312: save v3 down to v1
+    let mut emu = load(&[setup[0], setup[1], setup[2], setup[3], 0x5312], &[]);
+    run(&mut emu, 5);
+    assert_eq!(emu.ram()[data..data + 3], [3, 2, 1]);
+
+    // 5573 and 5753: load v5-v7 from 9, 8, 7, then in reverse
+    let mut rom = vec![0xA206, 0x5573, 0x1204];
+    let bytes = [9, 8, 7];
+    let mut emu = load(&rom, &bytes);
+    run(&mut emu, 2);
+    assert_eq!(emu.v_regs()[5..8], [9, 8, 7]);
+    rom[1] = 0x5753;
+    let mut emu = load(&rom, &bytes);
+    run(&mut emu, 2);
+    assert_eq!(emu.v_regs()[5..8], [7, 8, 9]);
+}
+
+// Colour of the pixel at (x, 0)
+fn pixel(emu: &Emu, x: usize) -> u8 {
+    emu.get_display()[x]
+}
+
+#[test]
+fn planes_are_drawn_cleared_and_scrolled_separately() {
+    // Sprite data at 0x240: one row for a single plane, two for both
+    let program = [
+        0xA240, // I := dot
+        0xF101, // plane 1
+        0xD001, // dot at (0, 0)
+        0xF201, // plane 2
+        0x6108, // v1 := 8
+        0xD101, // dot at (8, 0)
+        0x6110, // v1 := 16
+        0xD101, // dot at (16, 0)
+        0xF301, // plane 3
+        0x6118, // v1 := 24
+        0xD101, // (24, 0): plane 1 from the first row, plane 2 from the second
+        0xF201, // plane 2
+        0x00FB, // scroll right, plane 2 only
+        0xF101, // plane 1
+        0x00E0, // clear plane 1 only
+    ];
+    let mut data = vec![0; 0x240 - 0x200 - program.len() * 2];
+    data.extend([0x80, 0x80]);
+    let mut emu = load(&program, &data);
+
+    run(&mut emu, 3);
+    assert_eq!(pixel(&emu, 0), 1);
+    run(&mut emu, 3);
+    assert_eq!(pixel(&emu, 8), 2);
+    run(&mut emu, 2);
+    assert_eq!(pixel(&emu, 16), 2);
+    run(&mut emu, 3);
+    assert_eq!(pixel(&emu, 24), 3);
+    // Colours 0-3
+    let colors: Vec<u8> = [0, 1, 8, 24].iter().map(|&x| pixel(&emu, x)).collect();
+    assert_eq!(colors, [1, 0, 2, 3]);
+
+    // Plane 2 moves 4 to the right, plane 1 stays
+    run(&mut emu, 2);
+    let lit: Vec<(usize, u8)> = (0..64)
+        .filter(|&x| pixel(&emu, x) != 0)
+        .map(|x| (x, pixel(&emu, x)))
+        .collect();
+    assert_eq!(lit, [(0, 1), (12, 2), (20, 2), (24, 1), (28, 2)]);
+
+    // Clearing plane 1 leaves plane 2
+    run(&mut emu, 2);
+    let lit: Vec<(usize, u8)> = (0..64)
+        .filter(|&x| pixel(&emu, x) != 0)
+        .map(|x| (x, pixel(&emu, x)))
+        .collect();
+    assert_eq!(lit, [(12, 2), (20, 2), (28, 2)]);
+}
+
+#[test]
+fn audio_pattern_and_pitch() {
+    let pattern: Vec<u8> = (0..16).map(|n| n * 17).collect();
+    // I := pattern, audio, v0 := 112, pitch := v0
+    let mut emu = load(&[0xA20A, 0xF002, 0x6070, 0xF03A, 0x1208], &pattern);
+    assert_eq!(emu.audio_pattern(), &DEFAULT_AUDIO_PATTERN);
+    assert_eq!(emu.audio_playback_rate(), 4000.0);
+
+    run(&mut emu, 2);
+    assert_eq!(emu.audio_pattern()[..], pattern[..]);
+    run(&mut emu, 2);
+    // 48 steps above the default pitch is an octave up
+    assert!((emu.audio_playback_rate() - 8000.0).abs() < 0.01);
+}
+
+#[test]
+fn memory_above_4k_is_addressable() {
+    assert_eq!(load(&[], &[]).ram().len(), XO_RAM_SIZE);
+
+    // I := 0xF123, save v0-v1, clear them and load them back
+    let program = [
+        0x602A, 0x6155, 0xF000, 0xF123, 0xF155, 0x6000, 0x6100, 0xF000, 0xF123, 0xF165,
+    ];
+    let mut emu = load(&program, &[]);
+    run(&mut emu, 4);
+    assert_eq!(emu.ram()[0xF123..0xF125], [0x2A, 0x55]);
+    run(&mut emu, 4);
+    assert_eq!(emu.v_regs()[..2], [0x2A, 0x55]);
+
+    // Past the end of 64K is still an error
+    let mut emu = load(&[0xF000, 0xFFFF, 0xF155], &[]);
+    run(&mut emu, 1);
+    assert_eq!(
+        emu.tick(),
+        Err(Chip8Error::MemoryOutOfBounds {
+            addr: 0x10000,
+            pc: 0x204
+        })
+    );
+}